@@ -0,0 +1,199 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::download::DownloadedAsset;
+
+/// Name of the index of a bundle. It is a release manifest, written
+/// unsigned: a bundle directory only works as a `file://` release source
+/// with signature checks off, or once the index is signed as
+/// `manifest.json.minisig` by a trusted key.
+pub const INDEX_FILE: &str = "manifest.json";
+
+/// Name of the checksums file of a bundle, as published with each release.
+pub const CHECKSUMS_FILE: &str = "checksums-sha256.txt";
+
+/// A release archive copied into a bundle.
+#[derive(Debug)]
+pub struct BundledAsset {
+    pub target: &'static str,
+    pub name: String,
+    /// SHA-256 of the archive, hex encoded.
+    pub sha256: String,
+}
+
+/// Whether a bundle written to `output` should be a gzipped tarball rather
+/// than a directory.
+pub fn is_tarball(output: &Path) -> bool {
+    let name = output.to_string_lossy().to_ascii_lowercase();
+    [".tar.gz", ".tgz"]
+        .into_iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// Copy a verified `asset` to `path`.
+pub fn save_asset(asset: &mut DownloadedAsset, path: &Path) -> Result<(), String> {
+    let mut file =
+        File::create(path).map_err(|e| format!("failed to create {}: {e}", path.display()))?;
+    io::copy(&mut asset.file, &mut file)
+        .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    Ok(())
+}
+
+/// Write a checksums file listing `assets`, for sources that publish the
+/// hash of each asset instead of a checksums file.
+pub fn write_checksums(dir: &Path, assets: &[BundledAsset]) -> Result<(), String> {
+    let lines: Vec<_> = assets
+        .iter()
+        .map(|asset| format!("{}  {}\n", asset.sha256, asset.name))
+        .collect();
+    write_file(&dir.join(CHECKSUMS_FILE), &lines.concat())
+}
+
+/// Write the index of a bundle holding `assets` of release `tag`, and the
+/// SLSA provenance file named `provenance` when there is one.
+pub fn write_index(
+    dir: &Path,
+    tag: &str,
+    assets: &[BundledAsset],
+    provenance: Option<&str>,
+) -> Result<(), String> {
+    let assets: Map<String, Value> = assets
+        .iter()
+        .map(|asset| {
+            let entry = json!({ "url": asset.name, "sha256": asset.sha256 });
+            (asset.target.to_string(), entry)
+        })
+        .collect();
+    let mut version = json!({
+        "version": tag,
+        "prerelease": tag.contains('-'),
+        "assets": assets,
+    });
+    if let (Some(provenance), Some(version)) = (provenance, version.as_object_mut()) {
+        version.insert("provenance".to_string(), provenance.into());
+    }
+    let index = json!({ "versions": [version] });
+
+    let text = serde_json::to_string_pretty(&index)
+        .map_err(|e| format!("failed to serialize bundle index: {e}"))?;
+    write_file(&dir.join(INDEX_FILE), &format!("{text}\n"))
+}
+
+/// Pack the bundle in `dir` into a gzipped tarball at `output`, under a
+/// top-level directory named `root`.
+pub fn write_tarball(dir: &Path, output: &Path, root: &str) -> Result<(), String> {
+    let file = File::create(output)
+        .map_err(|e| format!("failed to create {}: {e}", output.display()))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+
+    let failed = |e: io::Error| format!("failed to write {}: {e}", output.display());
+    tar.append_dir_all(root, dir).map_err(failed)?;
+    tar.into_inner().map_err(failed)?.finish().map_err(failed)?;
+    Ok(())
+}
+
+/// Write `contents` to the file at `path`.
+pub fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+    use crate::digest::{parse_checksum, Digest};
+    use crate::manifest::ManifestSource;
+    use crate::platform::Platform;
+    use crate::retry::{Retrier, RetryPolicy};
+    use crate::source::{Checksum, ReleaseSource};
+    use std::io::Read;
+
+    const LINUX: Platform = Platform {
+        target: "x86_64-unknown-linux-gnu",
+        archive_ext: ".tar.gz",
+    };
+
+    const SHA256: &str = "c40ca88d580de8fa32db7d997b37f971a9a0a050d1b79a12aaf409a2191a32ca";
+
+    fn linux_asset() -> BundledAsset {
+        BundledAsset {
+            target: LINUX.target,
+            name: "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+            sha256: SHA256.to_string(),
+        }
+    }
+
+    #[test]
+    fn index_is_a_release_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        write_index(dir.path(), "v0.4.0", &[linux_asset()], Some("multiple.intoto.jsonl")).unwrap();
+
+        let url = format!("file://{}/{INDEX_FILE}", dir.path().display());
+        let client = reqwest::blocking::Client::new();
+        let source = ManifestSource::new(client, Retrier::quiet(RetryPolicy::none()), url, None);
+        assert_eq!(source.resolve_version(None, false).unwrap().tag, "v0.4.0");
+
+        let info = source.release_info("v0.4.0", &LINUX).unwrap();
+        assert_eq!(
+            info.asset_url,
+            format!(
+                "file://{}/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                dir.path().display()
+            )
+        );
+        assert_eq!(info.checksum, Checksum::Digest(Digest::sha256(SHA256)));
+        assert_eq!(
+            info.provenance,
+            Some(format!("file://{}/multiple.intoto.jsonl", dir.path().display()))
+        );
+    }
+
+    #[test]
+    fn checksums_list_every_asset() {
+        let dir = tempfile::tempdir().unwrap();
+        write_checksums(dir.path(), &[linux_asset()]).unwrap();
+
+        let text = fs::read_to_string(dir.path().join(CHECKSUMS_FILE)).unwrap();
+        let digest = parse_checksum(&text, &linux_asset().name, None).unwrap();
+        assert_eq!(digest, Digest::sha256(SHA256));
+    }
+
+    #[test]
+    fn tarball_holds_bundle_under_root() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(&dir.path().join(INDEX_FILE), "{}").unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let output = out.path().join("bundle.tar.gz");
+
+        write_tarball(dir.path(), &output, "centy-daemon-v0.4.0").unwrap();
+
+        let decoder = flate2::read::GzDecoder::new(File::open(&output).unwrap());
+        let mut archive = tar::Archive::new(decoder);
+        let mut entry = archive
+            .entries()
+            .unwrap()
+            .map(Result::unwrap)
+            .find(|entry| entry.path().unwrap().ends_with(INDEX_FILE))
+            .unwrap();
+        assert!(entry.path().unwrap().starts_with("centy-daemon-v0.4.0"));
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "{}");
+    }
+
+    #[test]
+    fn tarball_output_is_detected_by_extension() {
+        assert!(is_tarball(Path::new("bundle.tar.gz")));
+        assert!(is_tarball(Path::new("out/bundle.TGZ")));
+        assert!(!is_tarball(Path::new("bundle")));
+    }
+}
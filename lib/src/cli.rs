@@ -0,0 +1,575 @@
+pub const USAGE: &str = "\
+Usage: centy-installer [COMMAND] [OPTIONS]
+
+Commands:
+  install [VERSION]  Install centy-daemon (default when no command is given)
+  use <VERSION>      Switch to an already installed version
+  uninstall          Remove centy-daemon and all installed versions
+  list               List installed versions (* marks the active one)
+  status             Show installation and daemon status
+  check              Check for the latest available version
+  list-remote        List available versions (* active, + installed)
+  changelog [FROM] [TO]
+                     Show release notes from FROM (default: active version)
+                     to TO (default: latest release)
+  bundle [VERSION]   Download release archives for offline installs
+  help               Print this help
+
+Options:
+  --pre              Allow pre-release versions (install, check, list-remote)
+  --no-restart       Do not restart a running daemon (install, use)
+  --force            Reinstall even if the version is already installed (install)
+  --notes            Print release notes since the previous version (install)
+  --from-file <PATH> Install from a downloaded release archive (install)
+  --checksums <PATH> Checksums file for --from-file (default: checksums-*.txt next to it)
+  --target <TRIPLE>  Target to bundle, repeatable (bundle; default: all targets)
+  --output <PATH>    Bundle directory, or a .tar.gz file (bundle; default: centy-daemon-bundle)
+  -h, --help         Print this help
+  -V, --version      Print the installer version";
+
+/// A parsed command line.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Install {
+        version: Option<String>,
+        prerelease: bool,
+        restart: bool,
+        force: bool,
+        notes: bool,
+    },
+    /// `install --from-file`: install from an archive on disk.
+    InstallFile {
+        archive: String,
+        checksums: Option<String>,
+        version: Option<String>,
+        restart: bool,
+        force: bool,
+    },
+    Use {
+        version: String,
+        restart: bool,
+    },
+    Uninstall,
+    List,
+    Status,
+    Check {
+        prerelease: bool,
+    },
+    ListRemote {
+        prerelease: bool,
+    },
+    Changelog {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    Bundle {
+        version: Option<String>,
+        prerelease: bool,
+        targets: Vec<String>,
+        output: String,
+    },
+    Help,
+    Version,
+}
+
+/// Parse the command-line arguments (excluding the program name).
+///
+/// When the first argument is not a command name, `install` is assumed so that
+/// existing invocations such as `centy-installer --pre` keep working.
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let (name, rest) = match args.split_first() {
+        Some((first, rest)) if !first.starts_with('-') => (first.as_str(), rest),
+        _ => ("install", args),
+    };
+
+    if rest.iter().any(|a| a == "-h" || a == "--help") {
+        return Ok(Command::Help);
+    }
+    if rest.iter().any(|a| a == "-V" || a == "--version") {
+        return Ok(Command::Version);
+    }
+
+    match name {
+        "install" => parse_install(rest),
+        "use" => parse_use(rest),
+        "uninstall" => no_arguments(name, rest).map(|()| Command::Uninstall),
+        "list" => no_arguments(name, rest).map(|()| Command::List),
+        "status" => no_arguments(name, rest).map(|()| Command::Status),
+        "check" => {
+            parse_prerelease_flag(name, rest).map(|prerelease| Command::Check { prerelease })
+        }
+        "list-remote" => {
+            parse_prerelease_flag(name, rest).map(|prerelease| Command::ListRemote { prerelease })
+        }
+        "changelog" => parse_changelog(rest),
+        "bundle" => parse_bundle(rest),
+        "help" => no_arguments(name, rest).map(|()| Command::Help),
+        other => Err(format!("unknown command: {other}")),
+    }
+}
+
+fn parse_install(args: &[String]) -> Result<Command, String> {
+    let mut version = None;
+    let mut prerelease = false;
+    let mut restart = true;
+    let mut force = false;
+    let mut notes = false;
+    let mut archive = None;
+    let mut checksums = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pre" => prerelease = true,
+            "--no-restart" => restart = false,
+            "--force" => force = true,
+            "--notes" => notes = true,
+            flag @ ("--from-file" | "--checksums") => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {flag}"))?
+                    .clone();
+                if flag == "--from-file" {
+                    archive = Some(value);
+                } else {
+                    checksums = Some(value);
+                }
+            }
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option for install: {flag}"));
+            }
+            value => {
+                if version.is_some() {
+                    return Err(format!("unexpected argument for install: {value}"));
+                }
+                version = Some(value.to_string());
+            }
+        }
+    }
+
+    let Some(archive) = archive else {
+        if checksums.is_some() {
+            return Err("--checksums requires --from-file".to_string());
+        }
+        return Ok(Command::Install {
+            version,
+            prerelease,
+            restart,
+            force,
+            notes,
+        });
+    };
+    if prerelease {
+        return Err("--pre cannot be used with --from-file".to_string());
+    }
+    if notes {
+        return Err("--notes cannot be used with --from-file".to_string());
+    }
+
+    Ok(Command::InstallFile {
+        archive,
+        checksums,
+        version,
+        restart,
+        force,
+    })
+}
+
+fn parse_use(args: &[String]) -> Result<Command, String> {
+    let mut version = None;
+    let mut restart = true;
+
+    for arg in args {
+        match arg.as_str() {
+            "--no-restart" => restart = false,
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option for use: {flag}"));
+            }
+            value => {
+                if version.is_some() {
+                    return Err(format!("unexpected argument for use: {value}"));
+                }
+                version = Some(value.to_string());
+            }
+        }
+    }
+
+    let version = version.ok_or("missing version for use")?;
+
+    Ok(Command::Use { version, restart })
+}
+
+/// Parse the arguments of a command whose only option is `--pre`.
+fn parse_prerelease_flag(command: &str, args: &[String]) -> Result<bool, String> {
+    let mut prerelease = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--pre" => prerelease = true,
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option for {command}: {flag}"));
+            }
+            value => return Err(format!("unexpected argument for {command}: {value}")),
+        }
+    }
+
+    Ok(prerelease)
+}
+
+fn parse_changelog(args: &[String]) -> Result<Command, String> {
+    let mut versions = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option for changelog: {flag}"));
+            }
+            value if versions.len() == 2 => {
+                return Err(format!("unexpected argument for changelog: {value}"));
+            }
+            value => versions.push(value.to_string()),
+        }
+    }
+
+    let mut versions = versions.into_iter();
+    Ok(Command::Changelog {
+        from: versions.next(),
+        to: versions.next(),
+    })
+}
+
+fn parse_bundle(args: &[String]) -> Result<Command, String> {
+    let mut version = None;
+    let mut prerelease = false;
+    let mut targets = Vec::new();
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pre" => prerelease = true,
+            flag @ ("--target" | "--output") => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {flag}"))?
+                    .clone();
+                if flag == "--target" {
+                    targets.push(value);
+                } else {
+                    output = Some(value);
+                }
+            }
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option for bundle: {flag}"));
+            }
+            value => {
+                if version.is_some() {
+                    return Err(format!("unexpected argument for bundle: {value}"));
+                }
+                version = Some(value.to_string());
+            }
+        }
+    }
+
+    Ok(Command::Bundle {
+        version,
+        prerelease,
+        targets,
+        output: output.unwrap_or_else(|| "centy-daemon-bundle".to_string()),
+    })
+}
+
+fn no_arguments(command: &str, args: &[String]) -> Result<(), String> {
+    match args.first() {
+        None => Ok(()),
+        Some(flag) if flag.starts_with('-') => Err(format!("unknown option for {command}: {flag}")),
+        Some(value) => Err(format!("unexpected argument for {command}: {value}")),
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn no_arguments_defaults_to_install() {
+        let cmd = parse(&[]).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Install {
+                version: None,
+                prerelease: false,
+                restart: true,
+                force: false,
+                notes: false,
+            }
+        );
+    }
+
+    #[test]
+    fn bare_flags_apply_to_install() {
+        let cmd = parse(&args(&["--pre", "--no-restart"])).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Install {
+                version: None,
+                prerelease: true,
+                restart: false,
+                force: false,
+                notes: false,
+            }
+        );
+    }
+
+    #[test]
+    fn install_with_version() {
+        let cmd = parse(&args(&["install", "0.1.6"])).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Install {
+                version: Some("0.1.6".to_string()),
+                prerelease: false,
+                restart: true,
+                force: false,
+                notes: false,
+            }
+        );
+    }
+
+    #[test]
+    fn install_with_force() {
+        let cmd = parse(&args(&["install", "--force"])).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Install {
+                version: None,
+                prerelease: false,
+                restart: true,
+                force: true,
+                notes: false,
+            }
+        );
+    }
+
+    #[test]
+    fn install_with_notes() {
+        let cmd = parse(&args(&["install", "0.5", "--notes"])).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Install {
+                version: Some("0.5".to_string()),
+                prerelease: false,
+                restart: true,
+                force: false,
+                notes: true,
+            }
+        );
+
+        let err = parse(&args(&["--from-file", "a.tar.gz", "--notes"])).unwrap_err();
+        assert!(err.contains("--notes cannot be used with --from-file"));
+    }
+
+    #[test]
+    fn install_rejects_two_versions() {
+        let err = parse(&args(&["install", "0.1.6", "0.1.7"])).unwrap_err();
+        assert!(err.contains("unexpected argument for install: 0.1.7"));
+    }
+
+    #[test]
+    fn install_from_file() {
+        let cmd = parse(&args(&[
+            "install",
+            "--from-file",
+            "/media/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+            "--checksums",
+            "/media/checksums-sha256.txt",
+            "--no-restart",
+        ]))
+        .unwrap();
+        assert_eq!(
+            cmd,
+            Command::InstallFile {
+                archive: "/media/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+                checksums: Some("/media/checksums-sha256.txt".to_string()),
+                version: None,
+                restart: false,
+                force: false,
+            }
+        );
+
+        let cmd = parse(&args(&["--from-file", "daemon.tar.gz", "0.4.0"])).unwrap();
+        assert_eq!(
+            cmd,
+            Command::InstallFile {
+                archive: "daemon.tar.gz".to_string(),
+                checksums: None,
+                version: Some("0.4.0".to_string()),
+                restart: true,
+                force: false,
+            }
+        );
+    }
+
+    #[test]
+    fn install_from_file_rejects_invalid_combinations() {
+        let err = parse(&args(&["install", "--from-file"])).unwrap_err();
+        assert!(err.contains("missing value for --from-file"));
+
+        let err = parse(&args(&["install", "--checksums", "sums.txt"])).unwrap_err();
+        assert!(err.contains("--checksums requires --from-file"));
+
+        let err = parse(&args(&["install", "--from-file", "a.tar.gz", "--pre"])).unwrap_err();
+        assert!(err.contains("--pre cannot be used with --from-file"));
+    }
+
+    #[test]
+    fn unknown_flag_is_rejected() {
+        let err = parse(&args(&["--bogus"])).unwrap_err();
+        assert!(err.contains("unknown option for install: --bogus"));
+    }
+
+    #[test]
+    fn unknown_command_is_rejected() {
+        let err = parse(&args(&["frobnicate"])).unwrap_err();
+        assert!(err.contains("unknown command: frobnicate"));
+    }
+
+    #[test]
+    fn simple_commands_parse() {
+        assert_eq!(parse(&args(&["uninstall"])).unwrap(), Command::Uninstall);
+        assert_eq!(parse(&args(&["list"])).unwrap(), Command::List);
+        assert_eq!(parse(&args(&["status"])).unwrap(), Command::Status);
+        assert_eq!(parse(&args(&["help"])).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn simple_commands_reject_arguments() {
+        let err = parse(&args(&["status", "--pre"])).unwrap_err();
+        assert!(err.contains("unknown option for status: --pre"));
+
+        let err = parse(&args(&["list", "extra"])).unwrap_err();
+        assert!(err.contains("unexpected argument for list: extra"));
+    }
+
+    #[test]
+    fn use_with_version() {
+        assert_eq!(
+            parse(&args(&["use", "0.4.0", "--no-restart"])).unwrap(),
+            Command::Use {
+                version: "0.4.0".to_string(),
+                restart: false,
+            }
+        );
+    }
+
+    #[test]
+    fn use_requires_version() {
+        let err = parse(&args(&["use"])).unwrap_err();
+        assert!(err.contains("missing version for use"));
+    }
+
+    #[test]
+    fn check_accepts_pre() {
+        assert_eq!(
+            parse(&args(&["check", "--pre"])).unwrap(),
+            Command::Check { prerelease: true }
+        );
+    }
+
+    #[test]
+    fn bundle_with_targets_and_output() {
+        assert_eq!(
+            parse(&args(&[
+                "bundle",
+                "0.4.0",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--target",
+                "aarch64-apple-darwin",
+                "--output",
+                "centy.tar.gz",
+            ]))
+            .unwrap(),
+            Command::Bundle {
+                version: Some("0.4.0".to_string()),
+                prerelease: false,
+                targets: vec![
+                    "x86_64-unknown-linux-gnu".to_string(),
+                    "aarch64-apple-darwin".to_string(),
+                ],
+                output: "centy.tar.gz".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn bundle_defaults() {
+        assert_eq!(
+            parse(&args(&["bundle", "--pre"])).unwrap(),
+            Command::Bundle {
+                version: None,
+                prerelease: true,
+                targets: Vec::new(),
+                output: "centy-daemon-bundle".to_string(),
+            }
+        );
+
+        let err = parse(&args(&["bundle", "--target"])).unwrap_err();
+        assert!(err.contains("missing value for --target"));
+    }
+
+    #[test]
+    fn list_remote_accepts_pre() {
+        assert_eq!(
+            parse(&args(&["list-remote"])).unwrap(),
+            Command::ListRemote { prerelease: false }
+        );
+        assert_eq!(
+            parse(&args(&["list-remote", "--pre"])).unwrap(),
+            Command::ListRemote { prerelease: true }
+        );
+        let err = parse(&args(&["list-remote", "0.4"])).unwrap_err();
+        assert!(err.contains("unexpected argument for list-remote: 0.4"));
+    }
+
+    #[test]
+    fn changelog_takes_optional_range() {
+        assert_eq!(
+            parse(&args(&["changelog"])).unwrap(),
+            Command::Changelog {
+                from: None,
+                to: None,
+            }
+        );
+        assert_eq!(
+            parse(&args(&["changelog", "0.4.0", "0.5.0"])).unwrap(),
+            Command::Changelog {
+                from: Some("0.4.0".to_string()),
+                to: Some("0.5.0".to_string()),
+            }
+        );
+        let err = parse(&args(&["changelog", "0.4.0", "0.5.0", "0.6.0"])).unwrap_err();
+        assert!(err.contains("unexpected argument for changelog: 0.6.0"));
+        let err = parse(&args(&["changelog", "--pre"])).unwrap_err();
+        assert!(err.contains("unknown option for changelog: --pre"));
+    }
+
+    #[test]
+    fn help_and_version_flags() {
+        assert_eq!(parse(&args(&["--help"])).unwrap(), Command::Help);
+        assert_eq!(parse(&args(&["-h"])).unwrap(), Command::Help);
+        assert_eq!(parse(&args(&["install", "--help"])).unwrap(), Command::Help);
+        assert_eq!(parse(&args(&["--version"])).unwrap(), Command::Version);
+        assert_eq!(parse(&args(&["-V"])).unwrap(), Command::Version);
+    }
+}
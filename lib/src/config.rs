@@ -0,0 +1,635 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::digest::DigestAlgorithm;
+use crate::{signature, sigstore};
+
+/// Environment variable overriding [`SourceConfig::api_base`].
+const API_BASE_VAR: &str = "CENTY_INSTALLER_API_BASE";
+/// Environment variable overriding [`SourceConfig::download_base`].
+const DOWNLOAD_BASE_VAR: &str = "CENTY_INSTALLER_DOWNLOAD_BASE";
+/// Environment variable overriding the repository, as `owner/name`.
+const REPO_VAR: &str = "CENTY_INSTALLER_REPO";
+/// Environment variable overriding [`SourceConfig::manifest`].
+const MANIFEST_VAR: &str = "CENTY_INSTALLER_MANIFEST";
+/// Environment variable pointing at a config file other than the default one.
+const CONFIG_VAR: &str = "CENTY_INSTALLER_CONFIG";
+
+/// Where releases are looked up and downloaded from.
+///
+/// Defaults to `centy-io/centy-daemon` on github.com. Point it at GitHub
+/// Enterprise, or at a mirror that serves the same URL layout, to install
+/// without reaching github.com.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceConfig {
+    /// Base URL of the GitHub REST API, e.g. `https://ghe.example.com/api/v3`.
+    pub api_base: String,
+    /// Base URL release assets are downloaded from, followed by
+    /// `/<owner>/<name>/releases/download/<tag>/<asset>`.
+    pub download_base: String,
+    /// Owner of the repository publishing the releases.
+    pub owner: String,
+    /// Name of the repository publishing the releases.
+    pub name: String,
+    /// URL of a static release manifest (`http://`, `https://` or `file://`).
+    /// When set, releases are read from it and the GitHub settings are unused.
+    pub manifest: Option<String>,
+    /// Minisign public keys trusted to sign release checksums, or the release
+    /// manifest. Defaults to the keys embedded in the installer.
+    pub trusted_keys: Vec<String>,
+    /// Whether release checksums and manifests must be signed by one of
+    /// `trusted_keys`. On by default once the installer embeds a release
+    /// signing key, and whenever `trusted_keys` is configured; turning it off
+    /// installs unsigned releases.
+    pub verify_signatures: bool,
+    /// Sigstore trust root, in the format of Sigstore's `trusted_root.json`.
+    /// Defaults to the one embedded in the installer.
+    pub sigstore_root: String,
+    /// Whether release archives must carry a Sigstore bundle that verifies
+    /// against `sigstore_root`. On by default once the embedded trust root
+    /// lists a certificate authority, and whenever `sigstore_root` is
+    /// configured.
+    pub verify_sigstore: bool,
+    /// Prefix of the workflow URI that release signing certificates must be
+    /// issued to. Defaults to the release workflows of centy-daemon on
+    /// github.com, whichever repository releases are read from.
+    pub sigstore_identity: Option<String>,
+    /// SLSA provenance that installed archives must be attested by. `None`,
+    /// the default, skips the provenance check.
+    pub provenance: Option<ProvenancePolicy>,
+    /// Algorithm of release checksums files. When unset, it is told from the
+    /// name of the checksums file, such as `checksums-sha512.txt`.
+    pub checksum_algorithm: Option<DigestAlgorithm>,
+    /// Hosts of GitHub Enterprise APIs trusted with the `GITHUB_TOKEN` or
+    /// `GH_TOKEN` token from the environment. It is only ever sent over
+    /// HTTPS, to `api.github.com` or one of these hosts.
+    pub token_hosts: Vec<String>,
+}
+
+/// What the SLSA provenance of a release must attest for its archives to be
+/// installed: that they were built by `builder` from `repository`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProvenancePolicy {
+    /// Builder ID the provenance must name, with or without an `@<ref>`
+    /// suffix. Defaults to the generic generator of slsa-github-generator.
+    pub builder: Option<String>,
+    /// Source repository the archives must be built from. Defaults to
+    /// `https://github.com/centy-io/centy-daemon`, whichever repository
+    /// releases are read from.
+    pub repository: Option<String>,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        let trusted_keys = signature::release_keys();
+        let sigstore_root = sigstore::embedded_root();
+        Self {
+            api_base: "https://api.github.com".to_string(),
+            download_base: "https://github.com".to_string(),
+            owner: "centy-io".to_string(),
+            name: "centy-daemon".to_string(),
+            manifest: None,
+            verify_signatures: !trusted_keys.is_empty(),
+            trusted_keys,
+            verify_sigstore: sigstore::TrustedRoot::parse(&sigstore_root)
+                .is_ok_and(|root| !root.is_empty()),
+            sigstore_root,
+            sigstore_identity: None,
+            provenance: None,
+            checksum_algorithm: None,
+            token_hosts: Vec::new(),
+        }
+    }
+}
+
+impl SourceConfig {
+    /// Set the repository from an `owner/name` string.
+    pub fn set_repo(&mut self, repo: &str) -> Result<(), String> {
+        let (owner, name) = repo
+            .trim()
+            .split_once('/')
+            .filter(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+            .ok_or_else(|| format!("invalid repository \"{repo}\", expected owner/name"))?;
+        self.owner = owner.to_string();
+        self.name = name.to_string();
+        Ok(())
+    }
+
+    /// Keys release signatures are checked against, or `None` when
+    /// `verify_signatures` is off.
+    #[must_use]
+    pub fn signing_keys(&self) -> Option<&[String]> {
+        self.verify_signatures.then_some(self.trusted_keys.as_slice())
+    }
+
+    /// Prefix of the workflow URI that release signing certificates must be
+    /// issued to: `sigstore_identity`, or the workflows of centy-daemon on
+    /// github.com.
+    ///
+    /// It is never derived from `owner` and `name`, so that pointing the
+    /// release source elsewhere does not change whose signatures are trusted.
+    #[must_use]
+    pub fn sigstore_workflow(&self) -> &str {
+        self.sigstore_identity.as_deref().unwrap_or(sigstore::RELEASE_WORKFLOWS)
+    }
+}
+
+/// Return the default path of the installer config file.
+pub fn config_path_in(home_dir: &Path) -> PathBuf {
+    home_dir.join(".centy").join("installer.json")
+}
+
+/// Load the release source from the config file and the environment.
+///
+/// The config file is `$CENTY_INSTALLER_CONFIG` or `~/.centy/installer.json`,
+/// a JSON object with optional `api_base`, `download_base`, `repo`
+/// (`owner/name`), `manifest`, `trusted_keys`, `verify_signatures`,
+/// `sigstore_trusted_root`, `verify_sigstore`, `sigstore_identity`,
+/// `provenance`, `checksum_algorithm` and `token_hosts` keys. `CENTY_INSTALLER_API_BASE`,
+/// `CENTY_INSTALLER_DOWNLOAD_BASE`, `CENTY_INSTALLER_REPO` and
+/// `CENTY_INSTALLER_MANIFEST` override it.
+pub fn load_source_in(home_dir: &Path) -> Result<SourceConfig, String> {
+    let mut source = SourceConfig::default();
+
+    let path = env_var(CONFIG_VAR).map_or_else(|| config_path_in(home_dir), PathBuf::from);
+    apply_config_file(&mut source, &path)?;
+
+    apply_settings(
+        &mut source,
+        Settings {
+            api_base: env_var(API_BASE_VAR),
+            download_base: env_var(DOWNLOAD_BASE_VAR),
+            repo: env_var(REPO_VAR),
+            manifest: env_var(MANIFEST_VAR),
+        },
+    )
+    .map_err(|e| format!("{e} (from environment)"))?;
+
+    Ok(source)
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Apply the settings of the config file at `path`, if it exists.
+fn apply_config_file(source: &mut SourceConfig, path: &Path) -> Result<(), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+    };
+
+    let value: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+    let field = |name: &str| -> Result<Option<String>, String> {
+        match value.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(format!("\"{name}\" must be a string in {}", path.display())),
+        }
+    };
+    let flag = |name: &str| -> Result<Option<bool>, String> {
+        match value.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Bool(b)) => Ok(Some(*b)),
+            Some(_) => Err(format!("\"{name}\" must be a boolean in {}", path.display())),
+        }
+    };
+
+    apply_settings(
+        source,
+        Settings {
+            api_base: field("api_base")?,
+            download_base: field("download_base")?,
+            repo: field("repo")?,
+            manifest: field("manifest")?,
+        },
+    )
+    .map_err(|e| format!("{e} (in {})", path.display()))?;
+
+    if let Some(keys) = value.get("trusted_keys").filter(|keys| !keys.is_null()) {
+        source.trusted_keys = trusted_keys(keys).map_err(|e| format!("{e} (in {})", path.display()))?;
+        source.verify_signatures = true;
+    }
+    if let Some(verify) = flag("verify_signatures")? {
+        source.verify_signatures = verify;
+    }
+    if let Some(root) = field("sigstore_trusted_root")? {
+        // Relative to the directory of the config file
+        let root = path.parent().unwrap_or(path).join(root.trim());
+        source.sigstore_root =
+            sigstore_root(&root).map_err(|e| format!("{e} (in {})", path.display()))?;
+        source.verify_sigstore = true;
+    }
+    if let Some(verify) = flag("verify_sigstore")? {
+        source.verify_sigstore = verify;
+    }
+    if let Some(identity) = field("sigstore_identity")? {
+        let identity = base_url(&identity).map_err(|e| format!("{e} (in {})", path.display()))?;
+        source.sigstore_identity = Some(format!("{identity}/"));
+    }
+    if let Some(algorithm) = field("checksum_algorithm")? {
+        let algorithm = DigestAlgorithm::from_name(&algorithm).ok_or_else(|| {
+            format!(
+                "unknown checksum algorithm \"{algorithm}\", expected sha256, sha512 or \
+                 blake3 (in {})",
+                path.display()
+            )
+        })?;
+        source.checksum_algorithm = Some(algorithm);
+    }
+    if let Some(policy) = value.get("provenance") {
+        source.provenance =
+            provenance_policy(policy).map_err(|e| format!("{e} (in {})", path.display()))?;
+    }
+    if let Some(hosts) = value.get("token_hosts").filter(|hosts| !hosts.is_null()) {
+        source.token_hosts =
+            token_hosts(hosts).map_err(|e| format!("{e} (in {})", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Parse the `token_hosts` setting, an array of host names of GitHub
+/// Enterprise APIs trusted with the GitHub token from the environment.
+fn token_hosts(value: &Value) -> Result<Vec<String>, String> {
+    value
+        .as_array()
+        .ok_or("\"token_hosts\" must be an array of host names")?
+        .iter()
+        .map(|host| {
+            host.as_str()
+                .map(|host| host.trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty() && !host.contains(['/', ':', '@']))
+                .ok_or_else(|| "\"token_hosts\" must be an array of host names".to_string())
+        })
+        .collect()
+}
+
+/// Parse the `provenance` setting: `true` to require SLSA provenance with
+/// the default expectations, or an object with optional `builder` and
+/// `repository` keys to require it with those.
+fn provenance_policy(value: &Value) -> Result<Option<ProvenancePolicy>, String> {
+    let settings = match value {
+        Value::Null => return Ok(None),
+        Value::Bool(required) => return Ok(required.then(ProvenancePolicy::default)),
+        Value::Object(settings) => settings,
+        _ => return Err("\"provenance\" must be true, false or an object".to_string()),
+    };
+    let field = |name: &str| match settings.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.trim().trim_end_matches('/').to_string())),
+        Some(_) => Err(format!("\"provenance.{name}\" must be a string")),
+    };
+    Ok(Some(ProvenancePolicy {
+        builder: field("builder")?,
+        repository: field("repository")?,
+    }))
+}
+
+/// Read the `sigstore_trusted_root` setting, the path of a Sigstore
+/// `trusted_root.json` that replaces the one embedded in the installer.
+fn sigstore_root(path: &Path) -> Result<String, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read Sigstore trusted root {}: {e}", path.display()))?;
+    sigstore::TrustedRoot::parse(&text)?;
+    Ok(text)
+}
+
+/// Parse the `trusted_keys` setting, an array of minisign public keys that
+/// replaces the keys embedded in the installer.
+fn trusted_keys(value: &Value) -> Result<Vec<String>, String> {
+    let keys = value
+        .as_array()
+        .ok_or("\"trusted_keys\" must be an array of strings")?
+        .iter()
+        .map(|key| {
+            let key = key
+                .as_str()
+                .ok_or("\"trusted_keys\" must be an array of strings")?
+                .trim();
+            signature::check_key(key)?;
+            Ok(key.to_string())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if keys.is_empty() {
+        return Err("\"trusted_keys\" must list at least one key".to_string());
+    }
+    Ok(keys)
+}
+
+/// Settings read from one place, each overriding the current value when set.
+struct Settings {
+    api_base: Option<String>,
+    download_base: Option<String>,
+    repo: Option<String>,
+    manifest: Option<String>,
+}
+
+fn apply_settings(source: &mut SourceConfig, settings: Settings) -> Result<(), String> {
+    if let Some(api_base) = settings.api_base {
+        source.api_base = base_url(&api_base)?;
+    }
+    if let Some(download_base) = settings.download_base {
+        source.download_base = base_url(&download_base)?;
+    }
+    if let Some(repo) = settings.repo {
+        source.set_repo(&repo)?;
+    }
+    if let Some(manifest) = settings.manifest {
+        let manifest = manifest.trim();
+        if !manifest.starts_with("file://") {
+            base_url(manifest)?;
+        }
+        source.manifest = Some(manifest.to_string());
+    }
+    Ok(())
+}
+
+/// Validate a base URL and strip its trailing slashes.
+fn base_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(url.to_string())
+    } else {
+        Err(format!("invalid URL \"{url}\", expected http:// or https://"))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file_overrides_defaults() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        fs::write(
+            &path,
+            r#"{
+                "api_base": "https://ghe.example.com/api/v3/",
+                "download_base": "https://mirror.example.com/releases",
+                "repo": "acme/centy-daemon-fork"
+            }"#,
+        )
+        .unwrap();
+
+        let mut source = SourceConfig::default();
+        apply_config_file(&mut source, &path).unwrap();
+
+        assert_eq!(
+            source,
+            SourceConfig {
+                api_base: "https://ghe.example.com/api/v3".to_string(),
+                download_base: "https://mirror.example.com/releases".to_string(),
+                owner: "acme".to_string(),
+                name: "centy-daemon-fork".to_string(),
+                manifest: None,
+                trusted_keys: signature::release_keys(),
+                verify_signatures: !signature::release_keys().is_empty(),
+                sigstore_root: sigstore::embedded_root(),
+                verify_sigstore: SourceConfig::default().verify_sigstore,
+                sigstore_identity: None,
+                provenance: None,
+                checksum_algorithm: None,
+                token_hosts: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn config_file_partial_keeps_other_defaults() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        fs::write(&path, r#"{"download_base": "http://10.0.0.5:8080"}"#).unwrap();
+
+        let mut source = SourceConfig::default();
+        apply_config_file(&mut source, &path).unwrap();
+
+        assert_eq!(source.download_base, "http://10.0.0.5:8080");
+        assert_eq!(source.api_base, SourceConfig::default().api_base);
+        assert_eq!(source.owner, "centy-io");
+    }
+
+    #[test]
+    fn missing_config_file_is_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut source = SourceConfig::default();
+        apply_config_file(&mut source, &tmp.path().join("missing.json")).unwrap();
+        assert_eq!(source, SourceConfig::default());
+    }
+
+    #[test]
+    fn invalid_config_file_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+
+        fs::write(&path, "not-json").unwrap();
+        let result = apply_config_file(&mut SourceConfig::default(), &path);
+        assert!(result.unwrap_err().contains("failed to parse"));
+
+        fs::write(&path, r#"{"repo": 42}"#).unwrap();
+        let result = apply_config_file(&mut SourceConfig::default(), &path);
+        assert!(result.unwrap_err().contains("\"repo\" must be a string"));
+
+        fs::write(&path, r#"{"api_base": "ftp://example.com"}"#).unwrap();
+        let result = apply_config_file(&mut SourceConfig::default(), &path);
+        assert!(result.unwrap_err().contains("invalid URL"));
+    }
+
+    #[test]
+    fn set_repo_requires_owner_and_name() {
+        let mut source = SourceConfig::default();
+        assert!(source.set_repo("centy-daemon").is_err());
+        assert!(source.set_repo("/centy-daemon").is_err());
+        assert!(source.set_repo("a/b/c").is_err());
+        source.set_repo("acme/daemon").unwrap();
+        assert_eq!((source.owner.as_str(), source.name.as_str()), ("acme", "daemon"));
+    }
+
+    #[test]
+    fn config_file_sets_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        fs::write(&path, r#"{"manifest": "file:///srv/releases/manifest.json"}"#).unwrap();
+
+        let mut source = SourceConfig::default();
+        apply_config_file(&mut source, &path).unwrap();
+        assert_eq!(
+            source.manifest.as_deref(),
+            Some("file:///srv/releases/manifest.json")
+        );
+
+        fs::write(&path, r#"{"manifest": "s3://bucket/manifest.json"}"#).unwrap();
+        let result = apply_config_file(&mut SourceConfig::default(), &path);
+        assert!(result.unwrap_err().contains("invalid URL"));
+    }
+
+    #[test]
+    fn config_file_replaces_trusted_keys() {
+        use crate::signature::tests::{KEY, OTHER_KEY};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        fs::write(&path, format!(r#"{{"trusted_keys": ["{KEY}", " {OTHER_KEY} "]}}"#)).unwrap();
+
+        let mut source = SourceConfig::default();
+        apply_config_file(&mut source, &path).unwrap();
+        assert_eq!(source.trusted_keys, [KEY, OTHER_KEY]);
+
+        for invalid in [r#"{"trusted_keys": "RWQ"}"#, r#"{"trusted_keys": []}"#] {
+            fs::write(&path, invalid).unwrap();
+            assert!(apply_config_file(&mut SourceConfig::default(), &path).is_err());
+        }
+        fs::write(&path, r#"{"trusted_keys": ["RWQ-not-a-key"]}"#).unwrap();
+        let err = apply_config_file(&mut SourceConfig::default(), &path).unwrap_err();
+        assert!(err.contains("invalid minisign public key"), "{err}");
+    }
+
+    #[test]
+    fn config_file_turns_signatures_on_and_off() {
+        use crate::signature::tests::KEY;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        let mut source = SourceConfig {
+            verify_signatures: false,
+            verify_sigstore: true,
+            ..SourceConfig::default()
+        };
+        fs::write(&path, format!(r#"{{"trusted_keys": ["{KEY}"]}}"#)).unwrap();
+        apply_config_file(&mut source, &path).unwrap();
+        assert_eq!(source.signing_keys(), Some([KEY.to_string()].as_slice()));
+
+        fs::write(&path, r#"{"verify_signatures": false, "verify_sigstore": false}"#).unwrap();
+        apply_config_file(&mut source, &path).unwrap();
+        assert!(source.signing_keys().is_none());
+        assert!(!source.verify_sigstore);
+
+        for invalid in [r#"{"verify_signatures": "no"}"#, r#"{"verify_sigstore": 0}"#] {
+            fs::write(&path, invalid).unwrap();
+            let err = apply_config_file(&mut source, &path).unwrap_err();
+            assert!(err.contains("must be a boolean"), "{err}");
+        }
+    }
+
+    #[test]
+    fn config_file_sets_sigstore_trust_root_and_identity() {
+        use crate::sigstore::tests::ROOT;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        fs::write(tmp.path().join("trusted_root.json"), ROOT).unwrap();
+        fs::write(
+            &path,
+            r#"{
+                "sigstore_trusted_root": "trusted_root.json",
+                "sigstore_identity": "https://github.com/acme/fork/.github/workflows"
+            }"#,
+        )
+        .unwrap();
+
+        let mut source = SourceConfig {
+            verify_sigstore: false,
+            ..SourceConfig::default()
+        };
+        source.set_repo("acme/fork").unwrap();
+        assert_eq!(
+            source.sigstore_workflow(),
+            "https://github.com/centy-io/centy-daemon/.github/workflows/"
+        );
+        apply_config_file(&mut source, &path).unwrap();
+        assert_eq!(source.sigstore_root, ROOT);
+        assert!(source.verify_sigstore);
+        assert_eq!(
+            source.sigstore_workflow(),
+            "https://github.com/acme/fork/.github/workflows/"
+        );
+
+        fs::write(tmp.path().join("trusted_root.json"), "{").unwrap();
+        let err = apply_config_file(&mut SourceConfig::default(), &path).unwrap_err();
+        assert!(err.contains("invalid Sigstore trusted root"), "{err}");
+    }
+
+    #[test]
+    fn config_file_requires_provenance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        let load = |text: &str| {
+            fs::write(&path, text).unwrap();
+            let mut source = SourceConfig::default();
+            apply_config_file(&mut source, &path).map(|()| source)
+        };
+
+        assert_eq!(load("{}").unwrap().provenance, None);
+        assert_eq!(load(r#"{"provenance": false}"#).unwrap().provenance, None);
+        let source = load(r#"{"provenance": true}"#).unwrap();
+        assert_eq!(source.provenance, Some(ProvenancePolicy::default()));
+
+        let source = load(
+            r#"{"provenance": {"builder": "https://github.com/acme/builder.yml",
+                               "repository": "https://github.com/acme/centy-daemon/"}}"#,
+        )
+        .unwrap();
+        let policy = source.provenance.unwrap();
+        assert_eq!(policy.builder.as_deref(), Some("https://github.com/acme/builder.yml"));
+        assert_eq!(policy.repository.as_deref(), Some("https://github.com/acme/centy-daemon"));
+
+        assert!(load(r#"{"provenance": "yes"}"#).is_err());
+        assert!(load(r#"{"provenance": {"builder": 1}}"#).is_err());
+    }
+
+    #[test]
+    fn config_file_sets_checksum_algorithm() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        let load = |text: &str| {
+            fs::write(&path, text).unwrap();
+            let mut source = SourceConfig::default();
+            apply_config_file(&mut source, &path).map(|()| source.checksum_algorithm)
+        };
+
+        assert_eq!(load("{}").unwrap(), None);
+        assert_eq!(
+            load(r#"{"checksum_algorithm": "SHA-512"}"#).unwrap(),
+            Some(DigestAlgorithm::Sha512)
+        );
+        assert_eq!(
+            load(r#"{"checksum_algorithm": "blake3"}"#).unwrap(),
+            Some(DigestAlgorithm::Blake3)
+        );
+        let err = load(r#"{"checksum_algorithm": "md5"}"#).unwrap_err();
+        assert!(err.starts_with("unknown checksum algorithm \"md5\""), "{err}");
+    }
+
+    #[test]
+    fn config_file_sets_token_hosts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("installer.json");
+        let load = |text: &str| {
+            fs::write(&path, text).unwrap();
+            let mut source = SourceConfig::default();
+            apply_config_file(&mut source, &path).map(|()| source.token_hosts)
+        };
+
+        assert!(load("{}").unwrap().is_empty());
+        assert_eq!(
+            load(r#"{"token_hosts": [" GHE.example.com "]}"#).unwrap(),
+            ["ghe.example.com"]
+        );
+        for invalid in [
+            r#"{"token_hosts": "ghe.example.com"}"#,
+            r#"{"token_hosts": ["https://ghe.example.com"]}"#,
+        ] {
+            assert!(load(invalid).is_err(), "{invalid}");
+        }
+    }
+}
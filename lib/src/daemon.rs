@@ -168,7 +168,7 @@ fn send_kill_signal(pid: u32) {
 }
 
 /// Start the daemon process in the background.
-fn start_daemon(binary_path: &Path) -> Result<(), String> {
+pub fn start_daemon(binary_path: &Path) -> Result<(), String> {
     Command::new(binary_path)
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
@@ -178,6 +178,12 @@ fn start_daemon(binary_path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Return the PID of the running daemon, if one is found.
+pub fn running_pid() -> Option<u32> {
+    let home = dirs::home_dir()?;
+    find_daemon_pid(&home)
+}
+
 /// Restart the daemon if it is currently running.
 ///
 /// Returns `true` if the daemon was found and restarted, `false` if it was not
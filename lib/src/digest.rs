@@ -0,0 +1,402 @@
+use std::fmt;
+use std::io;
+
+use sha2::{Digest as _, Sha256, Sha512};
+
+/// Algorithm of the digests listed in a checksums file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    /// Every supported algorithm, in the order their checksums files are
+    /// looked for.
+    pub const ALL: [Self; 3] = [Self::Sha256, Self::Sha512, Self::Blake3];
+
+    /// Parse an algorithm name such as `sha512`, `SHA-512` or `BLAKE3`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase().replace(['-', '_'], "");
+        Self::ALL.into_iter().find(|algorithm| algorithm.id() == name)
+    }
+
+    /// Guess the algorithm of a checksums file from its name, such as
+    /// `checksums-sha512.txt`, `SHA256SUMS` or `B3SUMS`.
+    #[must_use]
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.starts_with("b3sums") {
+            return Some(Self::Blake3);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| name.contains(algorithm.id()))
+    }
+
+    /// Lowercase identifier, as used in `sha512:<hex>` digests.
+    #[must_use]
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Name of the checksums file published with a release, such as
+    /// `checksums-sha512.txt`.
+    #[must_use]
+    pub fn checksums_file(self) -> String {
+        format!("checksums-{}.txt", self.id())
+    }
+
+    /// Length of a digest in hex digits.
+    const fn hex_len(self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake3 => 64,
+            Self::Sha512 => 128,
+        }
+    }
+
+    /// Whether `hex` is a digest of this algorithm: hex digits, in either
+    /// case, of the right length.
+    fn is_hex_digest(self, hex: &str) -> bool {
+        hex.len() == self.hex_len() && hex.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sha256 => "SHA-256",
+            Self::Sha512 => "SHA-512",
+            Self::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+/// A digest of a release asset, hex encoded in lowercase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub algorithm: DigestAlgorithm,
+    pub hex: String,
+}
+
+impl Digest {
+    pub fn sha256(hex: &str) -> Self {
+        Self {
+            algorithm: DigestAlgorithm::Sha256,
+            hex: hex.to_ascii_lowercase(),
+        }
+    }
+
+    /// Parse a hex digest of `algorithm`, checking that it holds as many hex
+    /// digits as digests of that algorithm do.
+    pub fn from_hex(algorithm: DigestAlgorithm, hex: &str) -> Result<Self, String> {
+        if !algorithm.is_hex_digest(hex) {
+            return Err(format!("invalid {algorithm} digest: {hex}"));
+        }
+        Ok(Self {
+            algorithm,
+            hex: hex.to_ascii_lowercase(),
+        })
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.id(), self.hex)
+    }
+}
+
+/// Hashes data with SHA-256, which receipts and attestations refer to, and
+/// with the algorithm of the digest it is verified against when that is
+/// another one.
+pub struct Hasher {
+    sha256: Sha256,
+    other: Option<OtherHasher>,
+}
+
+enum OtherHasher {
+    Sha512(Box<Sha512>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        let other = match algorithm {
+            DigestAlgorithm::Sha256 => None,
+            DigestAlgorithm::Sha512 => Some(OtherHasher::Sha512(Box::default())),
+            DigestAlgorithm::Blake3 => Some(OtherHasher::Blake3(Box::default())),
+        };
+        Self {
+            sha256: Sha256::new(),
+            other,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sha256.update(data);
+        match &mut self.other {
+            None => {}
+            Some(OtherHasher::Sha512(hasher)) => hasher.update(data),
+            Some(OtherHasher::Blake3(hasher)) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Return the SHA-256 of the data, hex encoded, and its digest with the
+    /// algorithm the hasher was created for.
+    pub fn finalize(self) -> (String, Digest) {
+        let sha256 = hex::encode(self.sha256.finalize());
+        let digest = match self.other {
+            None => Digest::sha256(&sha256),
+            Some(OtherHasher::Sha512(hasher)) => Digest {
+                algorithm: DigestAlgorithm::Sha512,
+                hex: hex::encode(hasher.finalize()),
+            },
+            Some(OtherHasher::Blake3(hasher)) => Digest {
+                algorithm: DigestAlgorithm::Blake3,
+                hex: hasher.finalize().to_hex().to_string(),
+            },
+        };
+        (sha256, digest)
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Parse a checksums file and return the digest listed for `asset_name`.
+///
+/// Lines are either GNU style, `<hex>  <name>` or `<hex> *<name>` in binary
+/// mode, or BSD style, `SHA512 (<name>) = <hex>`. Blank lines and `#`
+/// comments are skipped, and hex digits may be in either case.
+///
+/// `algorithm` is the algorithm of the file, from its name or an explicit
+/// setting. BSD lines of other algorithms are then ignored. When it is
+/// unknown, GNU lines are read as SHA-512 if they hold 128 hex digits and
+/// as SHA-256 otherwise.
+pub fn parse_checksum(
+    checksums_text: &str,
+    asset_name: &str,
+    algorithm: Option<DigestAlgorithm>,
+) -> Result<Digest, String> {
+    let (listed, hex) = checksums_text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| parse_line(line, algorithm))
+        .find(|(_, name, _)| *name == asset_name)
+        .map(|(listed, _, hex)| (listed, hex))
+        .ok_or_else(|| format!("checksum not found for {asset_name} in checksums file"))?;
+
+    let algorithm = listed.or(algorithm).unwrap_or(if hex.len() == 128 {
+        DigestAlgorithm::Sha512
+    } else {
+        DigestAlgorithm::Sha256
+    });
+    if !algorithm.is_hex_digest(hex) {
+        return Err(format!(
+            "invalid {algorithm} checksum for {asset_name} in checksums file: {hex}"
+        ));
+    }
+    Ok(Digest {
+        algorithm,
+        hex: hex.to_ascii_lowercase(),
+    })
+}
+
+/// Split a checksums line into the algorithm it names, if any, the file
+/// name and the hex digest. BSD lines that do not match `algorithm` are
+/// skipped.
+fn parse_line(
+    line: &str,
+    algorithm: Option<DigestAlgorithm>,
+) -> Option<(Option<DigestAlgorithm>, &str, &str)> {
+    let bsd = line.rsplit_once(" = ").and_then(|(head, hex)| {
+        let (tag, name) = head.split_once(" (")?;
+        Some((tag, name.strip_suffix(')')?, hex))
+    });
+    if let Some((tag, name, hex)) = bsd {
+        let listed = DigestAlgorithm::from_name(tag)
+            .filter(|listed| algorithm.is_none_or(|algorithm| algorithm == *listed))?;
+        return Some((Some(listed), name, hex.trim()));
+    }
+
+    let (hex, name) = line.split_once(char::is_whitespace)?;
+    let name = name.trim();
+    Some((None, name.strip_prefix('*').unwrap_or(name), hex))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "c40ca88d580de8fa32db7d997b37f971a9a0a050d1b79a12aaf409a2191a32ca";
+
+    fn hashes(data: &[u8], algorithm: DigestAlgorithm) -> (String, Digest) {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn parse_checksum_found() {
+        let checksums = format!(
+            "\
+{SHA256}  centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz
+{}  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
+",
+            "0".repeat(64)
+        );
+        let digest =
+            parse_checksum(&checksums, "centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz", None)
+                .unwrap();
+        assert_eq!(digest, Digest::sha256(SHA256));
+    }
+
+    #[test]
+    fn parse_checksum_second_entry() {
+        let checksums = format!(
+            "\
+{}  centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz
+{SHA256}  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
+",
+            "0".repeat(64)
+        );
+        let digest = parse_checksum(
+            &checksums,
+            "centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz",
+            None,
+        )
+        .unwrap();
+        assert_eq!(digest.hex, SHA256);
+    }
+
+    #[test]
+    fn parse_checksum_single_space_separator() {
+        let checksums = format!("{SHA256} my-asset.tar.gz\n");
+        let digest = parse_checksum(&checksums, "my-asset.tar.gz", None).unwrap();
+        assert_eq!(digest.hex, SHA256);
+    }
+
+    #[test]
+    fn parse_checksum_not_found() {
+        let checksums = format!("{SHA256}  other-file.tar.gz\n");
+        let result = parse_checksum(&checksums, "missing.tar.gz", None);
+        assert!(result
+            .unwrap_err()
+            .contains("checksum not found for missing.tar.gz"));
+    }
+
+    #[test]
+    fn parse_checksum_empty_input() {
+        let result = parse_checksum("", "anything.tar.gz", None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_checksum_blank_lines_and_comments() {
+        let checksums = format!("\n# SHA-256 checksums\n\n{SHA256}  target.tar.gz\n\n");
+        let digest = parse_checksum(&checksums, "target.tar.gz", None).unwrap();
+        assert_eq!(digest.hex, SHA256);
+    }
+
+    #[test]
+    fn parse_checksum_binary_mode_and_uppercase() {
+        let checksums = format!("{} *target.tar.gz\n", SHA256.to_ascii_uppercase());
+        let digest = parse_checksum(&checksums, "target.tar.gz", None).unwrap();
+        assert_eq!(digest, Digest::sha256(SHA256));
+    }
+
+    #[test]
+    fn parse_checksum_bsd_lines() {
+        let sha512 = "ab".repeat(64);
+        let checksums = format!(
+            "SHA256 (target.tar.gz) = {SHA256}\nSHA512 (target.tar.gz) = {sha512}\n"
+        );
+        let digest = parse_checksum(&checksums, "target.tar.gz", None).unwrap();
+        assert_eq!(digest, Digest::sha256(SHA256));
+
+        let digest =
+            parse_checksum(&checksums, "target.tar.gz", Some(DigestAlgorithm::Sha512)).unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha512);
+        assert_eq!(digest.hex, sha512);
+
+        let err = parse_checksum(&checksums, "target.tar.gz", Some(DigestAlgorithm::Blake3))
+            .unwrap_err();
+        assert!(err.contains("checksum not found"), "{err}");
+    }
+
+    #[test]
+    fn parse_checksum_selects_algorithm() {
+        let sha512 = "AB".repeat(64);
+        let checksums = format!("{sha512}  target.tar.gz\n");
+        let digest = parse_checksum(&checksums, "target.tar.gz", None).unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha512);
+        assert_eq!(digest.hex, sha512.to_ascii_lowercase());
+
+        let checksums = format!("{SHA256}  target.tar.gz\n");
+        let digest =
+            parse_checksum(&checksums, "target.tar.gz", Some(DigestAlgorithm::Blake3)).unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Blake3);
+
+        let err = parse_checksum(&checksums, "target.tar.gz", Some(DigestAlgorithm::Sha512))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            format!("invalid SHA-512 checksum for target.tar.gz in checksums file: {SHA256}")
+        );
+    }
+
+    #[test]
+    fn algorithm_from_file_name() {
+        let guess = DigestAlgorithm::from_file_name;
+        assert_eq!(guess("checksums-sha256.txt"), Some(DigestAlgorithm::Sha256));
+        assert_eq!(guess("SHA512SUMS"), Some(DigestAlgorithm::Sha512));
+        assert_eq!(guess("checksums-blake3.txt"), Some(DigestAlgorithm::Blake3));
+        assert_eq!(guess("B3SUMS"), Some(DigestAlgorithm::Blake3));
+        assert_eq!(guess("checksums.txt"), None);
+        assert_eq!(DigestAlgorithm::from_name("SHA-512"), Some(DigestAlgorithm::Sha512));
+        assert_eq!(DigestAlgorithm::from_name("md5"), None);
+    }
+
+    #[test]
+    fn hasher_computes_sha256_alongside_other_algorithm() {
+        let (sha256, digest) = hashes(b"abc", DigestAlgorithm::Sha256);
+        assert_eq!(
+            sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(digest, Digest::sha256(&sha256));
+
+        let (other, digest) = hashes(b"abc", DigestAlgorithm::Sha512);
+        assert_eq!(other, sha256);
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha512);
+        assert!(digest.hex.starts_with("ddaf35a193617aba"));
+
+        let (_, digest) = hashes(b"abc", DigestAlgorithm::Blake3);
+        assert_eq!(digest.algorithm, DigestAlgorithm::Blake3);
+        assert!(digest.hex.starts_with("6437b3ac38465133"));
+        assert_eq!(digest.to_string(), format!("blake3:{}", digest.hex));
+    }
+}
@@ -1,46 +1,385 @@
-use reqwest::blocking::Client;
-use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use crate::github::ReleaseInfo;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_LENGTH, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use tempfile::TempPath;
+
+use crate::digest::{Digest, Hasher};
+use crate::source::ReleaseInfo;
+use crate::progress::{Phase, Progress, ProgressObserver};
+use crate::retry::{self, Failure, Retrier};
+
+/// Size of the buffer used to stream the asset to disk.
+const CHUNK_SIZE: usize = 64 * 1024;
 
 #[derive(Debug)]
 pub struct DownloadedAsset {
-    pub bytes: Vec<u8>,
+    /// File holding the archive, positioned at its start.
+    pub file: File,
+    /// SHA-256 of the archive, hex encoded.
+    pub sha256: String,
+    /// Digest the archive was verified against.
+    pub digest: Digest,
+    /// Path of `file` in the download cache, deleted once this is dropped.
+    /// Declared after `file` so the handle is closed first. `None` for a
+    /// local archive, which is read in place.
+    _path: Option<TempPath>,
+}
+
+/// Return the directory where partial downloads are kept between runs.
+pub fn cache_dir_in(home_dir: &Path) -> PathBuf {
+    home_dir.join(".centy").join("cache")
+}
+
+/// Download a text file, retrying transient failures. `file://` URLs are
+/// read from disk. `context` prefixes any error.
+pub fn fetch_text(
+    client: &Client,
+    retry: &Retrier,
+    url: &str,
+    context: &str,
+) -> Result<String, String> {
+    if let Some(path) = file_url_path(url) {
+        return fs::read_to_string(&path)
+            .map_err(|e| format!("{context}: failed to read {}: {e}", path.display()));
+    }
+
+    retry::retry(retry, || {
+        let resp = client
+            .get(url)
+            .header("User-Agent", "centy-installer")
+            .send()
+            .map_err(|e| Failure::request(&e, format!("{context}: {e}")))?;
+
+        if !resp.status().is_success() {
+            let message = format!("{context}: server returned {}", resp.status());
+            return Err(Failure::status(&resp, message));
+        }
+
+        resp.text()
+            .map_err(|e| Failure::transient(format!("{context}: {e}")))
+    })
+}
+
+/// Return the local path of a `file://` URL, or `None` for other URLs.
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    // `file:///C:/dir` names `C:/dir` on Windows
+    let path = if cfg!(windows) {
+        path.strip_prefix('/')
+            .filter(|rest| rest.get(1..2) == Some(":"))
+            .unwrap_or(path)
+    } else {
+        path
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Return the `file://` URL of an absolute `path`.
+pub fn file_url(path: &Path) -> String {
+    // `C:\dir` becomes `file:///C:\dir` on Windows
+    let slash = if cfg!(windows) { "/" } else { "" };
+    format!("file://{slash}{}", path.display())
+}
+
+/// A partial download left in the cache by an earlier, interrupted run.
+struct Partial {
+    len: u64,
+    /// `ETag` or `Last-Modified` of the response the bytes came from, sent
+    /// as `If-Range` so the server only resumes if the asset is unchanged.
+    validator: String,
+}
+
+/// Download the asset archive and verify it against `expected`, the digest
+/// listed for the asset by its release source. Its SHA-256 is computed as
+/// well, whatever the algorithm of `expected`.
+///
+/// `file://` URLs are read in place. Other archives are written to
+/// `<cache_dir>/<asset>.part`. If a download is
+/// interrupted, the partial file is kept and the next call resumes it with a
+/// `Range` request, validated by the `ETag` or `Last-Modified` of the original
+/// response. Servers that ignore the range get a full download instead.
+///
+/// Transient failures are retried according to `retry`, resuming from the
+/// bytes received so far when the server allows it. A checksum mismatch is
+/// not retried.
+///
+/// `progress` receives a [`Phase::Downloading`] update after every chunk.
+pub fn download_and_verify(
+    client: &Client,
+    retry: &Retrier,
+    info: &ReleaseInfo,
+    expected: &Digest,
+    cache_dir: &Path,
+    progress: &dyn ProgressObserver,
+) -> Result<DownloadedAsset, String> {
+    if info.asset_name.contains(['/', '\\']) {
+        return Err(format!("invalid asset name: {}", info.asset_name));
+    }
+
+    if let Some(path) = file_url_path(&info.asset_url) {
+        return open_and_verify(&path, expected, progress);
+    }
+
+    retry::retry(retry, || download_once(client, info, expected, cache_dir, progress))
+}
+
+/// Make a single attempt at downloading and verifying the asset.
+fn download_once(
+    client: &Client,
+    info: &ReleaseInfo,
+    expected: &Digest,
+    cache_dir: &Path,
+    progress: &dyn ProgressObserver,
+) -> Result<DownloadedAsset, Failure> {
+    fs::create_dir_all(cache_dir).map_err(|e| {
+        Failure::Permanent(format!("failed to create {}: {e}", cache_dir.display()))
+    })?;
+    let part_path = cache_dir.join(format!("{}.part", info.asset_name));
+    let meta_path = cache_dir.join(format!("{}.part.json", info.asset_name));
+
+    let mut partial = read_partial(&part_path, &meta_path, &info.asset_url);
+    let mut response = loop {
+        let mut request = client
+            .get(&info.asset_url)
+            .header("User-Agent", "centy-installer");
+        if let Some(partial) = &partial {
+            request = request
+                .header(RANGE, format!("bytes={}-", partial.len))
+                .header(IF_RANGE, &partial.validator);
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| Failure::request(&e, format!("failed to download asset: {e}")))?;
+
+        // The partial file is already complete or longer than the asset
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && partial.is_some() {
+            partial = None;
+            continue;
+        }
+        break response;
+    };
+
+    if !response.status().is_success() {
+        let message = format!(
+            "failed to download asset: server returned {}",
+            response.status()
+        );
+        return Err(Failure::status(&response, message));
+    }
+
+    let resumed = partial
+        .as_ref()
+        .filter(|_| response.status() == StatusCode::PARTIAL_CONTENT)
+        .map(|partial| partial.len);
+
+    let mut hasher = Hasher::new(expected.algorithm);
+    let mut file = open_part_file(&part_path, resumed, &mut hasher)?;
+    if resumed.is_none() {
+        write_partial_meta(&meta_path, &info.asset_url, &response)
+            .map_err(Failure::Permanent)?;
+    }
+
+    let received = resumed.unwrap_or(0);
+    let total = content_length(&response)
+        .map(|len| len + received)
+        .or(info.size);
+    stream_to(&mut response, &mut file, &mut hasher, received, total, progress)?;
+
+    // From here on the partial file is either verified or discarded
+    let _ = fs::remove_file(&meta_path);
+    let path = TempPath::try_from_path(&part_path).map_err(|e| {
+        let _ = fs::remove_file(&part_path);
+        Failure::Permanent(format!("failed to open {}: {e}", part_path.display()))
+    })?;
+    verify(file, Some(path), hasher, expected, progress)
+}
+
+/// Open a local archive and verify it against `expected` in place, without
+/// copying it.
+pub fn open_and_verify(
+    path: &Path,
+    expected: &Digest,
+    progress: &dyn ProgressObserver,
+) -> Result<DownloadedAsset, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+    let mut hasher = Hasher::new(expected.algorithm);
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    verify(file, None, hasher, expected, progress).map_err(Failure::message)
+}
+
+/// Stream `reader` to `file`, hashing it as it arrives.
+///
+/// `received` is the number of bytes already in `file`, and `total` the
+/// expected size once complete, both used for progress updates.
+fn stream_to(
+    reader: &mut impl Read,
+    file: &mut File,
+    hasher: &mut Hasher,
+    mut received: u64,
+    total: Option<u64>,
+    progress: &dyn ProgressObserver,
+) -> Result<(), Failure> {
+    progress.on_progress(Progress {
+        phase: Phase::Downloading,
+        bytes: received,
+        total,
+    });
+
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .map_err(|e| Failure::transient(format!("failed to download asset: {e}")))?;
+        let Some(chunk) = buf.get(..n).filter(|chunk| !chunk.is_empty()) else {
+            return Ok(());
+        };
+        hasher.update(chunk);
+        file.write_all(chunk).map_err(|e| {
+            Failure::Permanent(format!("failed to write downloaded asset: {e}"))
+        })?;
+
+        received += chunk.len() as u64;
+        progress.on_progress(Progress {
+            phase: Phase::Downloading,
+            bytes: received,
+            total,
+        });
+    }
 }
 
-/// Download the asset archive and verify its SHA256 checksum.
-pub fn download_and_verify(client: &Client, info: &ReleaseInfo) -> Result<DownloadedAsset, String> {
-    // Download checksums file
-    let checksums_text = client
-        .get(&info.checksums_url)
-        .header("User-Agent", "centy-installer")
-        .send()
-        .and_then(reqwest::blocking::Response::text)
-        .map_err(|e| format!("failed to download checksums: {e}"))?;
+/// Check the digest of a downloaded `file` against `expected`.
+///
+/// On success the file is rewound and returned; otherwise `path`, if any, is
+/// dropped, which deletes it.
+fn verify(
+    mut file: File,
+    path: Option<TempPath>,
+    hasher: Hasher,
+    expected: &Digest,
+    progress: &dyn ProgressObserver,
+) -> Result<DownloadedAsset, Failure> {
+    progress.on_progress(Progress::started(Phase::Verifying));
+    let (sha256, digest) = hasher.finalize();
+
+    if !digest.hex.eq_ignore_ascii_case(&expected.hex) {
+        return Err(Failure::Permanent(format!(
+            "{} checksum mismatch: expected {}, got {}",
+            expected.algorithm, expected.hex, digest.hex
+        )));
+    }
 
-    let expected_hash = crate::github::parse_checksum(&checksums_text, &info.asset_name)?;
+    file.seek(SeekFrom::Start(0)).map_err(|e| {
+        Failure::Permanent(format!("failed to rewind downloaded asset: {e}"))
+    })?;
 
-    // Download asset archive
-    let asset_bytes = client
-        .get(&info.asset_url)
-        .header("User-Agent", "centy-installer")
-        .send()
-        .and_then(reqwest::blocking::Response::bytes)
-        .map_err(|e| format!("failed to download asset: {e}"))?
-        .to_vec();
+    Ok(DownloadedAsset {
+        file,
+        sha256,
+        digest,
+        _path: path,
+    })
+}
 
-    // Verify checksum
-    let mut hasher = Sha256::new();
-    hasher.update(&asset_bytes);
-    let actual_hash = hex::encode(hasher.finalize());
+/// Open the partial download at `part_path` for appending.
+///
+/// When resuming from `resumed` bytes, those bytes are fed to `hasher` first;
+/// otherwise the file is truncated and the download starts over.
+fn open_part_file(
+    part_path: &Path,
+    resumed: Option<u64>,
+    hasher: &mut Hasher,
+) -> Result<File, Failure> {
+    let Some(offset) = resumed else {
+        return OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(part_path)
+            .map_err(|e| {
+                Failure::Permanent(format!("failed to create {}: {e}", part_path.display()))
+            });
+    };
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .open(part_path)
+        .map_err(|e| Failure::Permanent(format!("failed to open {}: {e}", part_path.display())))?;
+    io::copy(&mut (&mut file).take(offset), hasher).map_err(|e| {
+        Failure::Permanent(format!("failed to read {}: {e}", part_path.display()))
+    })?;
+    Ok(file)
+}
 
-    if actual_hash != expected_hash {
-        return Err(format!(
-            "checksum mismatch: expected {expected_hash}, got {actual_hash}"
-        ));
+/// Length of the response body, from the `Content-Length` header.
+///
+/// Read from the header rather than [`Response::content_length`], which
+/// reports the size of the full resource for `206 Partial Content` replies.
+fn content_length(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Look for a resumable partial download of `url`.
+fn read_partial(part_path: &Path, meta_path: &Path, url: &str) -> Option<Partial> {
+    let len = fs::metadata(part_path).ok()?.len();
+    if len == 0 {
+        return None;
     }
 
-    Ok(DownloadedAsset { bytes: asset_bytes })
+    let text = fs::read_to_string(meta_path).ok()?;
+    let meta: serde_json::Value = serde_json::from_str(&text).ok()?;
+    if meta.get("url").and_then(serde_json::Value::as_str) != Some(url) {
+        return None;
+    }
+
+    let field = |name| meta.get(name).and_then(serde_json::Value::as_str);
+    let validator = field("etag").or_else(|| field("last_modified"))?.to_string();
+
+    Some(Partial { len, validator })
+}
+
+/// Record the validators of `response` so an interrupted download can resume.
+///
+/// Without an `ETag` or `Last-Modified` a resume could silently splice two
+/// different files, so no metadata is written and the next run starts over.
+fn write_partial_meta(meta_path: &Path, url: &str, response: &Response) -> Result<(), String> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header(ETAG).filter(|etag| !etag.starts_with("W/"));
+    let last_modified = header(LAST_MODIFIED);
+
+    if etag.is_none() && last_modified.is_none() {
+        let _ = fs::remove_file(meta_path);
+        return Ok(());
+    }
+
+    let meta = serde_json::json!({
+        "url": url,
+        "etag": etag,
+        "last_modified": last_modified,
+    });
+
+    fs::write(meta_path, meta.to_string())
+        .map_err(|e| format!("failed to write {}: {e}", meta_path.display()))
 }
 
 #[cfg(test)]
@@ -53,13 +392,46 @@ pub fn download_and_verify(client: &Client, info: &ReleaseInfo) -> Result<Downlo
 )]
 mod tests {
     use super::*;
+    use crate::digest::{parse_checksum, DigestAlgorithm};
+    use crate::progress::NoProgress;
+    use crate::retry::RetryPolicy;
+    use sha2::{Digest as _, Sha256};
+    use crate::source::Checksum;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn fetch_and_verify(
+        client: &Client,
+        info: &ReleaseInfo,
+        cache_dir: &Path,
+    ) -> Result<DownloadedAsset, String> {
+        let expected = match &info.checksum {
+            Checksum::Digest(digest) => digest.clone(),
+            Checksum::File(url) => {
+                let text = fetch_text(client, &fast_retry(), url, "failed to download checksums")?;
+                let algorithm = DigestAlgorithm::from_file_name(url);
+                parse_checksum(&text, &info.asset_name, algorithm)?
+            }
+        };
+        download_and_verify(client, &fast_retry(), info, &expected, cache_dir, &NoProgress)
+    }
+
+    fn fast_retry() -> Retrier {
+        Retrier::quiet(RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::from_secs(1),
+        })
+    }
 
     fn make_info(server_url: &str) -> ReleaseInfo {
         ReleaseInfo {
             tag: "v1.0.0".to_string(),
             asset_url: format!("{server_url}/test-asset.tar.gz"),
-            checksums_url: format!("{server_url}/checksums-sha256.txt"),
+            size: None,
+            checksum: Checksum::File(format!("{server_url}/checksums-sha256.txt")),
             asset_name: "test-asset.tar.gz".to_string(),
+            provenance: None,
         }
     }
 
@@ -86,15 +458,95 @@ mod tests {
             .with_body(asset_bytes)
             .create();
 
+        let cache = tempfile::tempdir().unwrap();
         let client = Client::new();
         let info = make_info(&server.url());
-        let result = download_and_verify(&client, &info).unwrap();
-        assert_eq!(result.bytes, asset_bytes);
+        let mut result = fetch_and_verify(&client, &info, cache.path()).unwrap();
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, asset_bytes);
+        assert_eq!(result.sha256, expected_hash);
 
         checksums_mock.assert();
         asset_mock.assert();
     }
 
+    #[test]
+    fn download_and_verify_streams_large_asset() {
+        let mut server = mockito::Server::new();
+
+        // Several chunks long, and not a multiple of the chunk size
+        let asset_bytes: Vec<u8> = (0..=250).cycle().take(CHUNK_SIZE * 3 + 17).collect();
+        let expected_hash = hex::encode(Sha256::digest(&asset_bytes));
+
+        server
+            .mock("GET", "/checksums-sha256.txt")
+            .with_status(200)
+            .with_body(format!("{expected_hash}  test-asset.tar.gz\n"))
+            .create();
+        server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_body(&asset_bytes)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let client = Client::new();
+        let info = make_info(&server.url());
+        let mut result = fetch_and_verify(&client, &info, cache.path()).unwrap();
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, asset_bytes);
+        assert_eq!(result.sha256, expected_hash);
+    }
+
+    #[test]
+    fn download_and_verify_reports_progress() {
+        let mut server = mockito::Server::new();
+
+        let asset_bytes: Vec<u8> = (0..=250).cycle().take(CHUNK_SIZE + 5).collect();
+        let expected_hash = hex::encode(Sha256::digest(&asset_bytes));
+        server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_body(&asset_bytes)
+            .create();
+
+        let updates = Mutex::new(Vec::new());
+        let observer = |progress: Progress| updates.lock().unwrap().push(progress);
+
+        let cache = tempfile::tempdir().unwrap();
+        let client = Client::new();
+        let info = make_info(&server.url());
+        download_and_verify(
+            &client,
+            &fast_retry(),
+            &info,
+            &Digest::sha256(&expected_hash),
+            cache.path(),
+            &observer,
+        )
+        .unwrap();
+
+        let updates = updates.into_inner().unwrap();
+        let total = Some(asset_bytes.len() as u64);
+        assert_eq!(
+            updates.first(),
+            Some(&Progress {
+                phase: Phase::Downloading,
+                bytes: 0,
+                total,
+            })
+        );
+        let downloads: Vec<_> = updates
+            .iter()
+            .filter(|p| p.phase == Phase::Downloading)
+            .collect();
+        assert!(downloads.is_sorted_by_key(|p| p.bytes));
+        assert_eq!(downloads.last().unwrap().bytes, asset_bytes.len() as u64);
+        assert_eq!(updates.last().unwrap().phase, Phase::Verifying);
+    }
+
     #[test]
     fn download_and_verify_checksum_mismatch() {
         let mut server = mockito::Server::new();
@@ -113,9 +565,10 @@ mod tests {
             .with_body("some-data")
             .create();
 
+        let cache = tempfile::tempdir().unwrap();
         let client = Client::new();
         let info = make_info(&server.url());
-        let result = download_and_verify(&client, &info);
+        let result = fetch_and_verify(&client, &info, cache.path());
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("checksum mismatch"));
     }
@@ -133,9 +586,10 @@ mod tests {
             .with_body(checksums_body)
             .create();
 
+        let cache = tempfile::tempdir().unwrap();
         let client = Client::new();
         let info = make_info(&server.url());
-        let result = download_and_verify(&client, &info);
+        let result = fetch_and_verify(&client, &info, cache.path());
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("checksum not found"));
     }
@@ -145,12 +599,15 @@ mod tests {
         let info = ReleaseInfo {
             tag: "v1.0.0".to_string(),
             asset_url: "http://127.0.0.1:1/asset.tar.gz".to_string(),
-            checksums_url: "http://127.0.0.1:1/checksums-sha256.txt".to_string(),
+            size: None,
+            checksum: Checksum::File("http://127.0.0.1:1/checksums-sha256.txt".to_string()),
             asset_name: "asset.tar.gz".to_string(),
+            provenance: None,
         };
 
+        let cache = tempfile::tempdir().unwrap();
         let client = Client::new();
-        let result = download_and_verify(&client, &info);
+        let result = fetch_and_verify(&client, &info, cache.path());
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("failed to download checksums"));
     }
@@ -176,13 +633,351 @@ mod tests {
         let info = ReleaseInfo {
             tag: "v1.0.0".to_string(),
             asset_url: "http://127.0.0.1:1/test-asset.tar.gz".to_string(),
-            checksums_url: format!("{}/checksums-sha256.txt", server.url()),
+            size: None,
+            checksum: Checksum::File(format!("{}/checksums-sha256.txt", server.url())),
             asset_name: "test-asset.tar.gz".to_string(),
+            provenance: None,
         };
 
+        let cache = tempfile::tempdir().unwrap();
         let client = Client::new();
-        let result = download_and_verify(&client, &info);
+        let result = fetch_and_verify(&client, &info, cache.path());
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("failed to download asset"));
     }
+
+    /// Hash of `bytes`, hex encoded.
+    fn sha256_hex(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    fn write_partial(cache: &Path, info: &ReleaseInfo, bytes: &[u8], etag: &str) {
+        fs::write(cache.join("test-asset.tar.gz.part"), bytes).unwrap();
+        let meta = serde_json::json!({ "url": info.asset_url, "etag": etag });
+        fs::write(cache.join("test-asset.tar.gz.part.json"), meta.to_string()).unwrap();
+    }
+
+    #[test]
+    fn download_resumes_partial_file_with_range_request() {
+        let mut server = mockito::Server::new();
+        let asset_bytes: Vec<u8> = (0..=250).cycle().take(CHUNK_SIZE + 100).collect();
+        let (head, tail) = asset_bytes.split_at(1000);
+
+        let resume_mock = server
+            .mock("GET", "/test-asset.tar.gz")
+            .match_header("range", "bytes=1000-")
+            .match_header("if-range", "\"abc\"")
+            .with_status(206)
+            .with_header("content-range", &format!("bytes 1000-{}/*", asset_bytes.len() - 1))
+            .with_body(tail)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        write_partial(cache.path(), &info, head, "\"abc\"");
+
+        let updates = Mutex::new(Vec::new());
+        let observer = |progress: Progress| updates.lock().unwrap().push(progress);
+
+        let client = Client::new();
+        let mut result = download_and_verify(
+            &client,
+            &fast_retry(),
+            &info,
+            &Digest::sha256(&sha256_hex(&asset_bytes)),
+            cache.path(),
+            &observer,
+        )
+        .unwrap();
+
+        resume_mock.assert();
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, asset_bytes);
+
+        let updates = updates.into_inner().unwrap();
+        assert_eq!(updates.first().unwrap().bytes, 1000);
+        assert_eq!(updates.first().unwrap().total, Some(asset_bytes.len() as u64));
+    }
+
+    #[test]
+    fn download_restarts_when_server_ignores_range() {
+        let mut server = mockito::Server::new();
+        let asset_bytes = b"the-complete-asset";
+
+        server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_body(asset_bytes)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        write_partial(cache.path(), &info, b"stale-bytes", "\"old\"");
+
+        let client = Client::new();
+        let mut result = download_and_verify(
+            &client,
+            &fast_retry(),
+            &info,
+            &Digest::sha256(&sha256_hex(asset_bytes)),
+            cache.path(),
+            &NoProgress,
+        )
+        .unwrap();
+
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, asset_bytes);
+    }
+
+    #[test]
+    fn download_keeps_partial_file_for_resume_after_interruption() {
+        let mut server = mockito::Server::new();
+
+        // Drop the connection partway through the body
+        server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_header("etag", "\"abc\"")
+            .with_chunked_body(|w| {
+                w.write_all(b"partial-data")?;
+                w.flush()?;
+                // Give the client time to receive the bytes before the connection drops
+                std::thread::sleep(Duration::from_millis(50));
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "dropped"))
+            })
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        let client = Client::new();
+        let result = download_and_verify(
+            &client,
+            &Retrier::quiet(RetryPolicy::none()),
+            &info,
+            &Digest::sha256("abc"),
+            cache.path(),
+            &NoProgress,
+        );
+        assert!(result.unwrap_err().contains("failed to download asset"));
+
+        let partial = read_partial(
+            &cache.path().join("test-asset.tar.gz.part"),
+            &cache.path().join("test-asset.tar.gz.part.json"),
+            &info.asset_url,
+        )
+        .unwrap();
+        assert_eq!(partial.validator, "\"abc\"");
+    }
+
+    #[test]
+    fn download_removes_cached_file_after_verification() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_header("etag", "\"abc\"")
+            .with_body("some-data")
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        let client = Client::new();
+
+        let result = download_and_verify(
+            &client,
+            &fast_retry(),
+            &info,
+            &Digest::sha256("deadbeef"),
+            cache.path(),
+            &NoProgress,
+        );
+        assert!(result.unwrap_err().contains("checksum mismatch"));
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 0);
+
+        let result = download_and_verify(
+            &client,
+            &fast_retry(),
+            &info,
+            &Digest::sha256(&sha256_hex(b"some-data")),
+            cache.path(),
+            &NoProgress,
+        )
+        .unwrap();
+        drop(result);
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn download_rejects_not_found_without_retrying() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(404)
+            .expect(1)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        let result = download_and_verify(
+            &Client::new(),
+            &fast_retry(),
+            &info,
+            &Digest::sha256("abc"),
+            cache.path(),
+            &NoProgress,
+        );
+        assert!(result.unwrap_err().contains("404"));
+        mock.assert();
+    }
+
+    #[test]
+    fn download_retry_resumes_after_interruption() {
+        let mut server = mockito::Server::new();
+        let asset_bytes = b"partial-data-and-the-rest";
+        let (head, tail) = asset_bytes.split_at(12);
+
+        let interrupted = server
+            .mock("GET", "/test-asset.tar.gz")
+            .match_header("range", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("etag", "\"abc\"")
+            .with_chunked_body(move |w| {
+                w.write_all(head)?;
+                w.flush()?;
+                // Give the client time to receive the bytes before the connection drops
+                std::thread::sleep(Duration::from_millis(50));
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "dropped"))
+            })
+            .expect(1)
+            .create();
+        let resumed = server
+            .mock("GET", "/test-asset.tar.gz")
+            .match_header("range", "bytes=12-")
+            .with_status(206)
+            .with_body(tail)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        let mut result = download_and_verify(
+            &Client::new(),
+            &fast_retry(),
+            &info,
+            &Digest::sha256(&sha256_hex(asset_bytes)),
+            cache.path(),
+            &NoProgress,
+        )
+        .unwrap();
+
+        interrupted.assert();
+        resumed.assert();
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, asset_bytes);
+    }
+
+    #[test]
+    fn download_does_not_retry_checksum_mismatch() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/test-asset.tar.gz")
+            .with_status(200)
+            .with_body("some-data")
+            .expect(1)
+            .create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = make_info(&server.url());
+        let result = download_and_verify(
+            &Client::new(),
+            &fast_retry(),
+            &info,
+            &Digest::sha256("deadbeef"),
+            cache.path(),
+            &NoProgress,
+        );
+        assert!(result.unwrap_err().contains("checksum mismatch"));
+        mock.assert();
+    }
+
+    #[test]
+    fn download_copies_file_url() {
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp.path().join("test-asset.tar.gz");
+        fs::write(&archive, b"local-archive").unwrap();
+
+        let info = ReleaseInfo {
+            tag: "v1.0.0".to_string(),
+            asset_url: format!("file://{}", archive.display()),
+            asset_name: "test-asset.tar.gz".to_string(),
+            size: None,
+            checksum: Checksum::Digest(Digest::sha256(&sha256_hex(b"local-archive"))),
+            provenance: None,
+        };
+        let cache = tempfile::tempdir().unwrap();
+        let mut result = fetch_and_verify(&Client::new(), &info, cache.path()).unwrap();
+
+        let mut downloaded = Vec::new();
+        result.file.read_to_end(&mut downloaded).unwrap();
+        assert_eq!(downloaded, b"local-archive");
+        drop(result);
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn download_and_verify_sha512_checksums() {
+        let mut server = mockito::Server::new();
+        let asset_bytes = b"fake-binary-data";
+        let sha512 = hex::encode(sha2::Sha512::digest(asset_bytes));
+        server
+            .mock("GET", "/checksums-sha512.txt")
+            .with_body(format!("# mirror\nSHA512 (test-asset.tar.gz) = {sha512}\n"))
+            .create();
+        server.mock("GET", "/test-asset.tar.gz").with_body(asset_bytes).create();
+
+        let cache = tempfile::tempdir().unwrap();
+        let info = ReleaseInfo {
+            checksum: Checksum::File(format!("{}/checksums-sha512.txt", server.url())),
+            ..make_info(&server.url())
+        };
+        let result = fetch_and_verify(&Client::new(), &info, cache.path()).unwrap();
+        assert_eq!(result.digest.algorithm, DigestAlgorithm::Sha512);
+        assert_eq!(result.digest.hex, sha512);
+        assert_eq!(result.sha256, sha256_hex(asset_bytes));
+    }
+
+    #[test]
+    fn open_and_verify_blake3() {
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp.path().join("test-asset.tar.gz");
+        fs::write(&archive, b"local-archive").unwrap();
+
+        let expected = Digest {
+            algorithm: DigestAlgorithm::Blake3,
+            hex: blake3::hash(b"local-archive").to_hex().to_string(),
+        };
+        let result = open_and_verify(&archive, &expected, &NoProgress).unwrap();
+        assert_eq!(result.digest, expected);
+
+        let expected = Digest {
+            hex: blake3::hash(b"other-archive").to_hex().to_string(),
+            ..expected
+        };
+        let err = open_and_verify(&archive, &expected, &NoProgress).unwrap_err();
+        assert!(err.starts_with("BLAKE3 checksum mismatch"), "{err}");
+    }
+
+    #[test]
+    fn open_and_verify_rejects_mismatch_and_keeps_archive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp.path().join("test-asset.tar.gz");
+        fs::write(&archive, b"tampered").unwrap();
+
+        let expected = sha256_hex(b"local-archive");
+        let result = open_and_verify(&archive, &Digest::sha256(&expected), &NoProgress);
+        assert!(result.unwrap_err().contains("checksum mismatch"));
+        assert_eq!(fs::read(&archive).unwrap(), b"tampered");
+    }
 }
@@ -1,18 +1,184 @@
-use std::io::{Cursor, Read};
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+
+/// Expansion ratios are only checked once this many bytes were expanded, so
+/// that small, highly compressible archives pass.
+const RATIO_GRACE: u64 = 1024 * 1024;
+
+/// Caps applied while extracting a release archive, so that a corrupt or
+/// malicious archive fails instead of exhausting memory or disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractionLimits {
+    /// Most bytes the archive may expand to, counting the entries skipped on
+    /// the way to the binary.
+    pub max_size: u64,
+    /// Most entries the archive may hold.
+    pub max_entries: u64,
+    /// Highest ratio of expanded to compressed size.
+    pub max_ratio: u64,
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1024 * 1024 * 1024,
+            max_entries: 10_000,
+            max_ratio: 100,
+        }
+    }
+}
+
+/// Error from extracting a release archive.
+#[derive(Debug)]
+pub enum ExtractError {
+    /// The archive exceeds one of the [`ExtractionLimits`].
+    Limit(String),
+    Other(String),
+}
+
+impl From<String> for ExtractError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limit(message) | Self::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Running totals of an extraction, checked against its limits.
+struct Meter {
+    limits: ExtractionLimits,
+    compressed: Cell<u64>,
+    expanded: Cell<u64>,
+    entries: Cell<u64>,
+}
+
+impl Meter {
+    const fn new(limits: ExtractionLimits) -> Self {
+        Self {
+            limits,
+            compressed: Cell::new(0),
+            expanded: Cell::new(0),
+            entries: Cell::new(0),
+        }
+    }
+
+    /// Count `count` more entries.
+    fn entries(&self, count: u64) -> Result<(), ExtractError> {
+        let entries = self.entries.get().saturating_add(count);
+        self.entries.set(entries);
+        if entries > self.limits.max_entries {
+            return Err(ExtractError::Limit(format!(
+                "archive has more than {} entries",
+                self.limits.max_entries
+            )));
+        }
+        Ok(())
+    }
+
+    fn check(&self) -> Result<(), ExtractError> {
+        self.check_sizes(self.compressed.get(), self.expanded.get())
+    }
+
+    /// Check that `compressed` bytes expanding to `expanded` bytes stay
+    /// within the limits.
+    fn check_sizes(&self, compressed: u64, expanded: u64) -> Result<(), ExtractError> {
+        let limits = &self.limits;
+        if expanded > limits.max_size {
+            return Err(ExtractError::Limit(format!(
+                "archive expands to more than {} bytes",
+                limits.max_size
+            )));
+        }
+        if expanded > RATIO_GRACE && expanded / compressed.max(1) > limits.max_ratio {
+            return Err(ExtractError::Limit(format!(
+                "archive expands to more than {} times its compressed size",
+                limits.max_ratio
+            )));
+        }
+        Ok(())
+    }
+
+    /// The limit that was exceeded while reading, or `message` otherwise.
+    /// Readers report limits as I/O errors, which the archive crates wrap.
+    fn error(&self, message: String) -> ExtractError {
+        self.check().err().unwrap_or(ExtractError::Other(message))
+    }
+}
+
+/// Counts the compressed bytes read from an archive.
+struct CountCompressed<'a, R> {
+    inner: R,
+    meter: &'a Meter,
+}
+
+impl<R: Read> Read for CountCompressed<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let meter = self.meter;
+        meter.compressed.set(meter.compressed.get().saturating_add(n as u64));
+        Ok(n)
+    }
+}
+
+/// Counts the expanded bytes of an archive, failing once they exceed the
+/// limits.
+struct CountExpanded<'a, R> {
+    inner: R,
+    meter: &'a Meter,
+}
+
+impl<R: Read> Read for CountExpanded<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let meter = self.meter;
+        meter.expanded.set(meter.expanded.get().saturating_add(n as u64));
+        meter.check().map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(n)
+    }
+}
 
-/// Extract the `centy-daemon` binary from a `.tar.gz` archive.
-pub fn extract_tar_gz(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
-    let decoder = flate2::read::GzDecoder::new(Cursor::new(archive_bytes));
-    let mut archive = tar::Archive::new(decoder);
+/// Extract the `centy-daemon` binary from a `.tar.gz` archive into `out`,
+/// within `limits`.
+pub fn extract_tar_gz(
+    archive: impl Read,
+    out: &mut impl Write,
+    limits: &ExtractionLimits,
+) -> Result<(), ExtractError> {
+    let meter = Meter::new(*limits);
+    let decoder = flate2::read::GzDecoder::new(CountCompressed {
+        inner: archive,
+        meter: &meter,
+    });
+    let mut archive = tar::Archive::new(CountExpanded {
+        inner: decoder,
+        meter: &meter,
+    });
 
     for entry in archive
         .entries()
-        .map_err(|e| format!("failed to read tar entries: {e}"))?
+        .map_err(|e| meter.error(format!("failed to read tar entries: {e}")))?
     {
-        let mut entry = entry.map_err(|e| format!("failed to read tar entry: {e}"))?;
+        let mut entry = entry.map_err(|e| meter.error(format!("failed to read tar entry: {e}")))?;
+        meter.entries(1)?;
         let path = entry
             .path()
-            .map_err(|e| format!("failed to read entry path: {e}"))?;
+            .map_err(|e| format!("failed to read entry path: {e}"))?
+            .into_owned();
+        // Skipped entries are read through as well, so refuse oversized ones up front
+        if entry.size() > limits.max_size {
+            return Err(ExtractError::Limit(format!(
+                "archive entry {} is larger than {} bytes",
+                path.display(),
+                limits.max_size
+            )));
+        }
 
         let file_name = path
             .file_name()
@@ -20,25 +186,29 @@ pub fn extract_tar_gz(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
             .unwrap_or("");
 
         if file_name == "centy-daemon" {
-            let mut buf = Vec::new();
-            entry
-                .read_to_end(&mut buf)
-                .map_err(|e| format!("failed to read binary from archive: {e}"))?;
-            return Ok(buf);
+            io::copy(&mut entry, out)
+                .map_err(|e| meter.error(format!("failed to read binary from archive: {e}")))?;
+            return Ok(());
         }
     }
 
-    Err("centy-daemon binary not found in tar.gz archive".to_string())
+    Err(ExtractError::Other("centy-daemon binary not found in tar.gz archive".to_string()))
 }
 
-/// Extract the `centy-daemon` binary from a `.zip` archive.
-pub fn extract_zip(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
-    let reader = Cursor::new(archive_bytes);
+/// Extract the `centy-daemon` binary from a `.zip` archive into `out`,
+/// within `limits`.
+pub fn extract_zip(
+    archive: impl Read + Seek,
+    out: &mut impl Write,
+    limits: &ExtractionLimits,
+) -> Result<(), ExtractError> {
+    let meter = Meter::new(*limits);
     let mut archive =
-        zip::ZipArchive::new(reader).map_err(|e| format!("failed to open zip archive: {e}"))?;
+        zip::ZipArchive::new(archive).map_err(|e| format!("failed to open zip archive: {e}"))?;
+    meter.entries(u64::try_from(archive.len()).unwrap_or(u64::MAX))?;
 
     for i in 0..archive.len() {
-        let mut file = archive
+        let file = archive
             .by_index(i)
             .map_err(|e| format!("failed to read zip entry: {e}"))?;
 
@@ -49,14 +219,20 @@ pub fn extract_zip(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
             .unwrap_or_default();
 
         if file_name == "centy-daemon" || file_name == "centy-daemon.exe" {
-            let mut buf = Vec::new();
-            file.read_to_end(&mut buf)
-                .map_err(|e| format!("failed to read binary from zip: {e}"))?;
-            return Ok(buf);
+            // Sizes are checked as declared first, then as actually expanded
+            meter.check_sizes(file.compressed_size(), file.size())?;
+            meter.compressed.set(file.compressed_size());
+            let mut file = CountExpanded {
+                inner: file,
+                meter: &meter,
+            };
+            io::copy(&mut file, out)
+                .map_err(|e| meter.error(format!("failed to read binary from zip: {e}")))?;
+            return Ok(());
         }
     }
 
-    Err("centy-daemon binary not found in zip archive".to_string())
+    Err(ExtractError::Other("centy-daemon binary not found in zip archive".to_string()))
 }
 
 #[cfg(test)]
@@ -68,7 +244,21 @@ pub fn extract_zip(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
 )]
 mod tests {
     use super::*;
-    use std::io::Write;
+    use std::io::Cursor;
+
+    fn extract_tar_gz_to_vec(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        extract_tar_gz(archive_bytes, &mut out, &ExtractionLimits::default())
+            .map(|()| out)
+            .map_err(|e| e.to_string())
+    }
+
+    fn extract_zip_to_vec(archive_bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        extract_zip(Cursor::new(archive_bytes), &mut out, &ExtractionLimits::default())
+            .map(|()| out)
+            .map_err(|e| e.to_string())
+    }
 
     #[test]
     fn extract_tar_gz_finds_binary() {
@@ -90,7 +280,7 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_tar_gz(&gz_bytes).unwrap();
+        let result = extract_tar_gz_to_vec(&gz_bytes).unwrap();
         assert_eq!(result, b"fake-binary-content");
     }
 
@@ -112,7 +302,7 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_tar_gz(&gz_bytes).unwrap();
+        let result = extract_tar_gz_to_vec(&gz_bytes).unwrap();
         assert_eq!(result, b"nested-binary");
     }
 
@@ -134,14 +324,14 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_tar_gz(&gz_bytes);
+        let result = extract_tar_gz_to_vec(&gz_bytes);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found in tar.gz archive"));
     }
 
     #[test]
     fn extract_tar_gz_invalid_data() {
-        let result = extract_tar_gz(b"not-a-valid-archive");
+        let result = extract_tar_gz_to_vec(b"not-a-valid-archive");
         assert!(result.is_err());
     }
 
@@ -175,10 +365,128 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_tar_gz(&gz_bytes).unwrap();
+        let result = extract_tar_gz_to_vec(&gz_bytes).unwrap();
         assert_eq!(result, b"the-binary");
     }
 
+    fn tar_gz_with_files(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar_builder = tar::Builder::new(Vec::new());
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            tar_builder.append_data(&mut header, name, *content).unwrap();
+        }
+        let tar_bytes = tar_builder.into_inner().unwrap();
+
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar_bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zip_with_files(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn limit_error(result: Result<(), ExtractError>) -> String {
+        match result {
+            Err(ExtractError::Limit(message)) => message,
+            other => panic!("expected a limit error, got {other:?}"),
+        }
+    }
+
+    const SMALL: ExtractionLimits = ExtractionLimits {
+        max_size: 4096,
+        max_entries: 3,
+        max_ratio: 100,
+    };
+
+    #[test]
+    fn extract_tar_gz_rejects_expansion_bomb() {
+        let zeros = vec![0; 16 * 1024 * 1024];
+        let gz_bytes = tar_gz_with_files(&[("centy-daemon", &zeros)]);
+
+        let mut out = Vec::new();
+        let limits = ExtractionLimits::default();
+        let err = limit_error(extract_tar_gz(&gz_bytes[..], &mut out, &limits));
+        assert_eq!(err, "archive expands to more than 100 times its compressed size");
+        assert!(out.len() < zeros.len());
+    }
+
+    #[test]
+    fn extract_tar_gz_rejects_oversized_entry() {
+        let gz_bytes =
+            tar_gz_with_files(&[("docs/readme.txt", &[b'x'; 8192]), ("centy-daemon", b"binary")]);
+        let err = limit_error(extract_tar_gz(&gz_bytes[..], &mut Vec::new(), &SMALL));
+        assert_eq!(err, "archive entry docs/readme.txt is larger than 4096 bytes");
+    }
+
+    #[test]
+    fn extract_tar_gz_rejects_expansion_over_max_size() {
+        // Each entry is within the limit, but together they are not
+        let gz_bytes = tar_gz_with_files(&[
+            ("a.txt", &[b'a'; 2000]),
+            ("b.txt", &[b'b'; 2000]),
+            ("centy-daemon", b"binary"),
+        ]);
+        let err = limit_error(extract_tar_gz(&gz_bytes[..], &mut Vec::new(), &SMALL));
+        assert_eq!(err, "archive expands to more than 4096 bytes");
+    }
+
+    #[test]
+    fn extract_tar_gz_rejects_too_many_entries() {
+        let gz_bytes = tar_gz_with_files(&[
+            ("a.txt", b"a"),
+            ("b.txt", b"b"),
+            ("c.txt", b"c"),
+            ("centy-daemon", b"binary"),
+        ]);
+        let err = limit_error(extract_tar_gz(&gz_bytes[..], &mut Vec::new(), &SMALL));
+        assert_eq!(err, "archive has more than 3 entries");
+
+        let limits = ExtractionLimits {
+            max_entries: 4,
+            ..SMALL
+        };
+        let mut out = Vec::new();
+        extract_tar_gz(&gz_bytes[..], &mut out, &limits).unwrap();
+        assert_eq!(out, b"binary");
+    }
+
+    #[test]
+    fn extract_zip_rejects_expansion_bomb() {
+        let zeros = vec![0; 16 * 1024 * 1024];
+        let zip_bytes = zip_with_files(&[("centy-daemon", &zeros)]);
+
+        let mut out = Vec::new();
+        let limits = ExtractionLimits::default();
+        let err = limit_error(extract_zip(Cursor::new(zip_bytes), &mut out, &limits));
+        assert_eq!(err, "archive expands to more than 100 times its compressed size");
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn extract_zip_rejects_oversized_binary_and_too_many_entries() {
+        let zip_bytes = zip_with_files(&[("centy-daemon", &[b'x'; 8192])]);
+        let err = limit_error(extract_zip(Cursor::new(zip_bytes), &mut Vec::new(), &SMALL));
+        assert_eq!(err, "archive expands to more than 4096 bytes");
+
+        let files: Vec<_> = ["a", "b", "c", "centy-daemon"]
+            .into_iter()
+            .map(|name| (name, &b"data"[..]))
+            .collect();
+        let zip_bytes = zip_with_files(&files);
+        let err = limit_error(extract_zip(Cursor::new(zip_bytes), &mut Vec::new(), &SMALL));
+        assert_eq!(err, "archive has more than 3 entries");
+    }
+
     fn create_zip_with_file(name: &str, content: &[u8]) -> Vec<u8> {
         let buf = Cursor::new(Vec::new());
         let mut zip = zip::ZipWriter::new(buf);
@@ -191,28 +499,28 @@ mod tests {
     #[test]
     fn extract_zip_finds_binary() {
         let zip_bytes = create_zip_with_file("centy-daemon", b"zip-binary-content");
-        let result = extract_zip(&zip_bytes).unwrap();
+        let result = extract_zip_to_vec(&zip_bytes).unwrap();
         assert_eq!(result, b"zip-binary-content");
     }
 
     #[test]
     fn extract_zip_finds_exe_binary() {
         let zip_bytes = create_zip_with_file("centy-daemon.exe", b"exe-binary-content");
-        let result = extract_zip(&zip_bytes).unwrap();
+        let result = extract_zip_to_vec(&zip_bytes).unwrap();
         assert_eq!(result, b"exe-binary-content");
     }
 
     #[test]
     fn extract_zip_missing_binary() {
         let zip_bytes = create_zip_with_file("other-file.txt", b"not the binary");
-        let result = extract_zip(&zip_bytes);
+        let result = extract_zip_to_vec(&zip_bytes);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found in zip archive"));
     }
 
     #[test]
     fn extract_zip_invalid_data() {
-        let result = extract_zip(b"not-a-valid-zip");
+        let result = extract_zip_to_vec(b"not-a-valid-zip");
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("failed to open zip archive"));
     }
@@ -232,7 +540,7 @@ mod tests {
         zip.write_all(b"the-binary").unwrap();
 
         let zip_bytes = zip.finish().unwrap().into_inner();
-        let result = extract_zip(&zip_bytes).unwrap();
+        let result = extract_zip_to_vec(&zip_bytes).unwrap();
         assert_eq!(result, b"the-binary");
     }
 }
@@ -1,180 +1,579 @@
-use reqwest::blocking::Client;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{AUTHORIZATION, LINK, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use crate::config::SourceConfig;
+use crate::digest::{Digest, DigestAlgorithm};
 use crate::platform::Platform;
+use crate::provenance::PROVENANCE_EXT;
+use crate::receipt;
+use crate::retry::{self, Failure, Retrier, RetryPolicy};
+use crate::source::{
+    self, ApiError, Checksum, ReleaseInfo, RemoteAsset, RemoteRelease, VersionInfo,
+};
+use crate::version::{self, Requested};
 
-const REPO: &str = "centy-io/centy-daemon";
+/// Environment variables checked, in order, for a GitHub token.
+const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
 
-#[derive(Debug)]
-pub struct VersionInfo {
-    pub tag: String,
-    pub notice: Option<String>,
-}
+/// Host of the public GitHub API, always trusted with a token from the
+/// environment.
+const GITHUB_API_HOST: &str = "api.github.com";
 
-pub struct ReleaseInfo {
-    /// Retained for consumers that need the resolved tag (e.g. for display/logging).
-    #[allow(
-        dead_code,
-        reason = "retained for consumers that need the resolved tag (e.g. for display/logging)"
-    )]
-    pub tag: String,
-    pub asset_url: String,
-    pub checksums_url: String,
-    pub asset_name: String,
-}
+/// Most pages of releases fetched by [`list_releases_from`], in case a
+/// server keeps linking to further pages.
+const MAX_PAGES: usize = 100;
 
 /// Resolve the version tag to use.
 ///
+/// A full `version` pins that release, which must exist. It is looked up on
+/// its own; only when it is missing is the release list fetched, so the
+/// error can suggest the closest published versions. A semver range such as `^0.4` or
+/// `>=0.3,<0.5` resolves to the highest matching release, including
+/// pre-releases only when `prerelease` is set.
+///
 /// When no `version` is specified:
 /// - `prerelease = false` (default): fetches the latest stable release via `/releases/latest`
 /// - `prerelease = true`: fetches all releases (including pre-releases) and picks the first one
 ///
 /// When a stable version is resolved, an additional check is made for a newer pre-release
 /// and a notification is included in the returned `VersionInfo` if one exists.
-pub fn resolve_version(
-    client: &Client,
-    version: Option<&str>,
-    prerelease: bool,
-) -> Result<VersionInfo, String> {
-    resolve_version_from(client, version, prerelease, "https://api.github.com")
-}
-
+///
+/// Releases are looked up through the API of `source`. Transient API failures
+/// are retried according to `retry`. Requests are authenticated with `token`
+/// when one is given.
 pub fn resolve_version_from(
     client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
     version: Option<&str>,
     prerelease: bool,
-    api_base: &str,
-) -> Result<VersionInfo, String> {
+    source: &SourceConfig,
+) -> Result<VersionInfo, ApiError> {
+    let releases_url = format!("{}/releases", repo_api_url(source));
     if let Some(v) = version {
-        let tag = if v.starts_with('v') {
-            v.to_string()
-        } else {
-            format!("v{v}")
+        let tag = match version::parse_requested(v) {
+            Requested::Tag(tag) => find_pinned_tag(client, retry, token, &tag, source)?,
+            Requested::Range(range) => {
+                let releases = list_releases_from(client, retry, token, source)?;
+                let published = releases.iter().filter(|r| !r.draft);
+                let releases = published.map(|r| (r.tag.as_str(), r.prerelease));
+                version::resolve_range(v, &range, releases, prerelease)?
+            }
         };
         return Ok(VersionInfo { tag, notice: None });
     }
 
     if prerelease {
         // Fetch all releases and pick the first one (most recent, includes pre-releases)
-        let url = format!("{api_base}/repos/{REPO}/releases");
-        let tag = fetch_tag_from_releases_array(client, &url)?;
+        let tag = fetch_tag_from_releases_array(client, retry, token, &releases_url)?;
         return Ok(VersionInfo { tag, notice: None });
     }
 
     // Default: fetch the latest stable (non-pre-release) version
-    let url = format!("{api_base}/repos/{REPO}/releases/latest");
-    let resp = client
-        .get(&url)
-        .header("User-Agent", "centy-installer")
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .map_err(|e| format!("failed to fetch latest release: {e}"))?;
-
-    if !resp.status().is_success() {
-        return Err(format!("GitHub API returned {}", resp.status()));
-    }
-
-    let text = resp
-        .text()
-        .map_err(|e| format!("failed to read response body: {e}"))?;
+    let url = format!("{releases_url}/latest");
+    let text = fetch_text(client, retry, token, &url, "failed to fetch latest release")?;
     let body: serde_json::Value = serde_json::from_str(&text)
         .map_err(|e| format!("failed to parse release JSON: {e}"))?;
 
     let tag = body
         .get("tag_name")
         .and_then(serde_json::Value::as_str)
-        .ok_or("no stable release found")?
+        .ok_or_else(|| "no stable release found".to_string())?
         .to_string();
 
     // Check if a newer pre-release is available and notify the user
-    let notice = check_prerelease_notice(client, api_base, &tag);
+    let notice = check_prerelease_notice(client, token, &releases_url, &tag);
 
     Ok(VersionInfo { tag, notice })
 }
 
-/// Fetch the tag name from the first entry of a releases array endpoint.
-fn fetch_tag_from_releases_array(client: &Client, url: &str) -> Result<String, String> {
-    let resp = client
-        .get(url)
-        .header("User-Agent", "centy-installer")
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .map_err(|e| format!("failed to fetch releases: {e}"))?;
+/// Check that release `tag` is published, with a single request for it.
+///
+/// Only when it is missing is the whole release list fetched, to suggest the
+/// closest published versions.
+fn find_pinned_tag(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    tag: &str,
+    source: &SourceConfig,
+) -> Result<String, ApiError> {
+    let url = format!("{}/releases/tags/{tag}", repo_api_url(source));
+    match fetch_text(client, retry, token, &url, "failed to fetch release") {
+        Ok(text) => {
+            let body: serde_json::Value = serde_json::from_str(&text)
+                .map_err(|e| format!("failed to parse release JSON: {e}"))?;
+            if !is_draft(&body) {
+                return Ok(tag.to_string());
+            }
+        }
+        Err(ApiError::NotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    let releases = list_releases_from(client, retry, token, source)?;
+    let published = releases.iter().filter(|r| !r.draft).map(|r| r.tag.as_str());
+    Ok(version::find_tag(tag, published)?)
+}
 
-    if !resp.status().is_success() {
-        return Err(format!("GitHub API returned {}", resp.status()));
+/// API URL of the repository publishing the releases.
+fn repo_api_url(source: &SourceConfig) -> String {
+    format!("{}/repos/{}/{}", source.api_base, source.owner, source.name)
+}
+
+/// Return the GitHub token from `GITHUB_TOKEN` or `GH_TOKEN`, if either is set.
+pub fn token_from_env() -> Option<String> {
+    TOKEN_VARS
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+/// Choose the token to authenticate requests to the API at `source.api_base`.
+///
+/// An explicit `token` is sent to whatever API is configured, but never over
+/// plain HTTP. A token from the environment is meant for github.com, so it is
+/// only sent over HTTPS to `api.github.com` or one of `source.token_hosts`;
+/// other APIs, such as mirrors, get anonymous requests.
+pub fn api_token(token: Option<String>, source: &SourceConfig) -> Result<Option<String>, String> {
+    if token.is_some() {
+        return match https_host(&source.api_base) {
+            Some(_) => Ok(token),
+            None => Err(format!(
+                "refusing to send the GitHub token to {} without HTTPS",
+                source.api_base
+            )),
+        };
     }
+    Ok(trusts_env_token(source).then(token_from_env).flatten())
+}
 
-    let text = resp
-        .text()
-        .map_err(|e| format!("failed to read response body: {e}"))?;
+/// Whether a GitHub token from the environment may be sent to
+/// `source.api_base`.
+fn trusts_env_token(source: &SourceConfig) -> bool {
+    https_host(&source.api_base).is_some_and(|host| {
+        host == GITHUB_API_HOST
+            || source.token_hosts.iter().any(|trusted| trusted.eq_ignore_ascii_case(&host))
+    })
+}
+
+/// Lowercase host of an `https://` URL, without port or credentials; `None`
+/// for any other scheme.
+fn https_host(url: &str) -> Option<String> {
+    let scheme = url.get(..8).filter(|scheme| scheme.eq_ignore_ascii_case("https://"))?;
+    let authority = url
+        .get(scheme.len()..)?
+        .split(['/', '?', '#'])
+        .next()?
+        .rsplit('@')
+        .next()?;
+    let host = authority.rsplit_once(':').map_or(authority, |(host, _)| host);
+    Some(host.to_ascii_lowercase()).filter(|host| !host.is_empty())
+}
+
+/// Turn a user-supplied version such as `0.1.6` into a release tag (`v0.1.6`).
+pub fn normalize_tag(version: &str) -> String {
+    if version.starts_with('v') {
+        version.to_string()
+    } else {
+        format!("v{version}")
+    }
+}
+
+/// List every release of `source`, newest first and drafts included,
+/// following the `Link` headers of the paginated API.
+pub fn list_releases_from(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    source: &SourceConfig,
+) -> Result<Vec<RemoteRelease>, ApiError> {
+    let mut url = Some(format!("{}/releases?per_page=100", repo_api_url(source)));
+    let mut releases = Vec::new();
+
+    for _ in 0..MAX_PAGES {
+        let Some(page_url) = url.take() else {
+            return Ok(releases);
+        };
+        let (text, next) = fetch_page(client, retry, token, &page_url, "failed to fetch releases")?;
+        let body: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| format!("failed to parse releases JSON: {e}"))?;
+        let page = body
+            .as_array()
+            .ok_or_else(|| "failed to parse releases JSON: expected an array".to_string())?;
+
+        releases.extend(page.iter().filter_map(parse_release));
+        url = next;
+    }
+
+    if url.is_some() {
+        return Err(format!("release list is longer than {MAX_PAGES} pages").into());
+    }
+    Ok(releases)
+}
+
+/// Parse an entry of the releases API, or return `None` if it has no tag.
+fn parse_release(release: &serde_json::Value) -> Option<RemoteRelease> {
+    let string = |value: &serde_json::Value, name: &str| {
+        value.get(name)?.as_str().map(String::from)
+    };
+    let assets = release
+        .get("assets")
+        .and_then(serde_json::Value::as_array)
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| {
+                    Some(RemoteAsset {
+                        name: string(asset, "name")?,
+                        url: string(asset, "browser_download_url")?,
+                        size: asset.get("size").and_then(serde_json::Value::as_u64),
+                        digest: string(asset, "digest"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(RemoteRelease {
+        tag: string(release, "tag_name")?,
+        prerelease: release.get("prerelease").and_then(serde_json::Value::as_bool) == Some(true),
+        draft: is_draft(release),
+        published_at: string(release, "published_at"),
+        notes: string(release, "body").filter(|body| !body.trim().is_empty()),
+        assets,
+    })
+}
+
+/// Return the target of the `rel="next"` link in a `Link` header.
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let (target, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| target.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+
+/// Fetch the tag name from the first published entry of a releases array
+/// endpoint. Drafts are skipped.
+fn fetch_tag_from_releases_array(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    url: &str,
+) -> Result<String, ApiError> {
+    let text = fetch_text(client, retry, token, url, "failed to fetch releases")?;
     let body: serde_json::Value = serde_json::from_str(&text)
         .map_err(|e| format!("failed to parse releases JSON: {e}"))?;
 
     body.as_array()
-        .and_then(|releases| releases.first())
+        .and_then(|releases| releases.iter().find(|r| !is_draft(r)))
         .and_then(|r| r["tag_name"].as_str())
-        .ok_or_else(|| "no releases found".to_string())
         .map(String::from)
+        .ok_or_else(|| ApiError::from("no releases found".to_string()))
+}
+
+/// Check if a published pre-release is newer than the resolved stable tag,
+/// and return a user-facing notice about the newest one if so.
+///
+/// Versions are compared as semver, so an older pre-release, a draft, or a
+/// tag that is not a version never triggers the notice.
+fn check_prerelease_notice(
+    client: &Client,
+    token: Option<&str>,
+    releases_url: &str,
+    stable_tag: &str,
+) -> Option<String> {
+    // The notice is optional, so a flaky API is not worth delaying the install for
+    let no_retry = Retrier::quiet(RetryPolicy::none());
+    let text =
+        fetch_text(client, &no_retry, token, releases_url, "failed to fetch releases").ok()?;
+    let body: serde_json::Value = serde_json::from_str(&text).ok()?;
+
+    let prereleases = body
+        .as_array()?
+        .iter()
+        .filter(|r| !is_draft(r))
+        .filter_map(|r| {
+            let tag = r.get("tag_name")?.as_str()?;
+            let flagged = r.get("prerelease").and_then(serde_json::Value::as_bool);
+            let prerelease = flagged == Some(true)
+                || version::parse_tag(tag).is_some_and(|v| !v.pre.is_empty());
+            prerelease.then_some(tag)
+        });
+
+    version::newer_prerelease(stable_tag, prereleases).map(source::prerelease_notice)
+}
+
+/// Whether a release entry of the API is an unpublished draft.
+fn is_draft(release: &serde_json::Value) -> bool {
+    release.get("draft").and_then(serde_json::Value::as_bool) == Some(true)
+}
+
+/// GET a GitHub API `url` and return the response body, retrying transient
+/// failures. `context` prefixes the error when the request cannot be sent.
+fn fetch_text(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    url: &str,
+    context: &str,
+) -> Result<String, ApiError> {
+    fetch_page(client, retry, token, url, context).map(|(text, _)| text)
+}
+
+/// Like [`fetch_text`], also returning the URL of the next page of results
+/// from the `Link` header.
+fn fetch_page(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    url: &str,
+    context: &str,
+) -> Result<(String, Option<String>), ApiError> {
+    let mut rate_limited = None;
+    let mut not_found = false;
+    let result = retry::retry(retry, || {
+        let mut request = client
+            .get(url)
+            .header("User-Agent", "centy-installer")
+            .header("Accept", "application/vnd.github+json");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let resp = request
+            .send()
+            .map_err(|e| Failure::request(&e, format!("{context}: {e}")))?;
+
+        if resp.status().is_success() {
+            let next = resp
+                .headers()
+                .get(LINK)
+                .and_then(|value| value.to_str().ok())
+                .and_then(next_link);
+            return resp
+                .text()
+                .map(|text| (text, next))
+                .map_err(|e| Failure::transient(format!("failed to read response body: {e}")));
+        }
+
+        if let Some(message) = rate_limit_message(&resp, token.is_some()) {
+            rate_limited = Some(message.clone());
+            return Err(Failure::Permanent(message));
+        }
+
+        let message = format!("GitHub API returned {}", resp.status());
+        not_found = resp.status() == StatusCode::NOT_FOUND;
+        // Secondary rate limits come as a 403 asking to retry after a delay
+        if resp.status() == StatusCode::FORBIDDEN && resp.headers().contains_key(RETRY_AFTER) {
+            return Err(Failure::transient(message));
+        }
+        Err(Failure::status(&resp, message))
+    });
+
+    result.map_err(|message| match rate_limited {
+        Some(message) => ApiError::RateLimited(message),
+        None if not_found => ApiError::NotFound(message),
+        None => ApiError::Other(message),
+    })
 }
 
-/// Check if the most recent release on GitHub is a pre-release newer than the
-/// resolved stable tag, and return a user-facing notice if so.
-fn check_prerelease_notice(client: &Client, api_base: &str, stable_tag: &str) -> Option<String> {
-    let url = format!("{api_base}/repos/{REPO}/releases");
-    let resp = client
-        .get(&url)
-        .header("User-Agent", "centy-installer")
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .ok()?;
-
-    if !resp.status().is_success() {
+/// Describe an exhausted primary rate limit, from the `X-RateLimit-*` headers
+/// GitHub sends with a `403` or `429`, or return `None` for other errors.
+fn rate_limit_message(resp: &Response, authenticated: bool) -> Option<String> {
+    let status = resp.status();
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
         return None;
     }
 
-    let text = resp.text().ok()?;
-    let body: serde_json::Value = serde_json::from_str(&text).ok()?;
-
-    let latest_tag = body.as_array()?.first()?["tag_name"].as_str()?;
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    if header("x-ratelimit-remaining")? != 0 {
+        return None;
+    }
 
-    if latest_tag == stable_tag {
-        None
-    } else {
-        Some(format!(
-            "Note: Pre-release {latest_tag} is available. Use --pre to install it."
-        ))
+    let mut message = header("x-ratelimit-limit").map_or_else(
+        || "GitHub API rate limit exceeded".to_string(),
+        |limit| format!("GitHub API rate limit of {limit} requests exceeded"),
+    );
+    if let Some(reset) = header("x-ratelimit-reset") {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let minutes = reset.saturating_sub(now).div_ceil(60);
+        message = format!(
+            "{message}; it resets at {} (in {minutes} min)",
+            receipt::format_rfc3339(reset)
+        );
+    }
+    if !authenticated {
+        message.push_str("; set GITHUB_TOKEN or GH_TOKEN to raise the limit");
     }
+    Some(message)
 }
 
-/// Build release info (download URLs) for the given version tag and platform.
-pub fn release_info(tag: &str, platform: &Platform) -> ReleaseInfo {
-    let asset_name = format!(
-        "centy-daemon-{tag}-{}{}",
-        platform.target, platform.archive_ext
-    );
-    let base = format!("https://github.com/{REPO}/releases/download/{tag}");
+/// Look up release `tag` and describe its asset for `platform`, from the
+/// `assets` list of the release.
+pub fn release_info(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<&str>,
+    tag: &str,
+    platform: &Platform,
+    source: &SourceConfig,
+) -> Result<ReleaseInfo, ApiError> {
+    let url = format!("{}/releases/tags/{tag}", repo_api_url(source));
+    let text = fetch_text(client, retry, token, &url, "failed to fetch release")?;
+    let body: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| format!("failed to parse release JSON: {e}"))?;
+    let release =
+        parse_release(&body).ok_or_else(|| "release JSON has no tag_name".to_string())?;
+
+    release_info_from(&release, platform, source)
+}
+
+/// Pick the asset of `release` built for `platform`: the archive whose name
+/// holds the target triple and ends with the archive extension.
+///
+/// Its published `sha256` digest is used as the checksum when there is one,
+/// the release checksums file otherwise. Unless signatures are off, only the
+/// checksums file is used, as it is the one carrying a signature. The
+/// checksums file is `checksums-<algorithm>.txt`, of the configured
+/// `checksum_algorithm` or of the first supported algorithm published; the
+/// `sha256` digest is not used when another algorithm is configured.
+fn release_info_from(
+    release: &RemoteRelease,
+    platform: &Platform,
+    source: &SourceConfig,
+) -> Result<ReleaseInfo, ApiError> {
+    let tag = &release.tag;
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| is_platform_asset(&asset.name, platform))
+        .ok_or_else(|| missing_asset_message(release, platform))?;
+
+    let configured = source.checksum_algorithm;
+    let algorithms = DigestAlgorithm::ALL
+        .into_iter()
+        .filter(|algorithm| configured.is_none_or(|configured| configured == *algorithm));
+    let checksums = algorithms
+        .clone()
+        .find_map(|algorithm| {
+            let name = algorithm.checksums_file();
+            release.assets.iter().find(|asset| asset.name == name)
+        })
+        .map(|checksums| Checksum::File(download_url(checksums, tag, source)));
+    let checksums_names = algorithms
+        .map(DigestAlgorithm::checksums_file)
+        .collect::<Vec<_>>()
+        .join(" or ");
+    // Only the checksums file is signed, so it is required unless signatures are off
+    if source.verify_signatures {
+        return checksums.map(|checksum| release_asset(release, asset, checksum, source)).ok_or_else(
+            || ApiError::Signature(format!("release {tag} publishes no signed {checksums_names}")),
+        );
+    }
+
+    let digest = asset
+        .digest
+        .as_deref()
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+        .filter(|_| configured.is_none_or(|configured| configured == DigestAlgorithm::Sha256))
+        .map(|hash| Checksum::Digest(Digest::sha256(hash)));
+    let checksum = digest.or(checksums).ok_or_else(|| {
+        format!(
+            "release {tag} publishes neither a digest for {} nor {checksums_names}",
+            asset.name
+        )
+    })?;
+    Ok(release_asset(release, asset, checksum, source))
+}
 
+/// Describe `asset` of `release`, along with the SLSA provenance of the
+/// release when it publishes one.
+fn release_asset(
+    release: &RemoteRelease,
+    asset: &RemoteAsset,
+    checksum: Checksum,
+    source: &SourceConfig,
+) -> ReleaseInfo {
+    let tag = &release.tag;
+    let provenance = release
+        .assets
+        .iter()
+        .find(|asset| asset.name.ends_with(PROVENANCE_EXT))
+        .map(|provenance| download_url(provenance, tag, source));
     ReleaseInfo {
-        tag: tag.to_string(),
-        asset_url: format!("{base}/{asset_name}"),
-        checksums_url: format!("{base}/checksums-sha256.txt"),
-        asset_name,
+        tag: tag.clone(),
+        asset_url: download_url(asset, tag, source),
+        asset_name: asset.name.clone(),
+        size: asset.size,
+        checksum,
+        provenance,
     }
 }
 
-/// Parse checksums-sha256.txt and return the expected hash for the given asset name.
-pub fn parse_checksum(checksums_text: &str, asset_name: &str) -> Result<String, String> {
-    for line in checksums_text.lines() {
-        // Format: "<hash>  <filename>" or "<hash> <filename>"
-        if let Some((hash, filename)) = line.split_once(char::is_whitespace) {
-            if filename.trim() == asset_name {
-                return Ok(hash.to_string());
-            }
-        }
+fn is_platform_asset(name: &str, platform: &Platform) -> bool {
+    name.contains(platform.target) && name.ends_with(platform.archive_ext)
+}
+
+/// Explain that `release` has no asset for `platform`, listing the
+/// platforms it does have assets for.
+fn missing_asset_message(release: &RemoteRelease, platform: &Platform) -> String {
+    let available: Vec<_> = crate::platform::SUPPORTED
+        .iter()
+        .filter(|supported| {
+            release
+                .assets
+                .iter()
+                .any(|asset| is_platform_asset(&asset.name, supported))
+        })
+        .map(|supported| supported.target)
+        .collect();
+
+    let message = format!("release {} has no asset for {}", release.tag, platform.target);
+    if available.is_empty() {
+        format!("{message}, nor for any other supported platform")
+    } else {
+        format!("{message}; available platforms: {}", available.join(", "))
     }
-    Err(format!(
-        "checksum not found for {asset_name} in checksums file"
-    ))
+}
+
+/// URL to download `asset` from: its `browser_download_url`, or the same
+/// path on the mirror when a custom `download_base` is configured.
+fn download_url(asset: &RemoteAsset, tag: &str, source: &SourceConfig) -> String {
+    if source.download_base == SourceConfig::default().download_base {
+        return asset.url.clone();
+    }
+    format!(
+        "{}/{}/{}/releases/download/{tag}/{}",
+        source.download_base, source.owner, source.name, asset.name
+    )
+}
+
+/// Recover the tag from a release asset name,
+/// `centy-daemon-<tag>-<target>.tar.gz` or `.zip`, built for `target`.
+pub fn tag_from_asset_name(asset_name: &str, target: &str) -> Option<String> {
+    let stem = asset_name
+        .strip_suffix(".tar.gz")
+        .or_else(|| asset_name.strip_suffix(".zip"))?;
+    stem.strip_prefix("centy-daemon-")?
+        .strip_suffix(target)?
+        .strip_suffix('-')
+        .filter(|tag| !tag.is_empty())
+        .map(normalize_tag)
 }
 
 #[cfg(test)]
@@ -187,60 +586,53 @@ pub fn parse_checksum(checksums_text: &str, asset_name: &str) -> Result<String,
 )]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
-    #[test]
-    fn parse_checksum_found() {
-        let checksums = "\
-abc123  centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz
-def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
-";
-        let hash =
-            parse_checksum(checksums, "centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz").unwrap();
-        assert_eq!(hash, "abc123");
-    }
-
-    #[test]
-    fn parse_checksum_second_entry() {
-        let checksums = "\
-abc123  centy-daemon-0.1.0-aarch64-apple-darwin.tar.gz
-def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
-";
-        let hash = parse_checksum(
-            checksums,
-            "centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz",
-        )
-        .unwrap();
-        assert_eq!(hash, "def456");
-    }
-
-    #[test]
-    fn parse_checksum_single_space_separator() {
-        let checksums = "abc123 my-asset.tar.gz\n";
-        let hash = parse_checksum(checksums, "my-asset.tar.gz").unwrap();
-        assert_eq!(hash, "abc123");
+    /// The default source, with signatures off so that unsigned test
+    /// releases are accepted.
+    fn github_source() -> SourceConfig {
+        SourceConfig {
+            verify_signatures: false,
+            ..SourceConfig::default()
+        }
     }
 
-    #[test]
-    fn parse_checksum_not_found() {
-        let checksums = "abc123  other-file.tar.gz\n";
-        let result = parse_checksum(checksums, "missing.tar.gz");
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .contains("checksum not found for missing.tar.gz"));
+    fn source_at(api_base: &str) -> SourceConfig {
+        SourceConfig {
+            api_base: api_base.to_string(),
+            ..github_source()
+        }
     }
 
-    #[test]
-    fn parse_checksum_empty_input() {
-        let result = parse_checksum("", "anything.tar.gz");
-        assert!(result.is_err());
+    /// A release with `names` as assets, downloadable from github.com.
+    fn github_release(tag: &str, names: &[&str]) -> RemoteRelease {
+        let assets = names
+            .iter()
+            .map(|name| RemoteAsset {
+                name: (*name).to_string(),
+                url: format!(
+                    "https://github.com/centy-io/centy-daemon/releases/download/{tag}/{name}"
+                ),
+                size: Some(1024),
+                digest: None,
+            })
+            .collect();
+        RemoteRelease {
+            tag: tag.to_string(),
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            notes: None,
+            assets,
+        }
     }
 
-    #[test]
-    fn parse_checksum_blank_lines() {
-        let checksums = "\n\nabc123  target.tar.gz\n\n";
-        let hash = parse_checksum(checksums, "target.tar.gz").unwrap();
-        assert_eq!(hash, "abc123");
+    fn fast_retry() -> Retrier {
+        Retrier::quiet(RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::from_secs(1),
+        })
     }
 
     #[test]
@@ -249,7 +641,16 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             target: "aarch64-apple-darwin",
             archive_ext: ".tar.gz",
         };
-        let info = release_info("v0.2.0", &platform);
+        let release = github_release(
+            "v0.2.0",
+            &[
+                "centy-daemon-v0.2.0-x86_64-apple-darwin.tar.gz",
+                "centy-daemon-v0.2.0-aarch64-apple-darwin.tar.gz",
+                "checksums-sha256.txt",
+                "multiple.intoto.jsonl",
+            ],
+        );
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
         assert_eq!(
             info.asset_name,
             "centy-daemon-v0.2.0-aarch64-apple-darwin.tar.gz"
@@ -259,8 +660,17 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             "https://github.com/centy-io/centy-daemon/releases/download/v0.2.0/centy-daemon-v0.2.0-aarch64-apple-darwin.tar.gz"
         );
         assert_eq!(
-            info.checksums_url,
-            "https://github.com/centy-io/centy-daemon/releases/download/v0.2.0/checksums-sha256.txt"
+            info.checksum,
+            Checksum::File(
+                "https://github.com/centy-io/centy-daemon/releases/download/v0.2.0/checksums-sha256.txt"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            info.provenance.as_deref(),
+            Some(
+                "https://github.com/centy-io/centy-daemon/releases/download/v0.2.0/multiple.intoto.jsonl"
+            )
         );
         assert_eq!(info.tag, "v0.2.0");
     }
@@ -271,11 +681,16 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             target: "x86_64-unknown-linux-gnu",
             archive_ext: ".tar.gz",
         };
-        let info = release_info("1.0.0", &platform);
+        let release = github_release(
+            "1.0.0",
+            &["centy-daemon-1.0.0-x86_64-unknown-linux-gnu.tar.gz", "checksums-sha256.txt"],
+        );
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
         assert_eq!(
             info.asset_name,
             "centy-daemon-1.0.0-x86_64-unknown-linux-gnu.tar.gz"
         );
+        assert_eq!(info.provenance, None);
         assert_eq!(
             info.asset_url,
             "https://github.com/centy-io/centy-daemon/releases/download/1.0.0/centy-daemon-1.0.0-x86_64-unknown-linux-gnu.tar.gz"
@@ -288,7 +703,11 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             target: "x86_64-unknown-linux-gnu",
             archive_ext: ".tar.gz",
         };
-        let info = release_info("v1.0.0", &platform);
+        let release = github_release(
+            "v1.0.0",
+            &["centy-daemon-v1.0.0-x86_64-unknown-linux-gnu.tar.gz", "checksums-sha256.txt"],
+        );
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
         assert_eq!(
             info.asset_name,
             "centy-daemon-v1.0.0-x86_64-unknown-linux-gnu.tar.gz"
@@ -305,26 +724,307 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             target: "x86_64-pc-windows-msvc",
             archive_ext: ".zip",
         };
-        let info = release_info("v0.3.0", &platform);
+        let release = github_release(
+            "v0.3.0",
+            &[
+                "centy-daemon-v0.3.0-x86_64-unknown-linux-gnu.tar.gz",
+                "centy-daemon-v0.3.0-x86_64-pc-windows-msvc.zip",
+                "checksums-sha256.txt",
+            ],
+        );
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
         assert_eq!(
             info.asset_name,
             "centy-daemon-v0.3.0-x86_64-pc-windows-msvc.zip"
         );
     }
 
+    #[test]
+    fn release_info_matches_renamed_assets_and_prefers_digest() {
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let mut release = github_release(
+            "v0.4.0",
+            &[
+                "centy-daemon-x86_64-unknown-linux-gnu.tar.gz.minisig",
+                "centy-daemon-x86_64-unknown-linux-gnu.tar.gz",
+                "checksums-sha256.txt",
+            ],
+        );
+        let asset = release.assets.get_mut(1).unwrap();
+        asset.digest = Some("sha256:ABCDEF".to_string());
+
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
+        assert_eq!(info.asset_name, "centy-daemon-x86_64-unknown-linux-gnu.tar.gz");
+        assert_eq!(info.size, Some(1024));
+        assert_eq!(info.checksum, Checksum::Digest(Digest::sha256("abcdef")));
+    }
+
+    #[test]
+    fn release_info_lists_available_platforms() {
+        let platform = Platform {
+            target: "aarch64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let release = github_release(
+            "v0.4.0",
+            &[
+                "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                "centy-daemon-v0.4.0-aarch64-apple-darwin.tar.gz",
+                "checksums-sha256.txt",
+            ],
+        );
+        let err = release_info_from(&release, &platform, &github_source()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "release v0.4.0 has no asset for aarch64-unknown-linux-gnu; \
+             available platforms: aarch64-apple-darwin, x86_64-unknown-linux-gnu"
+        );
+
+        let release = github_release("v0.4.0", &["checksums-sha256.txt"]);
+        let err = release_info_from(&release, &platform, &github_source()).unwrap_err();
+        assert!(err.to_string().ends_with("nor for any other supported platform"));
+    }
+
+    #[test]
+    fn release_info_requires_a_checksum() {
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let release =
+            github_release("v0.4.0", &["centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz"]);
+        let err = release_info_from(&release, &platform, &github_source()).unwrap_err();
+        assert!(err.to_string().contains("publishes neither a digest"));
+    }
+
+    #[test]
+    fn release_info_uses_signed_checksums_when_signatures_are_on() {
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let source = SourceConfig {
+            trusted_keys: vec![crate::signature::tests::KEY.to_string()],
+            verify_signatures: true,
+            ..github_source()
+        };
+        let mut release = github_release(
+            "v0.4.0",
+            &["centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz", "checksums-sha256.txt"],
+        );
+        release.assets.get_mut(0).unwrap().digest = Some("sha256:abcdef".to_string());
+
+        let info = release_info_from(&release, &platform, &source).unwrap();
+        assert_eq!(
+            info.checksum,
+            Checksum::File(
+                "https://github.com/centy-io/centy-daemon/releases/download/v0.4.0/checksums-sha256.txt"
+                    .to_string()
+            )
+        );
+
+        release.assets.truncate(1);
+        let err = release_info_from(&release, &platform, &source).unwrap_err();
+        assert!(matches!(err, ApiError::Signature(_)), "{err}");
+    }
+
+    #[test]
+    fn release_info_picks_checksums_of_configured_algorithm() {
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let mut release = github_release(
+            "v0.4.0",
+            &[
+                "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                "checksums-sha512.txt",
+                "checksums-blake3.txt",
+            ],
+        );
+        release.assets.get_mut(0).unwrap().digest = Some("sha256:abcdef".to_string());
+        let checksums = |name: &str| {
+            Checksum::File(format!(
+                "https://github.com/centy-io/centy-daemon/releases/download/v0.4.0/{name}"
+            ))
+        };
+
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
+        assert_eq!(info.checksum, Checksum::Digest(Digest::sha256("abcdef")));
+
+        let source = SourceConfig {
+            checksum_algorithm: Some(DigestAlgorithm::Blake3),
+            ..github_source()
+        };
+        let info = release_info_from(&release, &platform, &source).unwrap();
+        assert_eq!(info.checksum, checksums("checksums-blake3.txt"));
+
+        release.assets.get_mut(0).unwrap().digest = None;
+        let info = release_info_from(&release, &platform, &github_source()).unwrap();
+        assert_eq!(info.checksum, checksums("checksums-sha512.txt"));
+
+        release.assets.truncate(1);
+        let err = release_info_from(&release, &platform, &source).unwrap_err();
+        assert!(err.to_string().ends_with("nor checksums-blake3.txt"), "{err}");
+    }
+
+    #[test]
+    fn release_info_fetches_release_by_tag() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/tags/v0.4.0")
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "tag_name": "v0.4.0",
+                    "assets": [{
+                        "name": "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                        "browser_download_url": "https://objects.example.com/linux.tar.gz",
+                        "size": 2048,
+                        "digest": "sha256:0123abcd"
+                    }]
+                }"#,
+            )
+            .create();
+
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let source = source_at(&server.url());
+        let info = release_info(&Client::new(), &fast_retry(), None, "v0.4.0", &platform, &source)
+            .unwrap();
+        assert_eq!(info.asset_url, "https://objects.example.com/linux.tar.gz");
+        assert_eq!(info.size, Some(2048));
+        assert_eq!(info.checksum, Checksum::Digest(Digest::sha256("0123abcd")));
+        mock.assert();
+    }
+
+    #[test]
+    fn tag_from_asset_name_matches_target() {
+        let linux = "x86_64-unknown-linux-gnu";
+        let windows = "x86_64-pc-windows-msvc";
+        let tag = |name: &str, target: &str| tag_from_asset_name(name, target);
+
+        assert_eq!(
+            tag("centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz", linux).as_deref(),
+            Some("v0.4.0")
+        );
+        assert_eq!(
+            tag("centy-daemon-0.5.0-rc.1-x86_64-pc-windows-msvc.zip", windows).as_deref(),
+            Some("v0.5.0-rc.1")
+        );
+        assert!(tag("centy-daemon-v0.4.0-aarch64-apple-darwin.tar.gz", linux).is_none());
+        assert!(tag("centy-daemon-x86_64-unknown-linux-gnu.tar.gz", linux).is_none());
+        assert!(tag("daemon.tar.gz", linux).is_none());
+    }
+
+    /// Serve a release list holding `tags` on a mock GitHub API.
+    fn releases_server(tags: &[&str]) -> (mockito::ServerGuard, mockito::Mock) {
+        let mut server = mockito::Server::new();
+        let body: Vec<_> = tags.iter().map(|tag| format!(r#"{{"tag_name": "{tag}"}}"#)).collect();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_query(mockito::Matcher::UrlEncoded("per_page".into(), "100".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}]", body.join(",")))
+            .create();
+        (server, mock)
+    }
+
+    /// Serve release `tag` on `server`, as `/releases/tags/{tag}` does.
+    fn tag_mock(server: &mut mockito::ServerGuard, tag: &str, body: &str) -> mockito::Mock {
+        server
+            .mock("GET", format!("/repos/centy-io/centy-daemon/releases/tags/{tag}").as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create()
+    }
+
     #[test]
     fn resolve_version_with_v_prefix() {
-        let client = Client::new();
-        let info = resolve_version(&client, Some("v1.0.0"), false).unwrap();
+        let (mut server, list) = releases_server(&["v1.1.0", "v1.0.0"]);
+        let list = list.expect(0);
+        let release = tag_mock(&mut server, "v1.0.0", r#"{"tag_name": "v1.0.0"}"#);
+        let info = resolve_version_from(
+            &Client::new(),
+            &fast_retry(),
+            None,
+            Some("v1.0.0"),
+            false,
+            &source_at(&server.url()),
+        )
+        .unwrap();
         assert_eq!(info.tag, "v1.0.0");
         assert!(info.notice.is_none());
+        release.assert();
+        list.assert();
     }
 
     #[test]
     fn resolve_version_without_v_prefix() {
-        let client = Client::new();
-        let info = resolve_version(&client, Some("1.0.0"), false).unwrap();
+        let (mut server, list) = releases_server(&["v1.1.0", "v1.0.0"]);
+        let list = list.expect(0);
+        let release = tag_mock(&mut server, "v1.0.0", r#"{"tag_name": "v1.0.0"}"#);
+        let info = resolve_version_from(
+            &Client::new(),
+            &fast_retry(),
+            None,
+            Some("1.0.0"),
+            false,
+            &source_at(&server.url()),
+        )
+        .unwrap();
         assert_eq!(info.tag, "v1.0.0");
+        release.assert();
+        list.assert();
+    }
+
+    #[test]
+    fn resolve_version_rejects_missing_pinned_version() {
+        let (mut server, list) = releases_server(&["v1.1.0", "v1.0.0", "v0.9.0"]);
+        let release = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/tags/v99.99.99")
+            .with_status(404)
+            .create();
+        let err = resolve_version_from(
+            &Client::new(),
+            &fast_retry(),
+            None,
+            Some("v99.99.99"),
+            false,
+            &source_at(&server.url()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "release v99.99.99 not found; closest versions: v1.1.0, v1.0.0, v0.9.0"
+        );
+        release.assert();
+        list.assert();
+    }
+
+    #[test]
+    fn resolve_version_rejects_pinned_draft() {
+        let (mut server, list) = releases_server(&["v1.0.0"]);
+        let release = tag_mock(&mut server, "v1.1.0", r#"{"tag_name": "v1.1.0", "draft": true}"#);
+        let err = resolve_version_from(
+            &Client::new(),
+            &fast_retry(),
+            None,
+            Some("v1.1.0"),
+            false,
+            &source_at(&server.url()),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "release v1.1.0 not found; closest versions: v1.0.0");
+        release.assert();
+        list.assert();
     }
 
     #[test]
@@ -338,12 +1038,149 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let info = resolve_version_from(&client, None, true, &server.url()).unwrap();
+        let source = source_at(&server.url());
+        let info = resolve_version_from(&client, &fast_retry(), None, None, true, &source).unwrap();
         assert_eq!(info.tag, "v0.5.0-alpha.1");
         assert!(info.notice.is_none());
         mock.assert();
     }
 
+    #[test]
+    fn resolve_version_range_picks_highest_match() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_query(mockito::Matcher::UrlEncoded("per_page".into(), "100".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"tag_name": "v0.4.3", "draft": true},
+                    {"tag_name": "v0.5.0"},
+                    {"tag_name": "v0.4.2-rc.1", "prerelease": true},
+                    {"tag_name": "v0.4.1"},
+                    {"tag_name": "v0.4.0"}
+                ]"#,
+            )
+            .expect(2)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let resolve = |version, prerelease| {
+            resolve_version_from(&client, &fast_retry(), None, Some(version), prerelease, &source)
+        };
+        assert_eq!(resolve("^0.4", false).unwrap().tag, "v0.4.1");
+        assert_eq!(resolve("~0.4", true).unwrap().tag, "v0.4.2-rc.1");
+        mock.assert();
+    }
+
+    #[test]
+    fn resolve_version_range_skips_flagged_prereleases() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_query(mockito::Matcher::UrlEncoded("per_page".into(), "100".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"tag_name": "v0.4.2", "prerelease": true},
+                    {"tag_name": "v0.4.1"}
+                ]"#,
+            )
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let resolve = |version, prerelease| {
+            resolve_version_from(&client, &fast_retry(), None, Some(version), prerelease, &source)
+        };
+        assert_eq!(resolve("^0.4", false).unwrap().tag, "v0.4.1");
+        assert_eq!(resolve("^0.4", true).unwrap().tag, "v0.4.2");
+        tag_mock(&mut server, "v0.4.2", r#"{"tag_name": "v0.4.2", "prerelease": true}"#);
+        assert_eq!(resolve("v0.4.2", false).unwrap().tag, "v0.4.2");
+    }
+
+    #[test]
+    fn list_releases_follows_link_headers() {
+        let mut server = mockito::Server::new();
+        let next = format!(
+            "{}/repos/centy-io/centy-daemon/releases?per_page=100&page=2",
+            server.url()
+        );
+        let first = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_query(mockito::Matcher::UrlEncoded("per_page".into(), "100".into()))
+            .with_status(200)
+            .with_header("link", &format!(r#"<{next}>; rel="next", <{next}>; rel="last""#))
+            .with_body(
+                r#"[{
+                    "tag_name": "v0.5.0-rc.1",
+                    "prerelease": true,
+                    "published_at": "2025-02-01T10:00:00Z",
+                    "body": "Adds link following.",
+                    "assets": [{
+                        "name": "centy-daemon-v0.5.0-rc.1-x86_64-unknown-linux-gnu.tar.gz",
+                        "browser_download_url": "https://example.com/a.tar.gz",
+                        "size": 1234
+                    }]
+                }]"#,
+            )
+            .create();
+        let second = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_query(mockito::Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_status(200)
+            .with_body(r#"[{"tag_name": "v0.4.0", "draft": true, "body": ""}, {"name": "no tag"}]"#)
+            .create();
+
+        let source = source_at(&server.url());
+        let releases = list_releases_from(&Client::new(), &fast_retry(), None, &source).unwrap();
+        assert_eq!(
+            releases,
+            [
+                RemoteRelease {
+                    tag: "v0.5.0-rc.1".to_string(),
+                    prerelease: true,
+                    draft: false,
+                    published_at: Some("2025-02-01T10:00:00Z".to_string()),
+                    notes: Some("Adds link following.".to_string()),
+                    assets: vec![RemoteAsset {
+                        name: "centy-daemon-v0.5.0-rc.1-x86_64-unknown-linux-gnu.tar.gz"
+                            .to_string(),
+                        url: "https://example.com/a.tar.gz".to_string(),
+                        size: Some(1234),
+                        digest: None,
+                    }],
+                },
+                RemoteRelease {
+                    tag: "v0.4.0".to_string(),
+                    prerelease: false,
+                    draft: true,
+                    published_at: None,
+                    notes: None,
+                    assets: Vec::new(),
+                },
+            ]
+        );
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn next_link_finds_next_relation() {
+        let header = concat!(
+            r#"<https://api.example.com/r?page=1>; rel="prev", "#,
+            r#"<https://api.example.com/r?page=3>; rel="next""#,
+        );
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("https://api.example.com/r?page=3")
+        );
+        assert!(next_link(r#"<https://api.example.com/r?page=1>; rel="first""#).is_none());
+    }
+
     #[test]
     fn resolve_version_stable_uses_latest_endpoint() {
         let mut server = mockito::Server::new();
@@ -361,7 +1198,9 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let info = resolve_version_from(&client, None, false, &server.url()).unwrap();
+        let source = source_at(&server.url());
+        let info =
+            resolve_version_from(&client, &fast_retry(), None, None, false, &source).unwrap();
         assert_eq!(info.tag, "v0.4.0");
         assert!(info.notice.is_none());
         latest_mock.assert();
@@ -385,7 +1224,9 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let info = resolve_version_from(&client, None, false, &server.url()).unwrap();
+        let source = source_at(&server.url());
+        let info =
+            resolve_version_from(&client, &fast_retry(), None, None, false, &source).unwrap();
         assert_eq!(info.tag, "v0.4.0");
         let notice = info.notice.unwrap();
         assert!(notice.contains("v0.5.0-alpha.1"));
@@ -394,6 +1235,82 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
         releases_mock.assert();
     }
 
+    #[test]
+    fn resolve_version_stable_ignores_older_and_draft_prereleases() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"tag_name": "v0.4.0"}"#)
+            .create();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"tag_name": "v0.6.0-beta.1", "draft": true, "prerelease": true},
+                    {"tag_name": "v0.4.0-rc.2", "prerelease": true},
+                    {"tag_name": "v0.4.0"},
+                    {"tag_name": "v0.3.0-alpha.1", "prerelease": true}
+                ]"#,
+            )
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let info =
+            resolve_version_from(&client, &fast_retry(), None, None, false, &source).unwrap();
+        assert_eq!(info.tag, "v0.4.0");
+        assert!(info.notice.is_none());
+    }
+
+    #[test]
+    fn resolve_version_stable_notifies_about_newest_prerelease() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"tag_name": "v0.4.0"}"#)
+            .create();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {"tag_name": "v0.4.1-rc.1", "prerelease": true},
+                    {"tag_name": "v0.5.0-alpha.1", "prerelease": true},
+                    {"tag_name": "v0.4.0"}
+                ]"#,
+            )
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let info =
+            resolve_version_from(&client, &fast_retry(), None, None, false, &source).unwrap();
+        assert!(info.notice.unwrap().contains("v0.5.0-alpha.1"));
+    }
+
+    #[test]
+    fn resolve_version_prerelease_skips_drafts() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"tag_name": "v0.6.0", "draft": true}, {"tag_name": "v0.5.0-rc.1"}]"#)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let info = resolve_version_from(&client, &fast_retry(), None, None, true, &source).unwrap();
+        assert_eq!(info.tag, "v0.5.0-rc.1");
+    }
+
     #[test]
     fn resolve_version_stable_api_error() {
         let mut server = mockito::Server::new();
@@ -403,9 +1320,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, false, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("GitHub API returned 403"));
+        assert!(result.unwrap_err().to_string().contains("GitHub API returned 403"));
         mock.assert();
     }
 
@@ -418,9 +1336,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, true, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, true, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("GitHub API returned 403"));
+        assert!(result.unwrap_err().to_string().contains("GitHub API returned 403"));
         mock.assert();
     }
 
@@ -434,9 +1353,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, false, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("failed to parse release JSON"));
+        assert!(result.unwrap_err().to_string().contains("failed to parse release JSON"));
         mock.assert();
     }
 
@@ -450,9 +1370,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, true, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, true, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("failed to parse releases JSON"));
+        assert!(result.unwrap_err().to_string().contains("failed to parse releases JSON"));
         mock.assert();
     }
 
@@ -467,9 +1388,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, true, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, true, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("no releases found"));
+        assert!(result.unwrap_err().to_string().contains("no releases found"));
         mock.assert();
     }
 
@@ -484,9 +1406,10 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, false, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("no stable release found"));
+        assert!(result.unwrap_err().to_string().contains("no stable release found"));
         mock.assert();
     }
 
@@ -501,21 +1424,13 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let result = resolve_version_from(&client, None, true, &server.url());
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, true, &source);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("no releases found"));
+        assert!(result.unwrap_err().to_string().contains("no releases found"));
         mock.assert();
     }
 
-    #[test]
-    fn resolve_version_from_with_version_ignores_api_base() {
-        let client = Client::new();
-        let info =
-            resolve_version_from(&client, Some("2.0.0"), false, "http://invalid-url.example.com")
-                .unwrap();
-        assert_eq!(info.tag, "v2.0.0");
-    }
-
     #[test]
     fn resolve_version_prerelease_notice_skipped_on_api_failure() {
         let mut server = mockito::Server::new();
@@ -531,10 +1446,225 @@ def456  centy-daemon-0.1.0-x86_64-unknown-linux-gnu.tar.gz
             .create();
 
         let client = Client::new();
-        let info = resolve_version_from(&client, None, false, &server.url()).unwrap();
+        let source = source_at(&server.url());
+        let info =
+            resolve_version_from(&client, &fast_retry(), None, None, false, &source).unwrap();
         assert_eq!(info.tag, "v0.4.0");
         assert!(info.notice.is_none());
         latest_mock.assert();
         releases_mock.assert();
     }
+
+    #[test]
+    fn resolve_version_retries_server_errors() {
+        let mut server = mockito::Server::new();
+        let unavailable = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(502)
+            .expect(2)
+            .create();
+        let releases = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(200)
+            .with_body(r#"[{"tag_name": "v1.0.0"}]"#)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let info = resolve_version_from(&client, &fast_retry(), None, None, true, &source).unwrap();
+        assert_eq!(info.tag, "v1.0.0");
+        unavailable.assert();
+        releases.assert();
+    }
+
+    #[test]
+    fn resolve_version_gives_up_after_max_attempts() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/latest")
+            .with_status(429)
+            .expect(3)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("GitHub API returned 429 Too Many Requests (gave up after 3 attempts)"));
+        mock.assert();
+    }
+
+    #[test]
+    fn resolve_version_does_not_retry_not_found() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/latest")
+            .with_status(404)
+            .expect(1)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
+        assert!(result.unwrap_err().to_string().contains("GitHub API returned 404"));
+        mock.assert();
+    }
+
+    #[test]
+    fn resolve_version_sends_token_as_bearer() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .match_header("authorization", "Bearer secret-token")
+            .with_status(200)
+            .with_body(r#"[{"tag_name": "v1.0.0"}]"#)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let info = resolve_version_from(
+            &client,
+            &fast_retry(),
+            Some("secret-token"),
+            None,
+            true,
+            &source,
+        )
+        .unwrap();
+        assert_eq!(info.tag, "v1.0.0");
+        mock.assert();
+    }
+
+    #[test]
+    fn resolve_version_reports_rate_limit() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases/latest")
+            .with_status(403)
+            .with_header("x-ratelimit-limit", "60")
+            .with_header("x-ratelimit-remaining", "0")
+            .with_header("x-ratelimit-reset", "1767323045")
+            .expect(1)
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), None, None, false, &source);
+        let Err(ApiError::RateLimited(message)) = result else {
+            panic!("expected a rate limit error, got {result:?}");
+        };
+        assert!(message.contains("rate limit of 60 requests exceeded"));
+        assert!(message.contains("resets at 2026-01-02T03:04:05Z"));
+        assert!(message.contains("GITHUB_TOKEN"));
+        mock.assert();
+    }
+
+    #[test]
+    fn env_token_is_only_trusted_to_github_over_https() {
+        let trusts = |api_base: &str, hosts: &[&str]| {
+            trusts_env_token(&SourceConfig {
+                api_base: api_base.to_string(),
+                token_hosts: hosts.iter().map(ToString::to_string).collect(),
+                ..SourceConfig::default()
+            })
+        };
+
+        assert!(trusts("https://api.github.com", &[]));
+        assert!(trusts("HTTPS://API.GitHub.com:443", &[]));
+        assert!(!trusts("http://api.github.com", &[]));
+        assert!(!trusts("https://api.github.com.mirror.example.com", &[]));
+        assert!(!trusts("https://api.github.com@mirror.example.com", &[]));
+        assert!(!trusts("https://ghe.example.com/api/v3", &[]));
+        assert!(trusts("https://ghe.example.com/api/v3", &["ghe.example.com"]));
+        assert!(!trusts("http://ghe.example.com/api/v3", &["ghe.example.com"]));
+    }
+
+    #[test]
+    fn explicit_token_is_never_sent_without_https() {
+        let source = |api_base: &str| SourceConfig {
+            api_base: api_base.to_string(),
+            ..SourceConfig::default()
+        };
+        let token = Some("secret".to_string());
+
+        assert_eq!(
+            api_token(token.clone(), &source("https://ghe.example.com/api/v3")).unwrap(),
+            token
+        );
+        let err = api_token(token, &source("http://ghe.example.com/api/v3")).unwrap_err();
+        assert!(err.contains("without HTTPS"), "{err}");
+    }
+
+    #[test]
+    fn resolve_version_rate_limit_with_token_omits_hint() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/repos/centy-io/centy-daemon/releases")
+            .with_status(429)
+            .with_header("x-ratelimit-remaining", "0")
+            .create();
+
+        let client = Client::new();
+        let source = source_at(&server.url());
+        let result = resolve_version_from(&client, &fast_retry(), Some("t"), None, true, &source);
+        let Err(ApiError::RateLimited(message)) = result else {
+            panic!("expected a rate limit error, got {result:?}");
+        };
+        assert_eq!(message, "GitHub API rate limit exceeded");
+    }
+
+    #[test]
+    fn release_info_uses_configured_source() {
+        let platform = Platform {
+            target: "x86_64-unknown-linux-gnu",
+            archive_ext: ".tar.gz",
+        };
+        let source = SourceConfig {
+            api_base: "https://ghe.example.com/api/v3".to_string(),
+            download_base: "https://mirror.example.com".to_string(),
+            owner: "acme".to_string(),
+            name: "daemon".to_string(),
+            manifest: None,
+            ..github_source()
+        };
+
+        let release = github_release(
+            "v1.0.0",
+            &["centy-daemon-v1.0.0-x86_64-unknown-linux-gnu.tar.gz", "checksums-sha256.txt"],
+        );
+        let info = release_info_from(&release, &platform, &source).unwrap();
+        assert_eq!(
+            info.asset_url,
+            "https://mirror.example.com/acme/daemon/releases/download/v1.0.0/centy-daemon-v1.0.0-x86_64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(
+            info.checksum,
+            Checksum::File(
+                "https://mirror.example.com/acme/daemon/releases/download/v1.0.0/checksums-sha256.txt"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn resolve_version_uses_configured_repository() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/repos/acme/daemon/releases")
+            .with_status(200)
+            .with_body(r#"[{"tag_name": "v3.0.0"}]"#)
+            .create();
+
+        let source = SourceConfig {
+            owner: "acme".to_string(),
+            name: "daemon".to_string(),
+            ..source_at(&server.url())
+        };
+        let client = Client::new();
+        let info = resolve_version_from(&client, &fast_retry(), None, None, true, &source).unwrap();
+        assert_eq!(info.tag, "v3.0.0");
+        mock.assert();
+    }
 }
@@ -1,37 +1,346 @@
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
-/// Install the binary bytes to `~/.centy/bin/centy-daemon` and return the path.
-pub fn install_binary(binary_bytes: &[u8]) -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("could not determine home directory")?;
-    install_binary_to(binary_bytes, &home)
+use crate::version::parse_tag;
+
+const BINARY_NAME: &str = if cfg!(target_os = "windows") {
+    "centy-daemon.exe"
+} else {
+    "centy-daemon"
+};
+
+/// Return the path of the active binary under the given home directory.
+pub fn binary_path_in(home_dir: &Path) -> PathBuf {
+    home_dir.join(".centy").join("bin").join(BINARY_NAME)
+}
+
+/// Return the directory holding one subdirectory per installed version.
+pub fn versions_dir_in(home_dir: &Path) -> PathBuf {
+    home_dir.join(".centy").join("versions")
+}
+
+/// Return the path of the binary stored for version `tag`.
+pub fn version_binary_path_in(home_dir: &Path, tag: &str) -> Result<PathBuf, String> {
+    if tag.is_empty() || tag == "." || tag == ".." || tag.contains(['/', '\\']) {
+        return Err(format!("invalid version tag: {tag}"));
+    }
+    Ok(versions_dir_in(home_dir).join(tag).join(BINARY_NAME))
+}
+
+/// A file replaced during installation, together with its saved copy.
+struct Backup {
+    original: PathBuf,
+    /// `None` when `original` did not exist before the install.
+    saved: Option<PathBuf>,
+}
+
+/// An installation whose replaced files can still be restored.
+///
+/// Every file the install overwrites is first saved next to it as `<name>.bak`.
+/// Call [`PendingInstall::commit`] once the new binary is known to work, or
+/// [`PendingInstall::rollback`] to put the previous files back.
+#[must_use = "a pending install must be committed or rolled back"]
+pub struct PendingInstall {
+    path: PathBuf,
+    backups: Vec<Backup>,
+}
+
+impl PendingInstall {
+    /// Path of the active binary (`~/.centy/bin/centy-daemon`).
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keep the new files and delete the saved copies.
+    pub fn commit(self) -> Result<PathBuf, String> {
+        for backup in &self.backups {
+            if let Some(saved) = &backup.saved {
+                fs::remove_file(saved)
+                    .map_err(|e| format!("failed to remove backup {}: {e}", saved.display()))?;
+            }
+        }
+        Ok(self.path)
+    }
+
+    /// Restore every replaced file, most recent first.
+    pub fn rollback(self) -> Result<(), String> {
+        for backup in self.backups.iter().rev() {
+            if let Some(saved) = &backup.saved {
+                fs::rename(saved, &backup.original).map_err(|e| {
+                    format!("failed to restore {}: {e}", backup.original.display())
+                })?;
+                continue;
+            }
+
+            if backup.original.symlink_metadata().is_ok() {
+                fs::remove_file(&backup.original).map_err(|e| {
+                    format!("failed to remove {}: {e}", backup.original.display())
+                })?;
+            }
+            // Drop the directory too if the install left it empty
+            if let Some(dir) = backup.original.parent() {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Install the binary read from `binary` as version `tag` and make it the
+/// active version.
+///
+/// The returned [`PendingInstall`] keeps the previous files until it is
+/// committed or rolled back.
+pub fn install_binary_to(
+    mut binary: impl Read,
+    tag: &str,
+    home_dir: &Path,
+) -> Result<PendingInstall, String> {
+    let version_path = version_binary_path_in(home_dir, tag)?;
+    let version_dir = version_path
+        .parent()
+        .ok_or("version binary path has no parent directory")?;
+
+    fs::create_dir_all(version_dir)
+        .map_err(|e| format!("failed to create {}: {e}", version_dir.display()))?;
+
+    let mut pending = PendingInstall {
+        path: binary_path_in(home_dir),
+        backups: vec![backup(&version_path)?],
+    };
+
+    let result = write_atomic(&version_path, &mut binary)
+        .and_then(|()| activate(home_dir, tag, &mut pending.backups));
+
+    match result {
+        Ok(()) => Ok(pending),
+        Err(e) => match pending.rollback() {
+            Ok(()) => Err(e),
+            Err(rollback_err) => Err(format!("{e} (rollback also failed: {rollback_err})")),
+        },
+    }
+}
+
+/// Point `~/.centy/bin/centy-daemon` at the already installed version `tag`.
+pub fn activate_version_in(home_dir: &Path, tag: &str) -> Result<PendingInstall, String> {
+    let mut pending = PendingInstall {
+        path: binary_path_in(home_dir),
+        backups: Vec::new(),
+    };
+    activate(home_dir, tag, &mut pending.backups)?;
+    Ok(pending)
 }
 
-pub fn install_binary_to(binary_bytes: &[u8], home_dir: &Path) -> Result<PathBuf, String> {
-    let bin_dir = home_dir.join(".centy").join("bin");
+fn activate(home_dir: &Path, tag: &str, backups: &mut Vec<Backup>) -> Result<(), String> {
+    let version_path = version_binary_path_in(home_dir, tag)?;
+    if !version_path.is_file() {
+        return Err(format!("version {tag} is not installed"));
+    }
 
-    fs::create_dir_all(&bin_dir)
+    let binary_path = binary_path_in(home_dir);
+    let bin_dir = binary_path
+        .parent()
+        .ok_or("installed binary path has no parent directory")?;
+
+    fs::create_dir_all(bin_dir)
         .map_err(|e| format!("failed to create {}: {e}", bin_dir.display()))?;
 
-    let binary_name = if cfg!(target_os = "windows") {
-        "centy-daemon.exe"
+    backups.push(backup(&binary_path)?);
+    link_active(&version_path, &binary_path)
+}
+
+/// Save a copy of `original` as `<name>.bak`, leaving `original` in place.
+///
+/// Regular files are hard-linked where possible so the copy is free; symlinks
+/// are recreated so the copy points at the same target.
+fn backup(original: &Path) -> Result<Backup, String> {
+    let Ok(metadata) = original.symlink_metadata() else {
+        return Ok(Backup {
+            original: original.to_path_buf(),
+            saved: None,
+        });
+    };
+
+    let mut saved = original.as_os_str().to_os_string();
+    saved.push(".bak");
+    let saved = PathBuf::from(saved);
+    let _ = fs::remove_file(&saved);
+
+    let result = if metadata.file_type().is_symlink() {
+        copy_symlink(original, &saved)
     } else {
-        "centy-daemon"
+        fs::hard_link(original, &saved)
+            .or_else(|_hard_link_err| fs::copy(original, &saved).map(|_| ()))
     };
-    let binary_path = bin_dir.join(binary_name);
+    result.map_err(|e| format!("failed to back up {}: {e}", original.display()))?;
 
-    fs::write(&binary_path, binary_bytes)
-        .map_err(|e| format!("failed to write binary to {}: {e}", binary_path.display()))?;
+    Ok(Backup {
+        original: original.to_path_buf(),
+        saved: Some(saved),
+    })
+}
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let perms = fs::Permissions::from_mode(0o755);
-        fs::set_permissions(&binary_path, perms)
-            .map_err(|e| format!("failed to set permissions: {e}"))?;
+#[cfg(unix)]
+fn copy_symlink(original: &Path, copy: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(original)?, copy)
+}
+
+#[cfg(windows)]
+fn copy_symlink(original: &Path, copy: &Path) -> std::io::Result<()> {
+    fs::copy(original, copy).map(|_| ())
+}
+
+/// Copy `source` to `path` without ever exposing a partially written file.
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk,
+/// and is then renamed over `path`.
+fn write_atomic(path: &Path, source: &mut impl Read) -> Result<(), String> {
+    let mut tmp_name = path.as_os_str().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        io::copy(source, &mut file)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o755))?;
+        }
+
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)?;
+        sync_parent_dir(path)
+    })();
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("failed to write binary to {}: {e}", path.display())
+    })
+}
+
+/// Flush the directory entry of `path` so a completed rename survives a crash.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) => fs::File::open(dir)?.sync_all(),
+        None => Ok(()),
     }
+}
 
-    Ok(binary_path)
+#[cfg(windows)]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Replace the active binary with a symlink to `target`.
+///
+/// The link is created next to `link` and renamed over it, so the active
+/// binary is never missing while switching versions.
+#[cfg(unix)]
+fn link_active(target: &Path, link: &Path) -> Result<(), String> {
+    let tmp_link = link.with_extension("link-tmp");
+    let _ = fs::remove_file(&tmp_link);
+
+    std::os::unix::fs::symlink(target, &tmp_link)
+        .map_err(|e| format!("failed to create link {}: {e}", tmp_link.display()))?;
+
+    fs::rename(&tmp_link, link).map_err(|e| {
+        let _ = fs::remove_file(&tmp_link);
+        format!("failed to activate {}: {e}", link.display())
+    })
+}
+
+/// Replace the active binary with a copy of `target`.
+///
+/// Creating symlinks requires elevated privileges on Windows, so the active
+/// version is copied instead.
+#[cfg(windows)]
+fn link_active(target: &Path, link: &Path) -> Result<(), String> {
+    let mut source = fs::File::open(target)
+        .map_err(|e| format!("failed to read {}: {e}", target.display()))?;
+    write_atomic(link, &mut source)
+}
+
+/// List the installed version tags, oldest first.
+///
+/// Tags are sorted as semver versions; tags that are not versions come last,
+/// sorted by name.
+pub fn installed_versions_in(home_dir: &Path) -> Result<Vec<String>, String> {
+    let versions_dir = versions_dir_in(home_dir);
+
+    let entries = match fs::read_dir(&versions_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {}: {e}", versions_dir.display())),
+    };
+
+    let mut versions = Vec::new();
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("failed to read {}: {e}", versions_dir.display()))?;
+        if entry.path().join(BINARY_NAME).is_file() {
+            if let Some(tag) = entry.file_name().to_str() {
+                versions.push(tag.to_string());
+            }
+        }
+    }
+
+    versions.sort_by_cached_key(|tag| {
+        let version = parse_tag(tag);
+        (version.is_none(), version, tag.clone())
+    });
+    Ok(versions)
+}
+
+/// Return the tag of the version the active binary points at, if known.
+#[cfg(unix)]
+pub fn active_version_in(home_dir: &Path) -> Option<String> {
+    let target = fs::read_link(binary_path_in(home_dir)).ok()?;
+    let tag = target
+        .strip_prefix(versions_dir_in(home_dir))
+        .ok()?
+        .components()
+        .next()?;
+    tag.as_os_str().to_str().map(String::from)
+}
+
+/// Return the tag of the version the active binary was copied from, if known.
+#[cfg(windows)]
+pub fn active_version_in(home_dir: &Path) -> Option<String> {
+    let active = fs::read(binary_path_in(home_dir)).ok()?;
+    installed_versions_in(home_dir)
+        .ok()?
+        .into_iter()
+        .find(|tag| {
+            version_binary_path_in(home_dir, tag)
+                .and_then(|path| fs::read(path).map_err(|e| e.to_string()))
+                .is_ok_and(|bytes| bytes == active)
+        })
+}
+
+/// Remove the active binary and every installed version.
+///
+/// Returns the removed binary path, or `None` if nothing was installed.
+pub fn uninstall_binary_from(home_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let binary_path = binary_path_in(home_dir);
+    let versions_dir = versions_dir_in(home_dir);
+
+    let had_binary = binary_path.symlink_metadata().is_ok();
+    if had_binary {
+        fs::remove_file(&binary_path)
+            .map_err(|e| format!("failed to remove {}: {e}", binary_path.display()))?;
+    }
+
+    if versions_dir.exists() {
+        fs::remove_dir_all(&versions_dir)
+            .map_err(|e| format!("failed to remove {}: {e}", versions_dir.display()))?;
+    }
+
+    Ok(had_binary.then_some(binary_path))
 }
 
 #[cfg(test)]
@@ -49,7 +358,10 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let binary_bytes = b"test-binary-content";
 
-        let path = install_binary_to(binary_bytes, tmp.path()).unwrap();
+        let path = install_binary_to(&binary_bytes[..], "v1.0.0", tmp.path())
+            .unwrap()
+            .commit()
+            .unwrap();
 
         assert!(path.exists());
         assert_eq!(fs::read(&path).unwrap(), binary_bytes);
@@ -69,7 +381,7 @@ mod tests {
         let bin_dir = tmp.path().join(".centy").join("bin");
         assert!(!bin_dir.exists());
 
-        install_binary_to(b"data", tmp.path()).unwrap();
+        install_binary_to(&b"data"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
 
         assert!(bin_dir.exists());
     }
@@ -78,10 +390,16 @@ mod tests {
     fn install_binary_to_overwrites_existing() {
         let tmp = tempfile::tempdir().unwrap();
 
-        let path = install_binary_to(b"first-version", tmp.path()).unwrap();
+        let path = install_binary_to(&b"first-version"[..], "v1.0.0", tmp.path())
+            .unwrap()
+            .commit()
+            .unwrap();
         assert_eq!(fs::read(&path).unwrap(), b"first-version");
 
-        let path = install_binary_to(b"second-version", tmp.path()).unwrap();
+        let path = install_binary_to(&b"second-version"[..], "v1.0.0", tmp.path())
+            .unwrap()
+            .commit()
+            .unwrap();
         assert_eq!(fs::read(&path).unwrap(), b"second-version");
     }
 
@@ -91,7 +409,10 @@ mod tests {
         use std::os::unix::fs::PermissionsExt;
 
         let tmp = tempfile::tempdir().unwrap();
-        let path = install_binary_to(b"binary", tmp.path()).unwrap();
+        let path = install_binary_to(&b"binary"[..], "v1.0.0", tmp.path())
+            .unwrap()
+            .commit()
+            .unwrap();
 
         let metadata = fs::metadata(&path).unwrap();
         let mode = metadata.permissions().mode();
@@ -101,7 +422,7 @@ mod tests {
     #[test]
     fn install_binary_to_returns_correct_path() {
         let tmp = tempfile::tempdir().unwrap();
-        let path = install_binary_to(b"data", tmp.path()).unwrap();
+        let path = install_binary_to(&b"data"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
 
         let expected = tmp.path().join(".centy").join("bin").join(if cfg!(target_os = "windows") {
             "centy-daemon.exe"
@@ -114,7 +435,185 @@ mod tests {
 
     #[test]
     fn install_binary_to_invalid_path() {
-        let result = install_binary_to(b"data", Path::new("/nonexistent/invalid/path"));
+        let result = install_binary_to(
+            &b"data"[..],
+            "v1.0.0",
+            Path::new("/nonexistent/invalid/path"),
+        );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn uninstall_binary_from_removes_installed_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = install_binary_to(&b"data"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        let removed = uninstall_binary_from(tmp.path()).unwrap();
+
+        assert_eq!(removed, Some(path.clone()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn uninstall_binary_from_nothing_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let removed = uninstall_binary_from(tmp.path()).unwrap();
+        assert!(removed.is_none());
+    }
+
+    #[test]
+    fn install_binary_to_keeps_versions_side_by_side() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        install_binary_to(&b"stable"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+        let path = install_binary_to(&b"pre"[..], "v1.1.0-alpha.1", tmp.path())
+            .unwrap()
+            .commit()
+            .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"pre");
+        let stable = version_binary_path_in(tmp.path(), "v1.0.0").unwrap();
+        assert_eq!(fs::read(stable).unwrap(), b"stable");
+        assert_eq!(
+            installed_versions_in(tmp.path()).unwrap(),
+            vec!["v1.0.0".to_string(), "v1.1.0-alpha.1".to_string()]
+        );
+        assert_eq!(
+            active_version_in(tmp.path()).as_deref(),
+            Some("v1.1.0-alpha.1")
+        );
+    }
+
+    #[test]
+    fn activate_version_in_switches_active_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"stable"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+        install_binary_to(&b"pre"[..], "v1.1.0-alpha.1", tmp.path()).unwrap().commit().unwrap();
+
+        let path = activate_version_in(tmp.path(), "v1.0.0").unwrap().commit().unwrap();
+
+        assert_eq!(path, binary_path_in(tmp.path()));
+        assert_eq!(fs::read(&path).unwrap(), b"stable");
+        assert_eq!(active_version_in(tmp.path()).as_deref(), Some("v1.0.0"));
+    }
+
+    #[test]
+    fn activate_version_in_missing_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let result = activate_version_in(tmp.path(), "v9.9.9");
+        assert!(result.err().unwrap().contains("version v9.9.9 is not installed"));
+    }
+
+    #[test]
+    fn version_binary_path_in_rejects_path_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        for tag in ["", ".", "..", "../evil", "a/b", "a\\b"] {
+            let result = version_binary_path_in(tmp.path(), tag);
+            assert!(result.unwrap_err().contains("invalid version tag"));
+        }
+    }
+
+    #[test]
+    fn installed_versions_in_nothing_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(installed_versions_in(tmp.path()).unwrap().is_empty());
+        assert!(active_version_in(tmp.path()).is_none());
+    }
+
+    #[test]
+    fn installed_versions_in_sorts_as_semver() {
+        let tmp = tempfile::tempdir().unwrap();
+        for tag in ["v0.10.0", "nightly", "v0.9.0", "v0.10.0-rc.1", "v0.2.1"] {
+            install_binary_to(&b"bin"[..], tag, tmp.path()).unwrap().commit().unwrap();
+        }
+
+        assert_eq!(
+            installed_versions_in(tmp.path()).unwrap(),
+            ["v0.2.1", "v0.9.0", "v0.10.0-rc.1", "v0.10.0", "nightly"]
+        );
+    }
+
+    #[test]
+    fn uninstall_binary_from_removes_all_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"one"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+        install_binary_to(&b"two"[..], "v2.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        uninstall_binary_from(tmp.path()).unwrap();
+
+        assert!(!versions_dir_in(tmp.path()).exists());
+        assert!(installed_versions_in(tmp.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn install_binary_to_commit_removes_backups() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"old"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+        install_binary_to(&b"new"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        let version_path = version_binary_path_in(tmp.path(), "v1.0.0").unwrap();
+        let version_dir = version_path.parent().unwrap();
+        let leftovers: Vec<_> = fs::read_dir(version_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers.len(), 1);
+        assert_eq!(fs::read(version_path).unwrap(), b"new");
+    }
+
+    #[test]
+    fn install_binary_to_rollback_restores_same_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"old"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        let pending = install_binary_to(&b"new"[..], "v1.0.0", tmp.path()).unwrap();
+        assert_eq!(fs::read(pending.path()).unwrap(), b"new");
+        pending.rollback().unwrap();
+
+        assert_eq!(fs::read(binary_path_in(tmp.path())).unwrap(), b"old");
+    }
+
+    #[test]
+    fn install_binary_to_rollback_restores_previous_active_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"old"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        let pending = install_binary_to(&b"new"[..], "v2.0.0", tmp.path()).unwrap();
+        pending.rollback().unwrap();
+
+        assert_eq!(fs::read(binary_path_in(tmp.path())).unwrap(), b"old");
+        assert_eq!(active_version_in(tmp.path()).as_deref(), Some("v1.0.0"));
+        assert_eq!(
+            installed_versions_in(tmp.path()).unwrap(),
+            vec!["v1.0.0".to_string()]
+        );
+    }
+
+    #[test]
+    fn install_binary_to_rollback_of_fresh_install_leaves_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let pending = install_binary_to(&b"new"[..], "v1.0.0", tmp.path()).unwrap();
+        pending.rollback().unwrap();
+
+        assert!(binary_path_in(tmp.path()).symlink_metadata().is_err());
+        assert!(installed_versions_in(tmp.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn install_binary_to_failed_write_keeps_previous_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_binary_to(&b"old"[..], "v1.0.0", tmp.path()).unwrap().commit().unwrap();
+
+        // A directory in place of the temp file makes the write fail
+        let version_path = version_binary_path_in(tmp.path(), "v1.0.0").unwrap();
+        let mut tmp_name = version_path.as_os_str().to_os_string();
+        tmp_name.push(".tmp");
+        fs::create_dir(PathBuf::from(tmp_name)).unwrap();
+
+        let result = install_binary_to(&b"new"[..], "v1.0.0", tmp.path());
+
+        assert!(result.err().unwrap().contains("failed to write binary"));
+        assert_eq!(fs::read(binary_path_in(tmp.path())).unwrap(), b"old");
+    }
 }
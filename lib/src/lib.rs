@@ -1,11 +1,35 @@
+mod bundle;
+mod config;
 mod daemon;
+mod digest;
 mod download;
 mod extract;
 mod github;
 mod install;
+mod manifest;
+mod notes;
 mod platform;
+mod provenance;
+mod progress;
+mod receipt;
+mod retry;
+mod signature;
+mod sigstore;
+mod source;
+mod version;
 
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use config::{ProvenancePolicy, SourceConfig};
+pub use digest::DigestAlgorithm;
+pub use extract::ExtractionLimits;
+pub use progress::{Phase, Progress, ProgressObserver, RetryNotice};
+pub use receipt::InstallReceipt;
+pub use retry::RetryPolicy;
+use retry::Retrier;
+pub use source::{RemoteAsset, RemoteRelease};
 
 #[derive(Debug, thiserror::Error)]
 pub enum InstallerError {
@@ -15,29 +39,136 @@ pub enum InstallerError {
     #[error("version resolution failed: {0}")]
     VersionResolution(String),
 
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+
     #[error("download failed: {0}")]
     Download(String),
 
     #[error("extraction failed: {0}")]
     Extraction(String),
 
+    #[error("unsafe archive: {0}")]
+    UnsafeArchive(String),
+
     #[error("installation failed: {0}")]
     Installation(String),
 
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
     #[error("daemon restart failed: {0}")]
     DaemonRestart(String),
+
+    #[error("bundle failed: {0}")]
+    Bundle(String),
+
+    #[error("signature verification failed: {0}")]
+    Signature(String),
+
+    #[error("provenance check failed: {0}")]
+    Provenance(String),
 }
 
 pub(crate) fn extract_binary(
-    archive_bytes: &[u8],
+    archive: impl Read + Seek,
     archive_ext: &str,
-) -> Result<Vec<u8>, InstallerError> {
+    out: &mut impl Write,
+    limits: &ExtractionLimits,
+) -> Result<(), InstallerError> {
     match archive_ext {
-        ".tar.gz" => extract::extract_tar_gz(archive_bytes),
-        ".zip" => extract::extract_zip(archive_bytes),
-        ext => Err(format!("unsupported archive format: {ext}")),
+        ".tar.gz" => extract::extract_tar_gz(archive, out, limits),
+        ".zip" => extract::extract_zip(archive, out, limits),
+        ext => Err(format!("unsupported archive format: {ext}").into()),
+    }
+    .map_err(|e| match e {
+        extract::ExtractError::Limit(message) => InstallerError::UnsafeArchive(message),
+        extract::ExtractError::Other(message) => InstallerError::Extraction(message),
+    })
+}
+
+/// Extract the binary from a downloaded archive into a temporary file,
+/// returned positioned at its start.
+fn extract_to_tempfile(
+    archive: impl Read + Seek,
+    archive_ext: &str,
+    limits: &ExtractionLimits,
+) -> Result<std::fs::File, InstallerError> {
+    let mut binary = tempfile::tempfile().map_err(|e| {
+        InstallerError::Extraction(format!("failed to create temporary file: {e}"))
+    })?;
+
+    extract_binary(archive, archive_ext, &mut binary, limits)?;
+
+    binary.seek(SeekFrom::Start(0)).map_err(|e| {
+        InstallerError::Extraction(format!("failed to rewind extracted binary: {e}"))
+    })?;
+
+    Ok(binary)
+}
+
+/// Options controlling [`install_with`].
+#[derive(Clone)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "each flag is an independent install option"
+)]
+pub struct InstallOptions {
+    /// Allow pre-release versions when no version is pinned.
+    pub prerelease: bool,
+    /// Restart the daemon if it was already running.
+    pub restart: bool,
+    /// Download and install again even if the same release is already installed.
+    pub force: bool,
+    /// Print the release notes of every release between the previously active
+    /// version and the installed one.
+    pub release_notes: bool,
+    /// Receives progress updates, including bytes downloaded.
+    pub progress: Option<Arc<dyn ProgressObserver>>,
+    /// How transient network and GitHub API failures are retried.
+    pub retry: RetryPolicy,
+    /// Caps on the size, entry count and compression ratio of the archive
+    /// while the binary is extracted from it.
+    pub extraction: ExtractionLimits,
+    /// Where releases are looked up and downloaded from. When unset, it is
+    /// read from `~/.centy/installer.json` and `CENTY_INSTALLER_*` variables.
+    pub source: Option<SourceConfig>,
+    /// Token for GitHub API requests, never sent without HTTPS. Defaults to
+    /// `GITHUB_TOKEN` or `GH_TOKEN` from the environment when unset, which is
+    /// only sent to `api.github.com` and the `token_hosts` of the source.
+    pub github_token: Option<String>,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        Self {
+            prerelease: false,
+            restart: true,
+            force: false,
+            release_notes: false,
+            progress: None,
+            retry: RetryPolicy::default(),
+            extraction: ExtractionLimits::default(),
+            source: None,
+            github_token: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for InstallOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstallOptions")
+            .field("prerelease", &self.prerelease)
+            .field("restart", &self.restart)
+            .field("force", &self.force)
+            .field("release_notes", &self.release_notes)
+            .field("progress", &self.progress.is_some())
+            .field("retry", &self.retry)
+            .field("extraction", &self.extraction)
+            .field("source", &self.source)
+            .field("github_token", &self.github_token.as_ref().map(|_| "<redacted>"))
+            .finish()
     }
-    .map_err(InstallerError::Extraction)
 }
 
 /// Download and install the `centy-daemon` binary.
@@ -45,48 +176,949 @@ pub(crate) fn extract_binary(
 /// If `version` is `None`, the latest stable release is used by default.
 /// Set `prerelease` to `true` to allow installing pre-release versions.
 /// When `restart` is `true`, the daemon is restarted if it was already running.
-/// Returns the path to the installed binary (`~/.centy/bin/centy-daemon`).
+/// The binary is stored under `~/.centy/versions/<tag>/` and becomes the active
+/// version. Returns the path to the active binary (`~/.centy/bin/centy-daemon`).
 pub fn install(
     version: Option<&str>,
     prerelease: bool,
     restart: bool,
+) -> Result<PathBuf, InstallerError> {
+    install_with(
+        version,
+        &InstallOptions {
+            prerelease,
+            restart,
+            ..InstallOptions::default()
+        },
+    )
+}
+
+/// Download and install the `centy-daemon` binary with the given options.
+///
+/// If the resolved release is already installed with a matching checksum, the
+/// download is skipped and the installed version is made active instead,
+/// unless `options.force` is set. An interrupted download is resumed on the
+/// next call. With `options.release_notes`, the notes of the releases since
+/// the previously active version are printed once the install succeeds.
+pub fn install_with(
+    version: Option<&str>,
+    options: &InstallOptions,
 ) -> Result<PathBuf, InstallerError> {
     let platform = platform::detect().map_err(InstallerError::Platform)?;
+    let home = home_dir()?;
+    let progress = options
+        .progress
+        .as_deref()
+        .unwrap_or(&progress::NoProgress);
 
     let client = reqwest::blocking::Client::new();
+    let retry = retrier(options);
 
-    let version_info = github::resolve_version(&client, version, prerelease)
-        .map_err(InstallerError::VersionResolution)?;
+    progress.on_progress(Progress::started(Phase::Resolving));
+    let config = source_config(options)?;
+    let source = release_source(&client, &config, options)?;
+    let version_info = source
+        .resolve_version(version, options.prerelease)
+        .map_err(version_error)?;
 
     if let Some(notice) = &version_info.notice {
         eprintln!("{notice}");
     }
 
-    let info = github::release_info(&version_info.tag, &platform);
+    let info = source
+        .release_info(&version_info.tag, &platform)
+        .map_err(version_error)?;
+
+    let expected = expected_digest(&client, &retry, &info, &config)?;
+
+    let previous = install::active_version_in(&home);
+    let print_notes = || {
+        if options.release_notes {
+            print_release_notes(source.as_ref(), previous.as_deref(), &version_info.tag);
+        }
+    };
+    if let Some(result) = reuse_installed(&home, &info, &expected, options) {
+        return result.inspect(|_| print_notes());
+    }
 
-    let asset = download::download_and_verify(&client, &info)
+    let cache_dir = download::cache_dir_in(&home);
+    let asset = download::download_and_verify(
+        &client,
+        &retry,
+        &info,
+        &expected,
+        &cache_dir,
+        progress,
+    )
+    .map_err(InstallerError::Download)?;
+    verify_sigstore_bundle(&client, &retry, &info, &asset.sha256, &config)?;
+    verify_provenance(&client, &retry, &info, &asset.sha256, &config)?;
+
+    install_asset(&home, &platform, info, &asset, platform.archive_ext, options)
+        .inspect(|_| print_notes())
+}
+
+/// The release source configuration of `options`, read from
+/// `~/.centy/installer.json` and the environment when `options.source` is
+/// unset.
+fn source_config(options: &InstallOptions) -> Result<SourceConfig, InstallerError> {
+    match &options.source {
+        Some(config) => Ok(config.clone()),
+        None => config::load_source_in(&home_dir()?).map_err(InstallerError::Config),
+    }
+}
+
+/// Build the release source of `config`, retrying and authenticating its
+/// requests as `options` asks.
+fn release_source(
+    client: &reqwest::blocking::Client,
+    config: &SourceConfig,
+    options: &InstallOptions,
+) -> Result<Box<dyn source::ReleaseSource>, InstallerError> {
+    let token = match &config.manifest {
+        // Release manifests are fetched without a token
+        Some(_) => None,
+        None => github::api_token(options.github_token.clone(), config)
+            .map_err(InstallerError::Config)?,
+    };
+    Ok(source::from_config(client, &retrier(options), token, config))
+}
+
+/// The retry policy of `options`, reporting retries to its progress observer.
+fn retrier(options: &InstallOptions) -> Retrier {
+    let observer = options
+        .progress
+        .clone()
+        .unwrap_or_else(|| Arc::new(progress::NoProgress));
+    Retrier::new(options.retry.clone(), observer)
+}
+
+/// Return the expected digest of the asset of `info`, downloading the
+/// checksums file and checking its signature if the release source did not
+/// publish the hash directly.
+fn expected_digest(
+    client: &reqwest::blocking::Client,
+    retry: &Retrier,
+    info: &source::ReleaseInfo,
+    config: &SourceConfig,
+) -> Result<digest::Digest, InstallerError> {
+    let url = match &info.checksum {
+        source::Checksum::Digest(digest) => return Ok(digest.clone()),
+        source::Checksum::File(url) => url,
+    };
+    let text = download::fetch_text(client, retry, url, "failed to download checksums")
         .map_err(InstallerError::Download)?;
+    verify_checksums(client, retry, url, &text, config.signing_keys())?;
+    find_checksum(&text, url, &info.asset_name, config)
+}
 
-    let binary_bytes = extract_binary(&asset.bytes, platform.archive_ext)?;
+/// Find the digest of `asset_name` in the checksums file `text`, read from
+/// `url`. Its algorithm is `config.checksum_algorithm`, or told from the
+/// file name when that is unset.
+fn find_checksum(
+    text: &str,
+    url: &str,
+    asset_name: &str,
+    config: &SourceConfig,
+) -> Result<digest::Digest, InstallerError> {
+    let algorithm = config
+        .checksum_algorithm
+        .or_else(|| DigestAlgorithm::from_file_name(file_name_of(url)));
+    digest::parse_checksum(text, asset_name, algorithm).map_err(InstallerError::Download)
+}
 
-    let path = install::install_binary(&binary_bytes)
-        .map_err(InstallerError::Installation)?;
+/// Last segment of a URL or path.
+fn file_name_of(url: &str) -> &str {
+    url.rsplit(['/', '\\']).next().unwrap_or(url)
+}
 
-    if restart {
-        match daemon::restart_if_running(&path) {
-            Ok(true) => {
-                eprintln!("Restarted centy-daemon to use the updated version");
+/// Check the checksums file `text`, downloaded from `url`, against its
+/// detached signature at `url` followed by `.minisig`, which must be made by
+/// one of `keys`. Nothing is checked when no key is trusted.
+///
+/// Returns the signature when it was checked.
+fn verify_checksums(
+    client: &reqwest::blocking::Client,
+    retry: &Retrier,
+    url: &str,
+    text: &str,
+    keys: Option<&[String]>,
+) -> Result<Option<String>, InstallerError> {
+    let Some(keys) = keys else {
+        return Ok(None);
+    };
+    signature::require_keys(keys).map_err(InstallerError::Signature)?;
+    let signature_url = format!("{url}{}", signature::SIGNATURE_EXT);
+    let signature =
+        download::fetch_text(client, retry, &signature_url, "failed to fetch checksums signature")
+            .map_err(InstallerError::Signature)?;
+    signature::verify(text.as_bytes(), &signature, keys, file_name_of(url))
+        .map_err(InstallerError::Signature)?;
+    Ok(Some(signature))
+}
+
+/// Check the Sigstore bundle of the asset of `info`, published at its URL
+/// followed by `.sigstore.json`, against the trust root of `config`. The
+/// bundle must sign `sha256`, the SHA-256 of the asset computed while it was
+/// downloaded. Nothing is checked when `verify_sigstore` is off; otherwise a
+/// trust root without any certificate authority is an error.
+///
+/// Returns the bundle when it was checked.
+fn verify_sigstore_bundle(
+    client: &reqwest::blocking::Client,
+    retry: &Retrier,
+    info: &source::ReleaseInfo,
+    sha256: &str,
+    config: &SourceConfig,
+) -> Result<Option<String>, InstallerError> {
+    if !config.verify_sigstore {
+        return Ok(None);
+    }
+    let root = sigstore::TrustedRoot::parse(&config.sigstore_root).map_err(InstallerError::Config)?;
+    if root.is_empty() {
+        return Err(InstallerError::Signature(
+            "the Sigstore trust root lists no certificate authority; set \
+             \"sigstore_trusted_root\", or set \"verify_sigstore\" to false to skip Sigstore \
+             bundles"
+                .to_string(),
+        ));
+    }
+    let url = format!("{}{}", info.asset_url, sigstore::BUNDLE_EXT);
+    let bundle = download::fetch_text(client, retry, &url, "failed to fetch Sigstore bundle")
+        .map_err(InstallerError::Signature)?;
+    let identity = sigstore::Identity::Release {
+        workflow: config.sigstore_workflow(),
+        tag: &info.tag,
+    };
+    sigstore::verify(&bundle, sha256, &root, identity, &info.asset_name)
+        .map_err(InstallerError::Signature)?;
+    Ok(Some(bundle))
+}
+
+/// Check that the SLSA provenance of the release of `info` attests its
+/// asset, whose SHA-256 was computed as `sha256` while it was downloaded, as
+/// `config.provenance` requires. Nothing is checked when it is unset.
+fn verify_provenance(
+    client: &reqwest::blocking::Client,
+    retry: &Retrier,
+    info: &source::ReleaseInfo,
+    sha256: &str,
+    config: &SourceConfig,
+) -> Result<(), InstallerError> {
+    let Some(policy) = &config.provenance else {
+        return Ok(());
+    };
+    let provenance = fetch_provenance(client, retry, info)?;
+    check_provenance(&provenance, info, sha256, config, policy)
+}
+
+/// Download the SLSA provenance of the release of `info`.
+fn fetch_provenance(
+    client: &reqwest::blocking::Client,
+    retry: &Retrier,
+    info: &source::ReleaseInfo,
+) -> Result<String, InstallerError> {
+    let url = info.provenance.as_deref().ok_or_else(|| {
+        InstallerError::Provenance(format!("release {} publishes no SLSA provenance", info.tag))
+    })?;
+    download::fetch_text(client, retry, url, "failed to fetch SLSA provenance")
+        .map_err(InstallerError::Provenance)
+}
+
+/// Check `provenance` against `policy` for the asset of `info` with `sha256`.
+///
+/// The provenance must be signed, as checked against the Sigstore trust root
+/// of `config`, either by the release workflow for the release tag or by the
+/// builder the policy expects.
+fn check_provenance(
+    provenance: &str,
+    info: &source::ReleaseInfo,
+    sha256: &str,
+    config: &SourceConfig,
+    policy: &ProvenancePolicy,
+) -> Result<(), InstallerError> {
+    let builder = policy.builder.as_deref().unwrap_or(provenance::DEFAULT_BUILDER);
+    let repository = policy.repository.as_deref().unwrap_or(provenance::DEFAULT_REPOSITORY);
+    let root = sigstore::TrustedRoot::parse(&config.sigstore_root).map_err(InstallerError::Config)?;
+    if root.is_empty() {
+        return Err(InstallerError::Provenance(
+            "the Sigstore trust root lists no certificate authority to check the signature of \
+             the SLSA provenance with; set \"sigstore_trusted_root\""
+                .to_string(),
+        ));
+    }
+    let signers = [
+        sigstore::Identity::Release {
+            workflow: config.sigstore_workflow(),
+            tag: &info.tag,
+        },
+        sigstore::Identity::Builder(builder),
+    ];
+    provenance::verify(provenance, sha256, builder, repository, &info.asset_name, &root, &signers)
+        .map_err(InstallerError::Provenance)
+}
+
+/// Print the notes of the releases between `from` and `to` on stderr.
+/// Failing to list them only prints a warning, as the install succeeded.
+fn print_release_notes(source: &dyn source::ReleaseSource, from: Option<&str>, to: &str) {
+    match source.list_releases() {
+        Ok(releases) => {
+            let releases = notes::between(&releases, from, to);
+            if !releases.is_empty() {
+                eprintln!("\n{}", notes::render(releases));
             }
-            Ok(false) => {}
-            Err(e) => {
-                eprintln!("Warning: {e}");
+        }
+        Err(e) => eprintln!("Warning: failed to fetch release notes: {e}"),
+    }
+}
+
+/// Install the `centy-daemon` binary from a release archive on disk, for
+/// machines without network access.
+///
+/// The archive is verified and extracted in place, without a copy in the
+/// download cache. It is verified against `checksums`, a checksums file such as
+/// `checksums-sha256.txt`, which defaults to the `checksums-<algorithm>.txt`
+/// file next to the archive. Its algorithm is told from its name unless
+/// `checksum_algorithm` is configured. Unless `verify_signatures` is off, the
+/// checksums file must be signed by a trusted key, with the signature next to
+/// it (`checksums-sha256.txt.minisig`). The version is
+/// read from the archive name (`centy-daemon-<tag>-<target>.tar.gz`). An
+/// explicit `version` names archives whose name holds none, and must match
+/// the one in the name otherwise. The archive's Sigstore bundle and the release's SLSA
+/// provenance are looked up next to it. `options.source` only supplies the
+/// trusted keys, Sigstore root and provenance policy;
+/// `options.prerelease`, `options.retry` and `options.github_token` are
+/// unused.
+pub fn install_from_file(
+    archive: &Path,
+    checksums: Option<&Path>,
+    version: Option<&str>,
+    options: &InstallOptions,
+) -> Result<PathBuf, InstallerError> {
+    let platform = platform::detect().map_err(InstallerError::Platform)?;
+    let home = home_dir()?;
+    let progress = options
+        .progress
+        .as_deref()
+        .unwrap_or(&progress::NoProgress);
+
+    let archive = std::path::absolute(archive).map_err(|e| {
+        InstallerError::Download(format!("invalid archive path {}: {e}", archive.display()))
+    })?;
+    let asset_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            InstallerError::Download(format!("invalid archive path {}", archive.display()))
+        })?
+        .to_string();
+    let archive_ext = archive_ext_of(&asset_name).ok_or_else(|| {
+        InstallerError::Extraction(format!("unsupported archive format: {asset_name}"))
+    })?;
+
+    progress.on_progress(Progress::started(Phase::Resolving));
+    let named = github::tag_from_asset_name(&asset_name, platform.target);
+    if let (Some(version), Some(named)) = (version, &named) {
+        if github::normalize_tag(version) != *named {
+            return Err(InstallerError::Installation(format!(
+                "version {version} does not match {asset_name}, which is version {named}"
+            )));
+        }
+    }
+    let tag = version
+        .map(github::normalize_tag)
+        .or(named)
+        .ok_or_else(|| {
+            InstallerError::VersionResolution(format!(
+                "cannot tell the version of {asset_name}; expected an archive named \
+                 centy-daemon-<version>-{}{}, or an explicit version",
+                platform.target, platform.archive_ext
+            ))
+        })?;
+
+    let checksums = checksums
+        .map_or_else(|| Ok(checksums_next_to(&archive)), std::path::absolute)
+        .map_err(|e| InstallerError::Download(format!("invalid checksums path: {e}")))?;
+    let checksums_text = std::fs::read_to_string(&checksums).map_err(|e| {
+        InstallerError::Download(format!(
+            "failed to read checksums file {}: {e}",
+            checksums.display()
+        ))
+    })?;
+    let config = source_config(options)?;
+    // Every file is read from disk, so there is nothing to retry
+    let client = reqwest::blocking::Client::new();
+    let retry = Retrier::quiet(RetryPolicy::none());
+    verify_checksums(
+        &client,
+        &retry,
+        &download::file_url(&checksums),
+        &checksums_text,
+        config.signing_keys(),
+    )?;
+    let checksums_url = download::file_url(&checksums);
+    let expected = find_checksum(&checksums_text, &checksums_url, &asset_name, &config)?;
+
+    let info = source::ReleaseInfo {
+        tag,
+        asset_url: download::file_url(&archive),
+        asset_name,
+        size: None,
+        checksum: source::Checksum::File(checksums_url),
+        provenance: provenance_next_to(&archive),
+    };
+    if let Some(result) = reuse_installed(&home, &info, &expected, options) {
+        return result;
+    }
+
+    let asset = download::open_and_verify(&archive, &expected, progress)
+        .map_err(InstallerError::Download)?;
+    verify_sigstore_bundle(&client, &retry, &info, &asset.sha256, &config)?;
+    verify_provenance(&client, &retry, &info, &asset.sha256, &config)?;
+
+    install_asset(&home, &platform, info, &asset, archive_ext, options)
+}
+
+/// Path of the checksums file next to `archive`: the first
+/// `checksums-<algorithm>.txt` that exists, `checksums-sha256.txt` if none.
+fn checksums_next_to(archive: &Path) -> PathBuf {
+    DigestAlgorithm::ALL
+        .into_iter()
+        .map(|algorithm| archive.with_file_name(algorithm.checksums_file()))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| archive.with_file_name(bundle::CHECKSUMS_FILE))
+}
+
+/// URL of the SLSA provenance next to `archive`: the first file in its
+/// directory, by name, with the `.intoto.jsonl` extension.
+fn provenance_next_to(archive: &Path) -> Option<String> {
+    let mut files: Vec<_> = std::fs::read_dir(archive.parent()?)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(provenance::PROVENANCE_EXT))
+        })
+        .collect();
+    files.sort();
+    files.first().map(|path| download::file_url(path))
+}
+
+/// Archive format of a release asset, from its file name.
+fn archive_ext_of(asset_name: &str) -> Option<&'static str> {
+    let name = asset_name.to_ascii_lowercase();
+    [(".tar.gz", ".tar.gz"), (".tgz", ".tar.gz"), (".zip", ".zip")]
+        .into_iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, ext)| ext)
+}
+
+/// Switch to release `info` if it is already installed with a matching
+/// checksum, unless `options.force` is set. Returns `None` when it still has
+/// to be installed.
+fn reuse_installed(
+    home: &Path,
+    info: &source::ReleaseInfo,
+    expected: &digest::Digest,
+    options: &InstallOptions,
+) -> Option<Result<PathBuf, InstallerError>> {
+    if options.force
+        || !receipt::matches_installed_in(home, &info.tag, &info.asset_name, expected)
+    {
+        return None;
+    }
+
+    if install::active_version_in(home).as_deref() == Some(info.tag.as_str()) {
+        eprintln!("centy-daemon {} is already installed", info.tag);
+        return Some(Ok(install::binary_path_in(home)));
+    }
+    eprintln!(
+        "centy-daemon {} is already installed, switching to it",
+        info.tag
+    );
+    Some(activate(home, &info.tag, options.restart))
+}
+
+/// Extract the binary from a verified `asset`, make it the active version and
+/// record the install receipt.
+fn install_asset(
+    home: &Path,
+    platform: &platform::Platform,
+    info: source::ReleaseInfo,
+    asset: &download::DownloadedAsset,
+    archive_ext: &str,
+    options: &InstallOptions,
+) -> Result<PathBuf, InstallerError> {
+    let progress = options
+        .progress
+        .as_deref()
+        .unwrap_or(&progress::NoProgress);
+
+    progress.on_progress(Progress::started(Phase::Extracting));
+    let binary = extract_to_tempfile(&asset.file, archive_ext, &options.extraction)?;
+
+    progress.on_progress(Progress::started(Phase::Installing));
+    let pending = install::install_binary_to(binary, &info.tag, home)
+        .map_err(InstallerError::Installation)?;
+
+    let path = finish_install(pending, options.restart)?;
+
+    let receipt = InstallReceipt {
+        version: info.tag,
+        target: platform.target.to_string(),
+        asset_name: info.asset_name,
+        sha256: asset.sha256.clone(),
+        digest: (asset.digest.algorithm != DigestAlgorithm::Sha256)
+            .then(|| asset.digest.to_string()),
+        source_url: info.asset_url,
+        installed_at: receipt::now_rfc3339(),
+        installer_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    if let Err(e) = receipt::write_receipt_in(home, &receipt) {
+        eprintln!("Warning: {e}");
+    }
+
+    Ok(path)
+}
+
+/// Download the archives of a release for several platforms into a bundle
+/// that can be carried to machines without network access.
+///
+/// `targets` are target triples; every supported platform is bundled when it
+/// is empty. The bundle holds the verified archives, the release checksums
+/// file and its signature when one is published, the Sigstore bundle of each
+/// archive and the SLSA provenance when they are checked, and a
+/// `manifest.json` index.
+/// It is written to the directory `output`, or packed into a gzipped tarball
+/// when `output` ends in `.tar.gz` or `.tgz`. Archives from the bundle install
+/// with [`install_from_file`]. The index is written unsigned, so it only works
+/// as a `file://` release manifest with signature checks off, or once it is
+/// signed as `manifest.json.minisig` by a trusted key, for example with
+/// `minisign -S -m manifest.json`. Each archive is reported to
+/// [`ProgressObserver::on_bundle`] before it is downloaded.
+/// `options.restart` and `options.force` are unused.
+pub fn bundle(
+    version: Option<&str>,
+    targets: &[&str],
+    output: &Path,
+    options: &InstallOptions,
+) -> Result<PathBuf, InstallerError> {
+    let platforms = if targets.is_empty() {
+        platform::SUPPORTED.to_vec()
+    } else {
+        targets
+            .iter()
+            .map(|target| platform::for_target(target))
+            .collect::<Result<_, _>>()
+            .map_err(InstallerError::Platform)?
+    };
+    let progress = options
+        .progress
+        .as_deref()
+        .unwrap_or(&progress::NoProgress);
+
+    let client = reqwest::blocking::Client::new();
+    let retry = retrier(options);
+
+    progress.on_progress(Progress::started(Phase::Resolving));
+    let config = source_config(options)?;
+    let source = release_source(&client, &config, options)?;
+    let version_info = source
+        .resolve_version(version, options.prerelease)
+        .map_err(version_error)?;
+
+    if let Some(notice) = &version_info.notice {
+        eprintln!("{notice}");
+    }
+
+    let infos = platforms
+        .iter()
+        .map(|platform| source.release_info(&version_info.tag, platform))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(version_error)?;
+
+    // Tarballs are staged in a temporary directory, then packed
+    let staging = bundle::is_tarball(output)
+        .then(tempfile::tempdir)
+        .transpose()
+        .map_err(|e| {
+            InstallerError::Bundle(format!("failed to create temporary directory: {e}"))
+        })?;
+    let dir = staging.as_ref().map_or(output, tempfile::TempDir::path);
+    std::fs::create_dir_all(dir)
+        .map_err(|e| InstallerError::Bundle(format!("failed to create {}: {e}", dir.display())))?;
+
+    // A single provenance file attests the archives of every platform
+    let provenance = match (&config.provenance, infos.first()) {
+        (Some(_), Some(info)) => {
+            let name = info
+                .provenance
+                .as_deref()
+                .and_then(|url| url.rsplit('/').next())
+                .unwrap_or_default()
+                .to_string();
+            Some((name, fetch_provenance(&client, &retry, info)?))
+        }
+        _ => None,
+    };
+    let provenance_text = provenance.as_ref().map(|(_, text)| text.as_str());
+    let assets = bundle_assets(&client, &platforms, infos, dir, &config, options, provenance_text)?;
+    if let Some((name, text)) = &provenance {
+        bundle::write_file(&dir.join(name), text).map_err(InstallerError::Bundle)?;
+    }
+    let provenance_name = provenance.as_ref().map(|(name, _)| name.as_str());
+    bundle::write_index(dir, &version_info.tag, &assets, provenance_name)
+        .map_err(InstallerError::Bundle)?;
+
+    if staging.is_some() {
+        let root = format!("centy-daemon-{}", version_info.tag);
+        bundle::write_tarball(dir, output, &root).map_err(InstallerError::Bundle)?;
+    }
+
+    Ok(output.to_path_buf())
+}
+
+/// Download and verify the archive of each release in `infos` into `dir`,
+/// along with the checksums file and its signature. Each archive is checked
+/// against `provenance` when `config` requires it.
+fn bundle_assets(
+    client: &reqwest::blocking::Client,
+    platforms: &[platform::Platform],
+    infos: Vec<source::ReleaseInfo>,
+    dir: &Path,
+    config: &SourceConfig,
+    options: &InstallOptions,
+    provenance: Option<&str>,
+) -> Result<Vec<bundle::BundledAsset>, InstallerError> {
+    let progress = options
+        .progress
+        .as_deref()
+        .unwrap_or(&progress::NoProgress);
+    let retry = retrier(options);
+
+    // GitHub releases publish a single checksums file covering every asset
+    let checksums_url = infos.iter().find_map(|info| match &info.checksum {
+        source::Checksum::File(url) => Some(url.clone()),
+        source::Checksum::Digest(_) => None,
+    });
+    let checksums = checksums_url
+        .as_deref()
+        .map(|url| download::fetch_text(client, &retry, url, "failed to fetch checksums"))
+        .transpose()
+        .map_err(InstallerError::Download)?;
+    let signature = match (&checksums_url, &checksums) {
+        (Some(url), Some(text)) => {
+            verify_checksums(client, &retry, url, text, config.signing_keys())?
+        }
+        _ => None,
+    };
+
+    let cache_dir = download::cache_dir_in(&home_dir()?);
+    let mut assets = Vec::new();
+    // Algorithm of the checksums file, which names its copy in the bundle
+    let mut listed = DigestAlgorithm::Sha256;
+    for (platform, info) in platforms.iter().zip(infos) {
+        let expected = match &info.checksum {
+            source::Checksum::File(url) => {
+                let text = checksums.as_deref().unwrap_or_default();
+                let expected = find_checksum(text, url, &info.asset_name, config)?;
+                listed = expected.algorithm;
+                expected
             }
+            source::Checksum::Digest(digest) => digest.clone(),
+        };
+
+        progress.on_bundle(&info.asset_name);
+        let mut asset = download::download_and_verify(
+            client,
+            &retry,
+            &info,
+            &expected,
+            &cache_dir,
+            progress,
+        )
+        .map_err(InstallerError::Download)?;
+        let sigstore_bundle =
+            verify_sigstore_bundle(client, &retry, &info, &asset.sha256, config)?;
+        if let (Some(policy), Some(provenance)) = (&config.provenance, provenance) {
+            check_provenance(provenance, &info, &asset.sha256, config, policy)?;
+        }
+        bundle::save_asset(&mut asset, &dir.join(&info.asset_name))
+            .map_err(InstallerError::Bundle)?;
+        if let Some(sigstore_bundle) = sigstore_bundle {
+            let path = dir.join(format!("{}{}", info.asset_name, sigstore::BUNDLE_EXT));
+            bundle::write_file(&path, &sigstore_bundle).map_err(InstallerError::Bundle)?;
         }
+
+        assets.push(bundle::BundledAsset {
+            target: platform.target,
+            name: info.asset_name,
+            sha256: asset.sha256,
+        });
+    }
+
+    let (Some(url), Some(checksums)) = (checksums_url, checksums) else {
+        bundle::write_checksums(dir, &assets).map_err(InstallerError::Bundle)?;
+        return Ok(assets);
+    };
+    let checksums_name = listed.checksums_file();
+    bundle::write_file(&dir.join(&checksums_name), &checksums).map_err(InstallerError::Bundle)?;
+
+    // Without trusted keys the signature is unchecked, but still bundled
+    let signature = signature.map_or_else(
+        || {
+            let signature_url = format!("{url}{}", signature::SIGNATURE_EXT);
+            download::fetch_text(client, &retry, &signature_url, "failed to fetch signature")
+        },
+        Ok,
+    );
+    match signature {
+        Ok(signature) => {
+            let path = dir.join(format!("{checksums_name}{}", signature::SIGNATURE_EXT));
+            bundle::write_file(&path, &signature).map_err(InstallerError::Bundle)?;
+        }
+        Err(e) => eprintln!("Warning: bundling without a checksums signature: {e}"),
+    }
+
+    Ok(assets)
+}
+
+/// Switch the active `centy-daemon` to an already installed version.
+///
+/// No download takes place; the version must have been installed before.
+/// When `restart` is `true`, the daemon is restarted if it was already running.
+/// Returns the path to the active binary (`~/.centy/bin/centy-daemon`).
+pub fn use_version(version: &str, restart: bool) -> Result<PathBuf, InstallerError> {
+    let tag = github::normalize_tag(version);
+    activate(&home_dir()?, &tag, restart)
+}
+
+fn activate(home: &Path, tag: &str, restart: bool) -> Result<PathBuf, InstallerError> {
+    let pending =
+        install::activate_version_in(home, tag).map_err(InstallerError::Installation)?;
+
+    let path = finish_install(pending, restart)?;
+
+    if let Err(e) = receipt::activate_receipt_in(home, tag) {
+        eprintln!("Warning: {e}");
     }
 
     Ok(path)
 }
 
+/// Restart the daemon on the new binary if requested, then commit the install.
+///
+/// If the restart fails, the previous binary is restored and, when the daemon
+/// had already been stopped, started again from the restored binary.
+fn finish_install(
+    pending: install::PendingInstall,
+    restart: bool,
+) -> Result<PathBuf, InstallerError> {
+    if restart {
+        if let Err(e) = restart_daemon(pending.path()) {
+            let path = pending.path().to_path_buf();
+            let message = match pending.rollback() {
+                Ok(()) => restart_previous(&path).map_or_else(
+                    |start_err| format!("{e}; restored the previous binary but {start_err}"),
+                    |()| format!("{e}; restored the previous binary"),
+                ),
+                Err(rollback_err) => {
+                    format!("{e}; failed to restore the previous binary: {rollback_err}")
+                }
+            };
+            return Err(InstallerError::DaemonRestart(message));
+        }
+    }
+
+    pending.commit().map_err(InstallerError::Installation)
+}
+
+fn restart_daemon(path: &Path) -> Result<(), String> {
+    if daemon::restart_if_running(path)? {
+        eprintln!("Restarted centy-daemon to use the updated version");
+    }
+    Ok(())
+}
+
+/// Start the restored binary if a failed restart left the daemon stopped.
+fn restart_previous(path: &Path) -> Result<(), String> {
+    if daemon::running_pid().is_some() || !path.exists() {
+        return Ok(());
+    }
+    daemon::start_daemon(path)
+}
+
+/// Current state of the local installation.
+#[derive(Debug)]
+pub struct Status {
+    /// Path where the `centy-daemon` binary is (or would be) installed.
+    pub binary_path: PathBuf,
+    /// Whether a binary exists at `binary_path`.
+    pub installed: bool,
+    /// Tag of the version `binary_path` points at, if known.
+    pub active_version: Option<String>,
+    /// Tags of every version stored under `~/.centy/versions`.
+    pub installed_versions: Vec<String>,
+    /// PID of the running daemon, if one was found.
+    pub daemon_pid: Option<u32>,
+}
+
+fn home_dir() -> Result<PathBuf, InstallerError> {
+    dirs::home_dir()
+        .ok_or_else(|| InstallerError::Installation("could not determine home directory".into()))
+}
+
+/// Report whether `centy-daemon` is installed and whether it is running.
+pub fn status() -> Result<Status, InstallerError> {
+    let home = home_dir()?;
+    let binary_path = install::binary_path_in(&home);
+    let installed = binary_path.exists();
+    let installed_versions =
+        install::installed_versions_in(&home).map_err(InstallerError::Installation)?;
+
+    Ok(Status {
+        binary_path,
+        installed,
+        active_version: install::active_version_in(&home),
+        installed_versions,
+        daemon_pid: daemon::running_pid(),
+    })
+}
+
+/// Remove the installed `centy-daemon` binary and every stored version.
+///
+/// Returns the removed path, or `None` if the binary was not installed.
+/// A running daemon is left untouched.
+pub fn uninstall() -> Result<Option<PathBuf>, InstallerError> {
+    let home = home_dir()?;
+    let removed =
+        install::uninstall_binary_from(&home).map_err(InstallerError::Installation)?;
+    receipt::remove_receipt_in(&home).map_err(InstallerError::Installation)?;
+    // Partial downloads are only kept to resume an install, so drop them too
+    let _ = std::fs::remove_dir_all(download::cache_dir_in(&home));
+    Ok(removed)
+}
+
+/// Read the install receipt of the active version.
+///
+/// Returns `None` if nothing was installed, or the active version was
+/// installed by a release of `centy-installer` that did not write receipts.
+pub fn read_receipt() -> Result<Option<InstallReceipt>, InstallerError> {
+    receipt::read_receipt_in(&home_dir()?).map_err(InstallerError::Installation)
+}
+
+fn version_error(error: source::ApiError) -> InstallerError {
+    match error {
+        source::ApiError::RateLimited(message) => InstallerError::RateLimited(message),
+        source::ApiError::Signature(message) => InstallerError::Signature(message),
+        source::ApiError::NotFound(message) | source::ApiError::Other(message) => {
+            InstallerError::VersionResolution(message)
+        }
+    }
+}
+
+/// Resolve the tag of the latest available release without installing it.
+///
+/// Set `prerelease` to `true` to include pre-release versions.
+pub fn latest_version(prerelease: bool) -> Result<String, InstallerError> {
+    latest_version_with(&InstallOptions {
+        prerelease,
+        ..InstallOptions::default()
+    })
+}
+
+/// Resolve the tag of the latest available release with the given options.
+///
+/// `options.prerelease` includes pre-release versions; `options.retry`,
+/// `options.source` and `options.github_token` apply as for [`install_with`].
+pub fn latest_version_with(options: &InstallOptions) -> Result<String, InstallerError> {
+    let client = reqwest::blocking::Client::new();
+
+    let config = source_config(options)?;
+    let source = release_source(&client, &config, options)?;
+    let version_info = source
+        .resolve_version(None, options.prerelease)
+        .map_err(version_error)?;
+
+    if let Some(notice) = &version_info.notice {
+        eprintln!("{notice}");
+    }
+
+    Ok(version_info.tag)
+}
+
+/// List every release available from the configured release source, newest
+/// first.
+///
+/// Drafts are included, marked with [`RemoteRelease::draft`]; GitHub only
+/// lists them to authenticated users with push access.
+pub fn list_releases() -> Result<Vec<RemoteRelease>, InstallerError> {
+    list_releases_with(&InstallOptions::default())
+}
+
+/// List every release available with the given options, newest first.
+///
+/// `options.retry`, `options.source` and `options.github_token` apply
+/// as for [`install_with`].
+pub fn list_releases_with(
+    options: &InstallOptions,
+) -> Result<Vec<RemoteRelease>, InstallerError> {
+    let client = reqwest::blocking::Client::new();
+
+    let config = source_config(options)?;
+    let source = release_source(&client, &config, options)?;
+    source.list_releases().map_err(version_error)
+}
+
+/// Return the releases between `from` and `to` with their notes, newest
+/// first.
+///
+/// `from` defaults to the active version and `to` to the latest stable
+/// release; `to` may also be a semver range. The releases newer than `from`
+/// up to and including `to` are returned, skipping pre-releases other than
+/// `to`. When nothing is installed and `from` is `None`, only `to` is
+/// returned.
+pub fn release_notes(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<RemoteRelease>, InstallerError> {
+    release_notes_with(from, to, &InstallOptions::default())
+}
+
+/// Return the releases between `from` and `to` with their notes, like
+/// [`release_notes`], with the given options.
+///
+/// `options.retry`, `options.source` and `options.github_token` apply as for
+/// [`install_with`].
+pub fn release_notes_with(
+    from: Option<&str>,
+    to: Option<&str>,
+    options: &InstallOptions,
+) -> Result<Vec<RemoteRelease>, InstallerError> {
+    let client = reqwest::blocking::Client::new();
+    let home = home_dir()?;
+
+    let config = source_config(options)?;
+    let source = release_source(&client, &config, options)?;
+    let to = source.resolve_version(to, false).map_err(version_error)?.tag;
+    let from = from
+        .map(github::normalize_tag)
+        .or_else(|| install::active_version_in(&home));
+
+    let releases = source.list_releases().map_err(version_error)?;
+    Ok(notes::between(&releases, from.as_deref(), &to)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Render release notes returned by [`release_notes`] for display.
+#[must_use]
+pub fn format_release_notes(releases: &[RemoteRelease]) -> String {
+    notes::render(releases)
+}
+
 #[cfg(test)]
 #[allow(
     clippy::unwrap_used,
@@ -149,9 +1181,51 @@ mod tests {
         assert!(debug.contains("Platform"));
     }
 
+    #[test]
+    fn query_functions_use_source_of_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("manifest.json");
+        std::fs::write(
+            &manifest,
+            r#"{"versions": [
+                {"version": "v0.5.0-rc.1", "prerelease": true, "assets": {}},
+                {"version": "v0.4.0", "prerelease": false, "assets": {}}
+            ]}"#,
+        )
+        .unwrap();
+        let options = InstallOptions {
+            source: Some(SourceConfig {
+                manifest: Some(download::file_url(&manifest)),
+                verify_signatures: false,
+                ..SourceConfig::default()
+            }),
+            ..InstallOptions::default()
+        };
+
+        let releases = list_releases_with(&options).unwrap();
+        let tags: Vec<_> = releases.iter().map(|release| release.tag.as_str()).collect();
+        assert_eq!(tags, ["v0.5.0-rc.1", "v0.4.0"]);
+        assert_eq!(latest_version_with(&options).unwrap(), "v0.4.0");
+        let prerelease = InstallOptions {
+            prerelease: true,
+            ..options.clone()
+        };
+        assert_eq!(latest_version_with(&prerelease).unwrap(), "v0.5.0-rc.1");
+        let notes = release_notes_with(Some("v0.3.0"), None, &options).unwrap();
+        assert_eq!(notes.len(), 1);
+    }
+
+    #[test]
+    fn archive_ext_from_asset_name() {
+        assert_eq!(archive_ext_of("centy-daemon-v1.0.0-x.tar.gz"), Some(".tar.gz"));
+        assert_eq!(archive_ext_of("centy-daemon-v1.0.0-x.TGZ"), Some(".tar.gz"));
+        assert_eq!(archive_ext_of("centy-daemon-v1.0.0-x.zip"), Some(".zip"));
+        assert_eq!(archive_ext_of("centy-daemon-v1.0.0-x.rar"), None);
+    }
+
     #[test]
     fn extract_binary_tar_gz() {
-        use std::io::Write;
+        use std::io::Cursor;
 
         let mut tar_builder = tar::Builder::new(Vec::new());
         let content = b"binary-data";
@@ -169,13 +1243,15 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_binary(&gz_bytes, ".tar.gz").unwrap();
+        let mut result = Vec::new();
+        let limits = ExtractionLimits::default();
+        extract_binary(Cursor::new(gz_bytes), ".tar.gz", &mut result, &limits).unwrap();
         assert_eq!(result, b"binary-data");
     }
 
     #[test]
     fn extract_binary_zip() {
-        use std::io::{Cursor, Write};
+        use std::io::Cursor;
 
         let buf = Cursor::new(Vec::new());
         let mut zip = zip::ZipWriter::new(buf);
@@ -184,13 +1260,18 @@ mod tests {
         zip.write_all(b"zip-binary").unwrap();
         let zip_bytes = zip.finish().unwrap().into_inner();
 
-        let result = extract_binary(&zip_bytes, ".zip").unwrap();
+        let mut result = Vec::new();
+        let limits = ExtractionLimits::default();
+        extract_binary(Cursor::new(zip_bytes), ".zip", &mut result, &limits).unwrap();
         assert_eq!(result, b"zip-binary");
     }
 
     #[test]
     fn extract_binary_unsupported_format() {
-        let result = extract_binary(b"data", ".rar");
+        use std::io::Cursor;
+
+        let limits = ExtractionLimits::default();
+        let result = extract_binary(Cursor::new(b"data"), ".rar", &mut Vec::new(), &limits);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("unsupported archive format: .rar"));
@@ -198,7 +1279,7 @@ mod tests {
 
     #[test]
     fn extract_binary_tar_gz_missing_binary() {
-        use std::io::Write;
+        use std::io::Cursor;
 
         let mut tar_builder = tar::Builder::new(Vec::new());
         let content = b"other";
@@ -216,7 +1297,9 @@ mod tests {
         encoder.write_all(&tar_bytes).unwrap();
         let gz_bytes = encoder.finish().unwrap();
 
-        let result = extract_binary(&gz_bytes, ".tar.gz");
+        let limits = ExtractionLimits::default();
+        let result =
+            extract_binary(Cursor::new(gz_bytes.clone()), ".tar.gz", &mut Vec::new(), &limits);
         assert!(result.is_err());
         match result.unwrap_err() {
             InstallerError::Extraction(msg) => {
@@ -224,5 +1307,209 @@ mod tests {
             }
             other => panic!("expected Extraction error, got: {other:?}"),
         }
+
+        let limits = ExtractionLimits {
+            max_entries: 0,
+            ..limits
+        };
+        let result = extract_binary(Cursor::new(gz_bytes), ".tar.gz", &mut Vec::new(), &limits);
+        match result.unwrap_err() {
+            InstallerError::UnsafeArchive(msg) => {
+                assert_eq!(msg, "archive has more than 0 entries");
+            }
+            other => panic!("expected UnsafeArchive error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn install_from_file_rejects_version_not_in_archive_name() {
+        let platform = platform::detect().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp
+            .path()
+            .join(format!("centy-daemon-v0.4.0-{}{}", platform.target, platform.archive_ext));
+        std::fs::write(&archive, b"archive").unwrap();
+
+        let options = InstallOptions::default();
+        let err = install_from_file(&archive, None, Some("0.3.0"), &options).unwrap_err();
+        let InstallerError::Installation(message) = err else {
+            panic!("expected Installation error, got: {err:?}");
+        };
+        assert!(message.contains("0.3.0") && message.contains("v0.4.0"), "{message}");
+    }
+
+    #[test]
+    fn install_from_file_rejects_unsigned_checksums() {
+        let platform = platform::detect().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp
+            .path()
+            .join(format!("centy-daemon-v0.4.0-{}{}", platform.target, platform.archive_ext));
+        std::fs::write(&archive, b"archive").unwrap();
+        let checksums = format!("{}  {}\n", "0".repeat(64), archive.display());
+        std::fs::write(tmp.path().join(bundle::CHECKSUMS_FILE), checksums).unwrap();
+
+        let options = InstallOptions {
+            source: Some(SourceConfig {
+                trusted_keys: vec![signature::tests::KEY.to_string()],
+                verify_signatures: true,
+                ..SourceConfig::default()
+            }),
+            ..InstallOptions::default()
+        };
+        let err = install_from_file(&archive, None, None, &options).unwrap_err();
+        assert!(matches!(err, InstallerError::Signature(_)), "{err}");
+    }
+
+    #[test]
+    fn verify_checksums_requires_signature_by_trusted_key() {
+        use signature::tests::{CHECKSUMS, KEY, OTHER_KEY, SIGNATURE};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(bundle::CHECKSUMS_FILE);
+        std::fs::write(&path, CHECKSUMS).unwrap();
+        let url = download::file_url(&path);
+        let client = reqwest::blocking::Client::new();
+        let verify = |keys: &[&str]| {
+            let keys: Vec<_> = keys.iter().map(ToString::to_string).collect();
+            let retry = Retrier::quiet(RetryPolicy::none());
+            verify_checksums(&client, &retry, &url, CHECKSUMS, Some(&keys))
+        };
+
+        let retry = Retrier::quiet(RetryPolicy::none());
+        assert!(verify_checksums(&client, &retry, &url, CHECKSUMS, None).unwrap().is_none());
+        let err = verify(&[]).unwrap_err();
+        assert!(err.to_string().contains("no release signing key is trusted"), "{err}");
+        assert!(matches!(verify(&[KEY]), Err(InstallerError::Signature(_))));
+
+        std::fs::write(tmp.path().join("checksums-sha256.txt.minisig"), SIGNATURE).unwrap();
+        assert_eq!(verify(&[OTHER_KEY, KEY]).unwrap().as_deref(), Some(SIGNATURE));
+        let err = verify(&[OTHER_KEY]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "signature verification failed: checksums-sha256.txt is not signed by a trusted key"
+        );
+    }
+
+    #[test]
+    fn verify_sigstore_bundle_requires_bundle_next_to_asset() {
+        use sigstore::tests::{BUNDLE, ROOT, SHA256};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let asset_name = "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz";
+        let info = source::ReleaseInfo {
+            tag: "v0.4.0".to_string(),
+            asset_url: download::file_url(&tmp.path().join(asset_name)),
+            asset_name: asset_name.to_string(),
+            size: None,
+            checksum: source::Checksum::Digest(digest::Digest::sha256(SHA256)),
+            provenance: None,
+        };
+        let client = reqwest::blocking::Client::new();
+        let verify = |config: &SourceConfig| {
+            verify_sigstore_bundle(&client, &Retrier::quiet(RetryPolicy::none()), &info, SHA256, config)
+        };
+
+        let empty = SourceConfig {
+            verify_sigstore: true,
+            ..SourceConfig::default()
+        };
+        let err = verify(&empty).unwrap_err();
+        assert!(err.to_string().contains("lists no certificate authority"), "{err}");
+        let off = SourceConfig {
+            verify_sigstore: false,
+            ..SourceConfig::default()
+        };
+        assert!(verify(&off).unwrap().is_none());
+        let config = SourceConfig {
+            sigstore_root: ROOT.to_string(),
+            verify_sigstore: true,
+            ..SourceConfig::default()
+        };
+        assert!(matches!(verify(&config), Err(InstallerError::Signature(_))));
+
+        std::fs::write(tmp.path().join(format!("{asset_name}.sigstore.json")), BUNDLE).unwrap();
+        assert_eq!(verify(&config).unwrap().as_deref(), Some(BUNDLE));
+        // The release source does not choose whose signatures are trusted
+        let mirror = SourceConfig {
+            owner: "someone".to_string(),
+            name: "fork".to_string(),
+            ..config.clone()
+        };
+        assert_eq!(verify(&mirror).unwrap().as_deref(), Some(BUNDLE));
+        let fork = SourceConfig {
+            sigstore_identity: Some("https://github.com/someone/fork/.github/workflows/".into()),
+            ..config
+        };
+        assert!(matches!(verify(&fork), Err(InstallerError::Signature(_))));
+    }
+
+    #[test]
+    fn verify_provenance_checks_policy() {
+        use provenance::tests::{envelope, provenance_v02, PROVENANCE, SHA256};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp.path().join("centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz");
+        std::fs::write(&archive, "").unwrap();
+        let mut info = source::ReleaseInfo {
+            tag: "v0.4.0".to_string(),
+            asset_url: download::file_url(&archive),
+            asset_name: "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+            size: None,
+            checksum: source::Checksum::Digest(digest::Digest::sha256(SHA256)),
+            provenance: provenance_next_to(&archive),
+        };
+        let client = reqwest::blocking::Client::new();
+        let required = SourceConfig {
+            provenance: Some(ProvenancePolicy::default()),
+            sigstore_root: sigstore::tests::ROOT.to_string(),
+            ..SourceConfig::default()
+        };
+        let verify = |info: &source::ReleaseInfo, sha256: &str, config: &SourceConfig| {
+            verify_provenance(&client, &Retrier::quiet(RetryPolicy::none()), info, sha256, config)
+        };
+
+        verify(&info, SHA256, &SourceConfig::default()).unwrap();
+        let err = verify(&info, SHA256, &required).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "provenance check failed: release v0.4.0 publishes no SLSA provenance"
+        );
+
+        let path = tmp.path().join("multiple.intoto.jsonl");
+        std::fs::write(&path, envelope(&provenance_v02(SHA256))).unwrap();
+        info.provenance = provenance_next_to(&archive);
+        assert_eq!(info.provenance, Some(download::file_url(&path)));
+        let err = verify(&info, SHA256, &required).unwrap_err();
+        assert!(err.to_string().contains("signature cannot be checked"), "{err}");
+
+        std::fs::write(&path, PROVENANCE).unwrap();
+        verify(&info, SHA256, &required).unwrap();
+        let unsigned_root = SourceConfig {
+            sigstore_root: SourceConfig::default().sigstore_root,
+            ..required.clone()
+        };
+        let err = verify(&info, SHA256, &unsigned_root).unwrap_err();
+        assert!(err.to_string().contains("lists no certificate authority"), "{err}");
+        assert!(matches!(
+            verify(&info, &"1".repeat(64), &required),
+            Err(InstallerError::Provenance(_))
+        ));
+        let fork = SourceConfig {
+            provenance: Some(ProvenancePolicy {
+                repository: Some("https://github.com/acme/fork".to_string()),
+                ..ProvenancePolicy::default()
+            }),
+            ..required.clone()
+        };
+        let err = verify(&info, SHA256, &fork).unwrap_err();
+        assert!(err.to_string().contains("was built from"), "{err}");
+        // The release source does not choose which repository is trusted
+        let mirror = SourceConfig {
+            owner: "acme".to_string(),
+            name: "fork".to_string(),
+            ..required
+        };
+        verify(&info, SHA256, &mirror).unwrap();
     }
 }
@@ -1,18 +1,206 @@
+mod cli;
+mod progress_bar;
+
+use std::path::Path;
 use std::process::ExitCode;
+use std::sync::Arc;
+
+use centy_installer::{InstallOptions, InstallerError};
+use cli::Command;
 
 fn main() -> ExitCode {
-    let args: Vec<String> = std::env::args().collect();
-    let prerelease = args.iter().any(|a| a == "--pre");
-    let restart = !args.iter().any(|a| a == "--no-restart");
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    match centy_installer::install(None, prerelease, restart) {
-        Ok(path) => {
-            println!("{}", path.display());
-            ExitCode::SUCCESS
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("Run 'centy-installer --help' for usage.");
+            return ExitCode::from(2);
         }
+    };
+
+    match run(command) {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("error: {e}");
             ExitCode::FAILURE
         }
     }
 }
+
+fn run(command: Command) -> Result<(), InstallerError> {
+    match command {
+        Command::Install {
+            version,
+            prerelease,
+            restart,
+            force,
+            notes,
+        } => {
+            let options = InstallOptions {
+                release_notes: notes,
+                ..install_options(prerelease, restart, force)
+            };
+            let path = centy_installer::install_with(version.as_deref(), &options)?;
+            println!("{}", path.display());
+        }
+        Command::InstallFile {
+            archive,
+            checksums,
+            version,
+            restart,
+            force,
+        } => {
+            let options = install_options(false, restart, force);
+            let path = centy_installer::install_from_file(
+                Path::new(&archive),
+                checksums.as_deref().map(Path::new),
+                version.as_deref(),
+                &options,
+            )?;
+            println!("{}", path.display());
+        }
+        Command::Use { version, restart } => {
+            let path = centy_installer::use_version(&version, restart)?;
+            println!("{}", path.display());
+        }
+        Command::Uninstall => match centy_installer::uninstall()? {
+            Some(path) => println!("Removed {}", path.display()),
+            None => println!("centy-daemon is not installed"),
+        },
+        Command::List => {
+            let status = centy_installer::status()?;
+            if status.installed_versions.is_empty() {
+                println!("centy-daemon is not installed");
+            }
+            for tag in &status.installed_versions {
+                let marker = if status.active_version.as_ref() == Some(tag) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{marker} {tag}");
+            }
+        }
+        Command::Status => print_status()?,
+        Command::Check { prerelease } => {
+            let options = install_options(prerelease, true, false);
+            let tag = centy_installer::latest_version_with(&options)?;
+            println!("{tag}");
+        }
+        Command::ListRemote { prerelease } => list_remote(prerelease)?,
+        Command::Changelog { from, to } => changelog(from.as_deref(), to.as_deref())?,
+        Command::Bundle {
+            version,
+            prerelease,
+            targets,
+            output,
+        } => {
+            let options = install_options(prerelease, true, false);
+            let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+            let path = centy_installer::bundle(
+                version.as_deref(),
+                &targets,
+                Path::new(&output),
+                &options,
+            )?;
+            println!("{}", path.display());
+        }
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Version => println!("centy-installer {}", env!("CARGO_PKG_VERSION")),
+    }
+
+    Ok(())
+}
+
+/// Print the installation and daemon status, with the install receipt of the
+/// active version when there is one.
+fn print_status() -> Result<(), InstallerError> {
+    let status = centy_installer::status()?;
+    if status.installed {
+        println!("installed: {}", status.binary_path.display());
+    } else {
+        println!("installed: no");
+    }
+    if let Some(tag) = &status.active_version {
+        println!("active version: {tag}");
+    }
+    if let Some(receipt) = centy_installer::read_receipt()? {
+        println!("target: {}", receipt.target);
+        println!("source: {}", receipt.source_url);
+        println!("sha256: {}", receipt.sha256);
+        if let Some(digest) = &receipt.digest {
+            println!("verified digest: {digest}");
+        }
+        println!("installed at: {}", receipt.installed_at);
+        println!("installed by: centy-installer {}", receipt.installer_version);
+    }
+    match status.daemon_pid {
+        Some(pid) => println!("daemon: running (PID {pid})"),
+        None => println!("daemon: not running"),
+    }
+
+    Ok(())
+}
+
+/// Print the release notes from `from` (default: the active version) to `to`
+/// (default: the latest stable release).
+fn changelog(from: Option<&str>, to: Option<&str>) -> Result<(), InstallerError> {
+    let options = install_options(false, true, false);
+    let releases = centy_installer::release_notes_with(from, to, &options)?;
+    if releases.is_empty() {
+        println!("no newer releases");
+    } else {
+        print!("{}", centy_installer::format_release_notes(&releases));
+    }
+
+    Ok(())
+}
+
+/// Print the available releases, marking the active (`*`) and installed (`+`)
+/// ones. Pre-releases are only shown with `prerelease`; drafts never are.
+fn list_remote(prerelease: bool) -> Result<(), InstallerError> {
+    let releases = centy_installer::list_releases_with(&install_options(prerelease, true, false))?;
+    let status = centy_installer::status()?;
+
+    let shown: Vec<_> = releases
+        .iter()
+        .filter(|release| !release.draft && (prerelease || !release.prerelease))
+        .collect();
+    if shown.is_empty() {
+        println!("no releases found");
+    }
+    let width = shown.iter().map(|release| release.tag.len()).max().unwrap_or(0);
+    for release in shown {
+        let marker = if status.active_version.as_ref() == Some(&release.tag) {
+            "*"
+        } else if status.installed_versions.contains(&release.tag) {
+            "+"
+        } else {
+            " "
+        };
+        let date = release
+            .published_at
+            .as_deref()
+            .and_then(|date| date.get(..10))
+            .unwrap_or("");
+        let kind = if release.prerelease { "  pre-release" } else { "" };
+        let line = format!("{marker} {:<width$}  {date}{kind}", release.tag);
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+/// Options for commands that look up or download releases, reporting
+/// progress on stderr.
+fn install_options(prerelease: bool, restart: bool, force: bool) -> InstallOptions {
+    InstallOptions {
+        prerelease,
+        restart,
+        force,
+        progress: Some(Arc::new(progress_bar::ProgressReporter::new())),
+        ..InstallOptions::default()
+    }
+}
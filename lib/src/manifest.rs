@@ -0,0 +1,630 @@
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+use crate::digest::{Digest, DigestAlgorithm};
+use crate::download;
+use crate::github::normalize_tag;
+use crate::platform::Platform;
+use crate::retry::Retrier;
+use crate::signature::{self, SIGNATURE_EXT};
+use crate::source::{
+    self, ApiError, Checksum, ReleaseInfo, ReleaseSource, RemoteAsset, RemoteRelease, VersionInfo,
+};
+use crate::version::{self, Requested};
+
+/// Releases listed in a static JSON manifest, served over HTTP or read from
+/// a `file://` URL.
+///
+/// ```json
+/// {
+///   "channels": { "stable": "v0.4.0", "prerelease": "v0.5.0-alpha.1" },
+///   "versions": [
+///     {
+///       "version": "v0.4.0",
+///       "prerelease": false,
+///       "assets": {
+///         "x86_64-unknown-linux-gnu": {
+///           "url": "v0.4.0/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+///           "sha256": "…"
+///         }
+///       }
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Each asset lists its digest under `sha256`, `sha512` or `blake3`. When it
+/// lists several, the first of those is verified, and all must be valid.
+///
+/// `versions` is ordered newest first. Channels are optional: without them
+/// the newest stable version, or the newest version of any kind with `--pre`,
+/// is used. A version may also carry its release `notes` and the URL of its
+/// SLSA `provenance`. Asset URLs may be relative to the manifest.
+///
+/// Unless signatures are off, the manifest must carry a detached minisign
+/// signature at its URL followed by `.minisig`.
+pub struct ManifestSource {
+    client: Client,
+    retry: Retrier,
+    url: String,
+    /// Keys that must have signed the manifest; `None` to skip the check.
+    trusted_keys: Option<Vec<String>>,
+    /// The parsed manifest, fetched on first use.
+    manifest: OnceCell<Manifest>,
+}
+
+impl ManifestSource {
+    pub const fn new(
+        client: Client,
+        retry: Retrier,
+        url: String,
+        trusted_keys: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            client,
+            retry,
+            url,
+            trusted_keys,
+            manifest: OnceCell::new(),
+        }
+    }
+
+    fn manifest(&self) -> Result<&Manifest, ApiError> {
+        if let Some(manifest) = self.manifest.get() {
+            return Ok(manifest);
+        }
+
+        let text = download::fetch_text(
+            &self.client,
+            &self.retry,
+            &self.url,
+            "failed to fetch release manifest",
+        )?;
+        if let Some(keys) = &self.trusted_keys {
+            signature::require_keys(keys).map_err(ApiError::Signature)?;
+            let signature = download::fetch_text(
+                &self.client,
+                &self.retry,
+                &format!("{}{SIGNATURE_EXT}", self.url),
+                "failed to fetch release manifest signature",
+            )
+            .map_err(ApiError::Signature)?;
+            signature::verify(text.as_bytes(), &signature, keys, "release manifest")
+                .map_err(ApiError::Signature)?;
+        }
+        let manifest = Manifest::parse(&text)?;
+        Ok(self.manifest.get_or_init(|| manifest))
+    }
+}
+
+impl ReleaseSource for ManifestSource {
+    fn resolve_version(
+        &self,
+        version: Option<&str>,
+        prerelease: bool,
+    ) -> Result<VersionInfo, ApiError> {
+        if let Some(v) = version {
+            let manifest = self.manifest()?;
+            let versions = manifest.versions.iter();
+            let tag = match version::parse_requested(v) {
+                Requested::Tag(tag) => {
+                    version::find_tag(&tag, versions.map(|r| r.version.as_str()))?
+                }
+                Requested::Range(range) => {
+                    let releases = versions.map(|r| (r.version.as_str(), r.prerelease));
+                    version::resolve_range(v, &range, releases, prerelease)?
+                }
+            };
+            return Ok(VersionInfo { tag, notice: None });
+        }
+
+        let manifest = self.manifest()?;
+        if prerelease {
+            let tag = manifest
+                .channels
+                .get("prerelease")
+                .cloned()
+                .or_else(|| manifest.versions.first().map(|r| r.version.clone()))
+                .ok_or_else(|| "no releases found in manifest".to_string())?;
+            return Ok(VersionInfo { tag, notice: None });
+        }
+
+        let tag = manifest
+            .channels
+            .get("stable")
+            .cloned()
+            .or_else(|| {
+                manifest
+                    .versions
+                    .iter()
+                    .find(|r| !r.prerelease)
+                    .map(|r| r.version.clone())
+            })
+            .ok_or_else(|| "no stable release found in manifest".to_string())?;
+
+        // Only a pre-release newer than the stable release is worth a notice
+        let prereleases = manifest
+            .versions
+            .iter()
+            .filter(|r| {
+                r.prerelease || version::parse_tag(&r.version).is_some_and(|v| !v.pre.is_empty())
+            })
+            .map(|r| r.version.as_str())
+            .chain(manifest.channels.get("prerelease").map(String::as_str));
+        let notice = version::newer_prerelease(&tag, prereleases).map(source::prerelease_notice);
+        Ok(VersionInfo { tag, notice })
+    }
+
+    fn release_info(&self, tag: &str, platform: &Platform) -> Result<ReleaseInfo, ApiError> {
+        let manifest = self.manifest()?;
+        let release = manifest
+            .versions
+            .iter()
+            .find(|r| r.version == tag)
+            .ok_or_else(|| format!("version {tag} is not listed in the release manifest"))?;
+        let asset = release.assets.get(platform.target).ok_or_else(|| {
+            let available: Vec<_> = release.assets.keys().map(String::as_str).collect();
+            format!(
+                "release {tag} has no asset for {} in the manifest; available platforms: {}",
+                platform.target,
+                available.join(", ")
+            )
+        })?;
+
+        let asset_url = resolve_url(&self.url, &asset.url);
+        let asset_name = file_name(&asset_url)
+            .ok_or_else(|| format!("asset URL has no file name: {asset_url}"))?
+            .to_string();
+
+        Ok(ReleaseInfo {
+            tag: tag.to_string(),
+            asset_name,
+            asset_url,
+            size: None,
+            checksum: Checksum::Digest(asset.digest.clone()),
+            provenance: release.provenance.as_deref().map(|url| resolve_url(&self.url, url)),
+        })
+    }
+
+    fn list_releases(&self) -> Result<Vec<RemoteRelease>, ApiError> {
+        let manifest = self.manifest()?;
+        let releases = manifest
+            .versions
+            .iter()
+            .map(|release| RemoteRelease {
+                tag: release.version.clone(),
+                prerelease: release.prerelease,
+                draft: false,
+                published_at: None,
+                notes: release.notes.clone(),
+                assets: release
+                    .assets
+                    .values()
+                    .map(|asset| {
+                        let url = resolve_url(&self.url, &asset.url);
+                        RemoteAsset {
+                            name: file_name(&url).unwrap_or_default().to_string(),
+                            url,
+                            size: None,
+                            digest: Some(asset.digest.to_string()),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(releases)
+    }
+}
+
+struct Manifest {
+    channels: BTreeMap<String, String>,
+    /// Releases, newest first.
+    versions: Vec<Release>,
+}
+
+struct Release {
+    version: String,
+    prerelease: bool,
+    notes: Option<String>,
+    /// URL of the SLSA provenance of the release assets.
+    provenance: Option<String>,
+    /// Assets keyed by target triple.
+    assets: BTreeMap<String, Asset>,
+}
+
+struct Asset {
+    url: String,
+    digest: Digest,
+}
+
+impl Manifest {
+    fn parse(text: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| format!("failed to parse release manifest: {e}"))?;
+        let invalid = |what: &str| format!("invalid release manifest: {what}");
+
+        let channels = match value.get("channels") {
+            None | Some(Value::Null) => BTreeMap::new(),
+            Some(Value::Object(channels)) => channels
+                .iter()
+                .map(|(name, tag)| {
+                    tag.as_str()
+                        .map(|tag| (name.clone(), normalize_tag(tag)))
+                        .ok_or_else(|| invalid(&format!("channel \"{name}\" is not a string")))
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(invalid("\"channels\" is not an object")),
+        };
+
+        let versions = value
+            .get("versions")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("\"versions\" is missing or not an array"))?
+            .iter()
+            .map(Release::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|e| invalid(&e))?;
+
+        Ok(Self { channels, versions })
+    }
+}
+
+impl Release {
+    fn parse(value: &Value) -> Result<Self, String> {
+        let version = value
+            .get("version")
+            .and_then(Value::as_str)
+            .ok_or("release without a \"version\"")?;
+        let version = normalize_tag(version);
+
+        let assets = value
+            .get("assets")
+            .and_then(Value::as_object)
+            .ok_or_else(|| format!("release {version} has no \"assets\" object"))?
+            .iter()
+            .map(|(target, asset)| {
+                let url = asset
+                    .get("url")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+                    .ok_or_else(|| format!("asset {target} of {version} has no \"url\""))?;
+                let digest = asset_digest(asset)
+                    .map_err(|e| format!("asset {target} of {version} {e}"))?;
+                Ok((target.clone(), Asset { url, digest }))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            prerelease: value
+                .get("prerelease")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            notes: value.get("notes").and_then(Value::as_str).map(String::from),
+            provenance: value.get("provenance").and_then(Value::as_str).map(String::from),
+            version,
+            assets,
+        })
+    }
+}
+
+/// Read the digests an asset lists under the ids of the supported
+/// algorithms, and return the first. Every one listed must be valid.
+fn asset_digest(asset: &Value) -> Result<Digest, String> {
+    let digests = DigestAlgorithm::ALL
+        .into_iter()
+        .filter_map(|algorithm| Some((algorithm, asset.get(algorithm.id())?)))
+        .map(|(algorithm, hex)| {
+            let hex = hex
+                .as_str()
+                .ok_or_else(|| format!("has a non-string \"{}\"", algorithm.id()))?;
+            Digest::from_hex(algorithm, hex).map_err(|e| format!("has an {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    digests
+        .into_iter()
+        .next()
+        .ok_or_else(|| "has no \"sha256\", \"sha512\" or \"blake3\" digest".to_string())
+}
+
+/// Return the last path segment of `url`, ignoring any query or fragment.
+fn file_name(url: &str) -> Option<&str> {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+}
+
+/// Resolve `url` against the URL of the manifest when it is relative.
+fn resolve_url(manifest_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    let base = manifest_url
+        .rsplit_once('/')
+        .map_or(manifest_url, |(base, _)| base);
+    format!("{base}/{}", url.trim_start_matches("./"))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::significant_drop_tightening,
+    reason = "tests use unwrap/expect/panic and short-lived locks for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use std::fs;
+
+    const LINUX: Platform = Platform {
+        target: "x86_64-unknown-linux-gnu",
+        archive_ext: ".tar.gz",
+    };
+
+    const MANIFEST: &str = r#"{
+        "channels": { "stable": "0.4.0" },
+        "versions": [
+            {
+                "version": "v0.5.0-alpha.1",
+                "prerelease": true,
+                "assets": {
+                    "x86_64-unknown-linux-gnu": {
+                        "url": "pre.tar.gz",
+                        "sha256": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+                    }
+                }
+            },
+            {
+                "version": "v0.4.0",
+                "notes": "First stable release.",
+                "provenance": "v0.4.0/multiple.intoto.jsonl",
+                "assets": {
+                    "x86_64-unknown-linux-gnu": {
+                        "url": "v0.4.0/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                        "sha256": "BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB"
+                    }
+                }
+            }
+        ]
+    }"#;
+
+    fn source_at(url: &str) -> ManifestSource {
+        let retry = Retrier::quiet(RetryPolicy {
+            initial_delay: std::time::Duration::ZERO,
+            ..RetryPolicy::default()
+        });
+        ManifestSource::new(Client::new(), retry, url.to_string(), None)
+    }
+
+    #[test]
+    fn resolves_stable_channel_with_prerelease_notice() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/releases/manifest.json")
+            .with_body(MANIFEST)
+            .create();
+
+        let source = source_at(&format!("{}/releases/manifest.json", server.url()));
+        let info = source.resolve_version(None, false).unwrap();
+        assert_eq!(info.tag, "v0.4.0");
+        assert!(info.notice.unwrap().contains("v0.5.0-alpha.1"));
+
+        let info = source.release_info("v0.4.0", &LINUX).unwrap();
+        assert_eq!(
+            info.asset_url,
+            format!(
+                "{}/releases/v0.4.0/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                server.url()
+            )
+        );
+        assert_eq!(
+            info.asset_name,
+            "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(info.checksum, Checksum::Digest(Digest::sha256(&"b".repeat(64))));
+        assert_eq!(
+            info.provenance,
+            Some(format!("{}/releases/v0.4.0/multiple.intoto.jsonl", server.url()))
+        );
+
+        // The manifest is fetched once and reused
+        mock.assert();
+    }
+
+    #[test]
+    fn no_notice_for_older_prerelease() {
+        let manifest = MANIFEST.replace("v0.5.0-alpha.1", "v0.4.0-rc.1");
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), manifest).unwrap();
+
+        let url = format!("file://{}/manifest.json", tmp.path().display());
+        let info = source_at(&url).resolve_version(None, false).unwrap();
+        assert_eq!(info.tag, "v0.4.0");
+        assert!(info.notice.is_none());
+    }
+
+    #[test]
+    fn prerelease_resolves_newest_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), MANIFEST).unwrap();
+
+        let url = format!("file://{}/manifest.json", tmp.path().display());
+        let info = source_at(&url).resolve_version(None, true).unwrap();
+        assert_eq!(info.tag, "v0.5.0-alpha.1");
+        assert!(info.notice.is_none());
+    }
+
+    #[test]
+    fn ranges_skip_flagged_prereleases() {
+        let manifest = MANIFEST.replace("v0.5.0-alpha.1", "v0.5.0");
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), manifest).unwrap();
+
+        let source = source_at(&format!("file://{}/manifest.json", tmp.path().display()));
+        assert_eq!(source.resolve_version(Some("*"), false).unwrap().tag, "v0.4.0");
+        assert_eq!(source.resolve_version(Some("*"), true).unwrap().tag, "v0.5.0");
+    }
+
+    #[test]
+    fn stable_without_channel_skips_prereleases() {
+        let manifest = MANIFEST.replace(r#""channels": { "stable": "0.4.0" },"#, "");
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), manifest).unwrap();
+
+        let url = format!("file://{}/manifest.json", tmp.path().display());
+        let info = source_at(&url).resolve_version(None, false).unwrap();
+        assert_eq!(info.tag, "v0.4.0");
+    }
+
+    #[test]
+    fn ranges_resolve_against_listed_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), MANIFEST).unwrap();
+        let source = source_at(&format!("file://{}/manifest.json", tmp.path().display()));
+
+        assert_eq!(source.resolve_version(Some("~0.4"), false).unwrap().tag, "v0.4.0");
+        assert_eq!(
+            source.resolve_version(Some(">=0.4"), true).unwrap().tag,
+            "v0.5.0-alpha.1"
+        );
+        let err = source.resolve_version(Some("^0.6"), false).unwrap_err();
+        assert!(err.to_string().contains("no release matches"));
+
+        assert_eq!(source.resolve_version(Some("0.4.0"), false).unwrap().tag, "v0.4.0");
+        let err = source.resolve_version(Some("v0.9.0"), false).unwrap_err();
+        assert!(err.to_string().starts_with("release v0.9.0 not found; closest versions: "));
+    }
+
+    #[test]
+    fn lists_manifest_releases() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), MANIFEST).unwrap();
+        let url = format!("file://{}/manifest.json", tmp.path().display());
+
+        let releases = source_at(&url).list_releases().unwrap();
+        let tags: Vec<_> = releases.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["v0.5.0-alpha.1", "v0.4.0"]);
+        assert!(releases.first().unwrap().prerelease);
+        assert_eq!(releases.get(1).unwrap().notes.as_deref(), Some("First stable release."));
+        let asset = releases.get(1).unwrap().assets.first().unwrap();
+        assert_eq!(asset.name, "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz");
+        assert_eq!(
+            asset.url,
+            format!("file://{}/v0.4.0/{}", tmp.path().display(), asset.name)
+        );
+    }
+
+    #[test]
+    fn release_info_reports_missing_version_and_target() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), MANIFEST).unwrap();
+        let source = source_at(&format!("file://{}/manifest.json", tmp.path().display()));
+
+        let err = source.release_info("v9.9.9", &LINUX).unwrap_err();
+        assert!(err.to_string().contains("v9.9.9 is not listed"));
+
+        let windows = Platform {
+            target: "x86_64-pc-windows-msvc",
+            archive_ext: ".zip",
+        };
+        let err = source.release_info("v0.4.0", &windows).unwrap_err();
+        assert!(err.to_string().contains("no asset for x86_64-pc-windows-msvc"));
+    }
+
+    #[test]
+    fn invalid_manifest_is_rejected() {
+        assert!(Manifest::parse("not-json").is_err());
+        let err = Manifest::parse(r#"{"versions": [{"version": "v1.0.0"}]}"#)
+            .err()
+            .unwrap();
+        assert!(err.contains("has no \"assets\" object"));
+        let err = Manifest::parse(
+            r#"{"versions": [{"version": "v1.0.0", "assets": {"t": {"url": "a"}}}]}"#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("has no \"sha256\""));
+    }
+
+    #[test]
+    fn asset_digests_are_validated() {
+        let parse = |digests: &str| {
+            let asset = format!(r#"{{"url": "a", {digests}}}"#);
+            let text = format!(
+                r#"{{"versions": [{{"version": "v1.0.0", "assets": {{"t": {asset}}}}}]}}"#
+            );
+            Manifest::parse(&text).map(|manifest| {
+                let release = manifest.versions.into_iter().next().unwrap();
+                release.assets.into_values().next().unwrap().digest
+            })
+        };
+        let sha512 = "C".repeat(128);
+        let blake3 = "d".repeat(64);
+
+        let digest = parse(&format!(r#""sha512": "{sha512}""#)).unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha512);
+        assert_eq!(digest.hex, sha512.to_ascii_lowercase());
+        let digest = parse(&format!(r#""blake3": "{blake3}""#)).unwrap();
+        assert_eq!(digest.to_string(), format!("blake3:{blake3}"));
+        let digest = parse(&format!(r#""blake3": "{blake3}", "sha256": "{blake3}""#)).unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha256);
+
+        for invalid in [
+            r#""sha256": "abc""#.to_string(),
+            format!(r#""sha256": "{}""#, "g".repeat(64)),
+            format!(r#""sha512": "{blake3}""#),
+            format!(r#""sha256": "{blake3}", "blake3": "xyz""#),
+            r#""sha256": 42"#.to_string(),
+        ] {
+            let err = parse(&invalid).err().unwrap();
+            assert!(err.starts_with("invalid release manifest: asset t of v1.0.0 has "), "{err}");
+        }
+    }
+
+    #[test]
+    fn resolve_url_handles_relative_and_absolute() {
+        let base = "https://cdn.example.com/centy/manifest.json";
+        assert_eq!(
+            resolve_url(base, "./v1/a.tar.gz"),
+            "https://cdn.example.com/centy/v1/a.tar.gz"
+        );
+        assert_eq!(
+            resolve_url(base, "https://other.example.com/a.tar.gz"),
+            "https://other.example.com/a.tar.gz"
+        );
+    }
+
+    #[test]
+    fn signed_manifest_requires_trusted_signature() {
+        use crate::signature::tests::{KEY, OTHER_KEY};
+
+        const SIGNED: &str = "{\"versions\": [{\"version\": \"v0.4.0\", \"assets\": {}}]}\n";
+        const SIGNATURE: &str = "\
+untrusted comment: signature from minisign secret key
+RUTv2fozqof3i6fnlCHLe1s3BJ6gUFJ9QwodVYJGSoVsKUPz64+RHBgm6MAELaZvrtdlXS+sb8vqbkmevia/KOylicWRIMOr+g0=
+trusted comment: timestamp:1735689600\tfile:manifest.json
+s8HQ0R8G4zUcQSM2Lg3KCt06i8twu6jtRT9Ls2NDc6vSAAKzyyDAhmWjBbD4KnM6lpy7u7TtqaOVM7qwolixCA==
+";
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("manifest.json"), SIGNED).unwrap();
+        let url = format!("file://{}/manifest.json", tmp.path().display());
+        let signed_by = |key: &str| {
+            let retry = Retrier::quiet(RetryPolicy::none());
+            ManifestSource::new(Client::new(), retry, url.clone(), Some(vec![key.into()]))
+                .resolve_version(None, false)
+        };
+
+        let err = signed_by(KEY).unwrap_err();
+        assert!(matches!(err, ApiError::Signature(_)), "{err}");
+
+        fs::write(tmp.path().join("manifest.json.minisig"), SIGNATURE).unwrap();
+        assert_eq!(signed_by(KEY).unwrap().tag, "v0.4.0");
+        let err = signed_by(OTHER_KEY).unwrap_err();
+        assert_eq!(err.to_string(), "release manifest is not signed by a trusted key");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::source::RemoteRelease;
+use crate::version::parse_tag;
+
+/// Return the releases of `releases` whose notes describe an upgrade from
+/// `from` to `to`, in the order they are listed.
+///
+/// These are the published releases newer than `from`, up to and including
+/// `to`. Pre-releases other than `to` itself are skipped. Without `from`, or
+/// when either tag is not a semver version, only `to` is returned. Nothing is
+/// returned when `from` is not older than `to`.
+pub fn between<'a>(
+    releases: &'a [RemoteRelease],
+    from: Option<&str>,
+    to: &str,
+) -> Vec<&'a RemoteRelease> {
+    let range = from.and_then(parse_tag).zip(parse_tag(to));
+    if range.as_ref().is_some_and(|(from, to)| from >= to) || from == Some(to) {
+        return Vec::new();
+    }
+
+    releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter(|release| {
+            if release.tag == to {
+                return true;
+            }
+            let (Some((from, to)), Some(version)) = (&range, parse_tag(&release.tag)) else {
+                return false;
+            };
+            !release.prerelease && version.pre.is_empty() && version > *from && version < *to
+        })
+        .collect()
+}
+
+/// Render the notes of `releases` for display, one section per release
+/// headed by its tag and publication date.
+pub fn render<'a>(releases: impl IntoIterator<Item = &'a RemoteRelease>) -> String {
+    let sections: Vec<_> = releases
+        .into_iter()
+        .map(|release| {
+            let date = release
+                .published_at
+                .as_deref()
+                .and_then(|date| date.get(..10))
+                .map(|date| format!(" ({date})"))
+                .unwrap_or_default();
+            let notes = release
+                .notes
+                .as_deref()
+                .map_or("No release notes.", str::trim);
+            format!("{}{date}\n\n{notes}\n", release.tag)
+        })
+        .collect();
+    sections.join("\n")
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, notes: Option<&str>) -> RemoteRelease {
+        RemoteRelease {
+            tag: tag.to_string(),
+            prerelease: tag.contains('-'),
+            draft: false,
+            published_at: None,
+            notes: notes.map(String::from),
+            assets: Vec::new(),
+        }
+    }
+
+    fn releases() -> Vec<RemoteRelease> {
+        let mut draft = release("v0.6.0", None);
+        draft.draft = true;
+        vec![
+            draft,
+            release("v0.5.1-rc.1", None),
+            release("v0.5.0", None),
+            release("v0.5.0-rc.1", None),
+            release("v0.4.2", None),
+            release("v0.4.1", None),
+            release("v0.4.0", None),
+        ]
+    }
+
+    fn tags(releases: &[&RemoteRelease]) -> Vec<String> {
+        releases.iter().map(|release| release.tag.clone()).collect()
+    }
+
+    #[test]
+    fn between_lists_stable_releases_since_from() {
+        let releases = releases();
+        assert_eq!(
+            tags(&between(&releases, Some("v0.4.0"), "v0.5.0")),
+            ["v0.5.0", "v0.4.2", "v0.4.1"]
+        );
+        assert_eq!(
+            tags(&between(&releases, Some("v0.4.1"), "v0.5.1-rc.1")),
+            ["v0.5.1-rc.1", "v0.5.0", "v0.4.2"]
+        );
+        assert_eq!(tags(&between(&releases, None, "v0.4.2")), ["v0.4.2"]);
+        assert_eq!(tags(&between(&releases, Some("nightly"), "v0.4.2")), ["v0.4.2"]);
+    }
+
+    #[test]
+    fn between_is_empty_without_an_upgrade() {
+        let releases = releases();
+        assert!(between(&releases, Some("v0.5.0"), "v0.4.0").is_empty());
+        assert!(between(&releases, Some("v0.5.0"), "v0.5.0").is_empty());
+        assert!(between(&releases, Some("v0.5.0"), "v0.6.0").is_empty());
+    }
+
+    #[test]
+    fn render_shows_tag_date_and_notes() {
+        let mut first = release("v0.5.0", Some("## Changes\n\n- faster installs\n"));
+        first.published_at = Some("2025-03-01T12:00:00Z".to_string());
+        let second = release("v0.4.2", None);
+
+        assert_eq!(
+            render([&first, &second]),
+            "v0.5.0 (2025-03-01)\n\n## Changes\n\n- faster installs\n\n\
+             v0.4.2\n\nNo release notes.\n"
+        );
+    }
+}
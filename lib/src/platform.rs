@@ -1,26 +1,66 @@
 use std::env::consts::{ARCH, OS};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Platform {
     pub target: &'static str,
     pub archive_ext: &'static str,
 }
 
+/// Every platform releases are published for.
+pub const SUPPORTED: [Platform; 5] = [
+    Platform {
+        target: "aarch64-apple-darwin",
+        archive_ext: ".tar.gz",
+    },
+    Platform {
+        target: "x86_64-apple-darwin",
+        archive_ext: ".tar.gz",
+    },
+    Platform {
+        target: "aarch64-unknown-linux-gnu",
+        archive_ext: ".tar.gz",
+    },
+    Platform {
+        target: "x86_64-unknown-linux-gnu",
+        archive_ext: ".tar.gz",
+    },
+    Platform {
+        target: "x86_64-pc-windows-msvc",
+        archive_ext: ".zip",
+    },
+];
+
+impl Platform {
+    /// Whether the target runs on `os` and `arch`, named as in
+    /// [`std::env::consts`].
+    fn runs_on(&self, os: &str, arch: &str) -> bool {
+        let os = if os == "macos" { "darwin" } else { os };
+        let mut parts = self.target.split('-');
+        parts.next() == Some(arch) && parts.any(|part| part == os)
+    }
+}
+
+/// Find the entry of [`SUPPORTED`] for the platform the installer runs on.
 pub fn detect() -> Result<Platform, String> {
-    let target = match (OS, ARCH) {
-        ("macos", "aarch64") => "aarch64-apple-darwin",
-        ("macos", "x86_64") => "x86_64-apple-darwin",
-        ("linux", "aarch64") => "aarch64-unknown-linux-gnu",
-        ("linux", "x86_64") => "x86_64-unknown-linux-gnu",
-        ("windows", "x86_64") => "x86_64-pc-windows-msvc",
-        _ => return Err(format!("unsupported platform: {OS}-{ARCH}")),
-    };
-
-    let archive_ext = match OS {
-        "windows" => ".zip",
-        _ => ".tar.gz",
-    };
-
-    Ok(Platform { target, archive_ext })
+    SUPPORTED
+        .into_iter()
+        .find(|platform| platform.runs_on(OS, ARCH))
+        .ok_or_else(|| format!("unsupported platform: {OS}-{ARCH}"))
+}
+
+/// Look up the supported platform with the given target triple.
+pub fn for_target(target: &str) -> Result<Platform, String> {
+    SUPPORTED
+        .iter()
+        .find(|platform| platform.target == target)
+        .copied()
+        .ok_or_else(|| {
+            let supported: Vec<_> = SUPPORTED.iter().map(|platform| platform.target).collect();
+            format!(
+                "unsupported target: {target} (supported: {})",
+                supported.join(", ")
+            )
+        })
 }
 
 #[cfg(test)]
@@ -49,4 +89,34 @@ mod tests {
             assert_eq!(platform.archive_ext, ".tar.gz");
         }
     }
+
+    #[test]
+    fn detected_platform_is_supported() {
+        let platform = detect().unwrap();
+        assert_eq!(for_target(platform.target).unwrap(), platform);
+    }
+
+    #[test]
+    fn targets_map_to_os_and_arch() {
+        let host = |os, arch| {
+            SUPPORTED
+                .into_iter()
+                .find(|platform| platform.runs_on(os, arch))
+                .map(|platform| platform.target)
+        };
+        assert_eq!(host("macos", "aarch64"), Some("aarch64-apple-darwin"));
+        assert_eq!(host("macos", "x86_64"), Some("x86_64-apple-darwin"));
+        assert_eq!(host("linux", "aarch64"), Some("aarch64-unknown-linux-gnu"));
+        assert_eq!(host("linux", "x86_64"), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(host("windows", "x86_64"), Some("x86_64-pc-windows-msvc"));
+        assert_eq!(host("windows", "aarch64"), None);
+        assert_eq!(host("freebsd", "x86_64"), None);
+    }
+
+    #[test]
+    fn for_target_rejects_unknown_triples() {
+        let err = for_target("riscv64gc-unknown-linux-gnu").unwrap_err();
+        assert!(err.contains("unsupported target: riscv64gc-unknown-linux-gnu"));
+        assert!(err.contains("x86_64-pc-windows-msvc"));
+    }
 }
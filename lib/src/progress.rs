@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+/// Stage of an install reported through a [`ProgressObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Resolving the version to install.
+    Resolving,
+    /// Downloading the release archive; byte counts are reported.
+    Downloading,
+    /// Verifying the downloaded archive.
+    Verifying,
+    /// Extracting the binary from the archive.
+    Extracting,
+    /// Writing the binary into place.
+    Installing,
+}
+
+/// A single progress update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    /// Bytes received so far. Only meaningful while downloading.
+    pub bytes: u64,
+    /// Total size from the `Content-Length` header, when the server sent one.
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// An update marking the start of `phase`.
+    #[must_use]
+    pub const fn started(phase: Phase) -> Self {
+        Self {
+            phase,
+            bytes: 0,
+            total: None,
+        }
+    }
+}
+
+/// A failed request that is about to be retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryNotice {
+    /// Why the last attempt failed.
+    pub message: String,
+    /// How long until the next attempt.
+    pub delay: Duration,
+    /// Number of the next attempt, counting the first one.
+    pub attempt: u32,
+    /// Total number of attempts allowed.
+    pub max_attempts: u32,
+}
+
+/// Receives progress updates during an install.
+///
+/// Implemented for any `Fn(Progress)` closure, so a plain closure can be
+/// plugged into [`InstallOptions::progress`](crate::InstallOptions::progress).
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: Progress);
+
+    /// Called before a failed request is retried. Retries are not reported
+    /// unless an observer overrides this.
+    fn on_retry(&self, _notice: &RetryNotice) {}
+
+    /// Called by [`bundle`](crate::bundle) before the archive named
+    /// `asset_name` is downloaded into the bundle. Not reported unless an
+    /// observer overrides this.
+    fn on_bundle(&self, _asset_name: &str) {}
+}
+
+impl<F> ProgressObserver for F
+where
+    F: Fn(Progress) + Send + Sync,
+{
+    fn on_progress(&self, progress: Progress) {
+        self(progress);
+    }
+}
+
+/// Observer that ignores every update, used when none was supplied.
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {
+    fn on_progress(&self, _progress: Progress) {}
+}
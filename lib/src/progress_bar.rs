@@ -0,0 +1,268 @@
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+
+use centy_installer::{Phase, Progress, ProgressObserver, RetryNotice};
+
+/// Width of the download bar, in characters.
+const BAR_WIDTH: u64 = 30;
+
+/// Without a `Content-Length`, print a line every this many bytes.
+const LINE_INTERVAL: u64 = 5 * 1024 * 1024;
+
+/// Renders install progress on stderr.
+///
+/// Terminals get a download bar redrawn in place; anything else (CI logs,
+/// pipes) gets a line per 10% downloaded so the output stays readable.
+pub struct ProgressReporter {
+    interactive: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    phase: Option<Phase>,
+    /// Last download step printed in line mode.
+    last_step: Option<u64>,
+    /// Whether the cursor is at the end of an unfinished bar line.
+    bar_drawn: bool,
+}
+
+impl ProgressReporter {
+    pub fn new() -> Self {
+        Self {
+            interactive: std::io::stderr().is_terminal(),
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl ProgressObserver for ProgressReporter {
+    fn on_progress(&self, progress: Progress) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let mut stderr = std::io::stderr().lock();
+
+        if state.phase != Some(progress.phase) {
+            if state.bar_drawn {
+                let _ = writeln!(stderr);
+                state.bar_drawn = false;
+            }
+            state.phase = Some(progress.phase);
+            state.last_step = None;
+            let _ = writeln!(stderr, "{}", phase_label(progress.phase));
+        }
+
+        if progress.phase != Phase::Downloading {
+            return;
+        }
+
+        if self.interactive {
+            let _ = write!(stderr, "\r{}", render_bar(progress));
+            let _ = stderr.flush();
+            state.bar_drawn = true;
+        } else {
+            let step = line_step(progress);
+            if state.last_step.is_none_or(|last| step > last) {
+                state.last_step = Some(step);
+                let _ = writeln!(stderr, "{}", render_line(progress));
+            }
+        }
+    }
+
+    fn on_retry(&self, notice: &RetryNotice) {
+        self.print_line(&render_retry(notice));
+    }
+
+    fn on_bundle(&self, asset_name: &str) {
+        self.print_line(&format!("Bundling {asset_name}"));
+    }
+}
+
+impl ProgressReporter {
+    /// Print `line` on its own line, below any download bar being drawn.
+    fn print_line(&self, line: &str) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let mut stderr = std::io::stderr().lock();
+
+        if state.bar_drawn {
+            let _ = writeln!(stderr);
+            state.bar_drawn = false;
+        }
+        let _ = writeln!(stderr, "{line}");
+    }
+}
+
+const fn phase_label(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Resolving => "Resolving version...",
+        Phase::Downloading => "Downloading...",
+        Phase::Verifying => "Verifying checksum...",
+        Phase::Extracting => "Extracting...",
+        Phase::Installing => "Installing...",
+    }
+}
+
+/// Render a retry warning such as
+/// `Warning: server returned 503; retrying in 0.5s (attempt 2/4)`.
+fn render_retry(notice: &RetryNotice) -> String {
+    format!(
+        "Warning: {}; retrying in {:.1}s (attempt {}/{})",
+        notice.message,
+        notice.delay.as_secs_f64(),
+        notice.attempt,
+        notice.max_attempts
+    )
+}
+
+/// Render a download bar such as `[=====>      ]  45%  12.3 MiB / 27.1 MiB`.
+fn render_bar(progress: Progress) -> String {
+    let Some(total) = progress.total.filter(|&t| t > 0) else {
+        return format!("  {}", format_bytes(progress.bytes));
+    };
+
+    let done = progress.bytes.min(total);
+    let filled = done.saturating_mul(BAR_WIDTH) / total;
+    let bar: String = (0..BAR_WIDTH)
+        .map(|i| match i.cmp(&filled) {
+            std::cmp::Ordering::Less => '=',
+            std::cmp::Ordering::Equal if filled < BAR_WIDTH => '>',
+            _ => ' ',
+        })
+        .collect();
+
+    format!(
+        "[{bar}] {:>3}%  {} / {}",
+        percent(done, total),
+        format_bytes(progress.bytes),
+        format_bytes(total)
+    )
+}
+
+/// Render a plain progress line for non-interactive output.
+fn render_line(progress: Progress) -> String {
+    progress.total.filter(|&t| t > 0).map_or_else(
+        || format!("Downloaded {}", format_bytes(progress.bytes)),
+        |total| {
+            format!(
+                "Downloaded {} / {} ({}%)",
+                format_bytes(progress.bytes),
+                format_bytes(total),
+                percent(progress.bytes.min(total), total)
+            )
+        },
+    )
+}
+
+/// Bucket used to decide when to print a new line: tenths of the total, or
+/// multiples of [`LINE_INTERVAL`] when the total is unknown.
+fn line_step(progress: Progress) -> u64 {
+    progress
+        .total
+        .filter(|&t| t > 0)
+        .map_or(progress.bytes / LINE_INTERVAL, |total| {
+            progress.bytes.min(total).saturating_mul(10) / total
+        })
+}
+
+const fn percent(done: u64, total: u64) -> u64 {
+    done.saturating_mul(100) / total
+}
+
+/// Format a byte count with one decimal, e.g. `12.3 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [
+        ("GiB", 1024 * 1024 * 1024),
+        ("MiB", 1024 * 1024),
+        ("KiB", 1024),
+    ];
+
+    for (name, size) in UNITS {
+        if bytes >= size {
+            let tenths = bytes.saturating_mul(10) / size;
+            return format!("{}.{} {name}", tenths / 10, tenths % 10);
+        }
+    }
+    format!("{bytes} B")
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    const fn downloading(bytes: u64, total: Option<u64>) -> Progress {
+        Progress {
+            phase: Phase::Downloading,
+            bytes,
+            total,
+        }
+    }
+
+    #[test]
+    fn format_bytes_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(12 * 1024 * 1024 + 300 * 1024), "12.2 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn render_bar_partial() {
+        let bar = render_bar(downloading(50, Some(100)));
+        assert_eq!(
+            bar,
+            "[===============>              ]  50%  50 B / 100 B"
+        );
+    }
+
+    #[test]
+    fn render_bar_complete() {
+        let bar = render_bar(downloading(100, Some(100)));
+        assert!(bar.starts_with("[=============================="));
+        assert!(bar.contains("100%"));
+    }
+
+    #[test]
+    fn render_retry_warning() {
+        let notice = RetryNotice {
+            message: "server returned 503".to_string(),
+            delay: std::time::Duration::from_millis(500),
+            attempt: 2,
+            max_attempts: 4,
+        };
+        assert_eq!(
+            render_retry(&notice),
+            "Warning: server returned 503; retrying in 0.5s (attempt 2/4)"
+        );
+    }
+
+    #[test]
+    fn render_bar_unknown_total() {
+        assert_eq!(render_bar(downloading(2048, None)), "  2.0 KiB");
+    }
+
+    #[test]
+    fn render_line_with_and_without_total() {
+        assert_eq!(
+            render_line(downloading(25, Some(100))),
+            "Downloaded 25 B / 100 B (25%)"
+        );
+        assert_eq!(render_line(downloading(25, None)), "Downloaded 25 B");
+    }
+
+    #[test]
+    fn line_step_buckets() {
+        assert_eq!(line_step(downloading(9, Some(100))), 0);
+        assert_eq!(line_step(downloading(10, Some(100))), 1);
+        assert_eq!(line_step(downloading(100, Some(100))), 10);
+        assert_eq!(line_step(downloading(LINE_INTERVAL * 2, None)), 2);
+    }
+}
@@ -0,0 +1,364 @@
+use serde_json::Value;
+
+use crate::sigstore::{self, Identity, TrustedRoot};
+
+/// Extension of in-toto attestation files published with a release.
+pub const PROVENANCE_EXT: &str = ".intoto.jsonl";
+
+/// Builder expected in the provenance by default: the generic generator of
+/// slsa-github-generator, at any version.
+pub const DEFAULT_BUILDER: &str = concat!(
+    "https://github.com/slsa-framework/slsa-github-generator",
+    "/.github/workflows/generator_generic_slsa3.yml"
+);
+
+/// Source repository expected in the provenance by default. It is never
+/// derived from the release source, so that pointing it at a mirror does not
+/// change which repository archives must be built from.
+pub const DEFAULT_REPOSITORY: &str = "https://github.com/centy-io/centy-daemon";
+
+/// Payload type of a DSSE envelope holding an in-toto statement.
+const IN_TOTO_PAYLOAD: &str = "application/vnd.in-toto+json";
+
+/// Prefix of the predicate types of SLSA provenance.
+const SLSA_PROVENANCE: &str = "https://slsa.dev/provenance/";
+
+/// Check that the SLSA provenance `text` is signed by one of `signers` and
+/// attests the asset named `name`, whose SHA-256 digest is `sha256` (hex), as
+/// built by `builder` from the source repository `repository`.
+///
+/// `text` holds Sigstore bundles of DSSE envelopes of in-toto statements, one
+/// per line. Every bundle is verified offline against `root` before any of
+/// its contents are read; a bare DSSE envelope is rejected, as its
+/// transparency log entry cannot be checked without the network. SLSA
+/// provenance v0.2 and v1 are understood, and the builder ID may carry an
+/// `@<ref>` suffix.
+pub fn verify(
+    text: &str,
+    sha256: &str,
+    builder: &str,
+    repository: &str,
+    name: &str,
+    root: &TrustedRoot,
+    signers: &[Identity<'_>],
+) -> Result<(), String> {
+    let statements = signed_statements(text, root, signers)?;
+    check_statements(&statements, sha256, builder, repository, name)
+}
+
+/// Check that one of `statements` attests the asset `name` as [`verify`]
+/// describes.
+fn check_statements(
+    statements: &[Value],
+    sha256: &str,
+    builder: &str,
+    repository: &str,
+    name: &str,
+) -> Result<(), String> {
+    let statement = statements
+        .iter()
+        .find(|statement| is_provenance_of(statement, sha256))
+        .ok_or_else(|| format!("{name} is not a subject of the SLSA provenance"))?;
+
+    let builder_id = text_at(statement, "/predicate/runDetails/builder/id")
+        .or_else(|| text_at(statement, "/predicate/builder/id"))
+        .ok_or_else(|| format!("the SLSA provenance of {name} names no builder"))?;
+    let built_by = builder_id
+        .strip_prefix(builder)
+        .is_some_and(|version| version.is_empty() || version.starts_with('@'));
+    if !built_by {
+        return Err(format!("{name} was built by {builder_id}, expected {builder}"));
+    }
+
+    let source = source_repository(statement)
+        .ok_or_else(|| format!("the SLSA provenance of {name} names no source repository"))?;
+    if !repository_name(source).eq_ignore_ascii_case(repository_name(repository)) {
+        return Err(format!("{name} was built from {source}, expected {repository}"));
+    }
+    Ok(())
+}
+
+/// Verify the Sigstore bundles in `text` against `root` and `signers`, and
+/// decode the in-toto statements they sign.
+fn signed_statements(
+    text: &str,
+    root: &TrustedRoot,
+    signers: &[Identity<'_>],
+) -> Result<Vec<Value>, String> {
+    serde_json::Deserializer::from_str(text)
+        .into_iter::<Value>()
+        .map(|bundle| {
+            let bundle = bundle.map_err(|e| format!("invalid SLSA provenance: {e}"))?;
+            if bundle.get("dsseEnvelope").is_none() {
+                return Err(
+                    "SLSA provenance is not a Sigstore bundle, so its signature cannot be checked"
+                        .to_string(),
+                );
+            }
+            let (payload_type, payload) = sigstore::verify_envelope(&bundle, root, signers)
+                .map_err(|e| format!("SLSA provenance: {e}"))?;
+            statement(payload_type, &payload)
+        })
+        .collect()
+}
+
+/// Decode the in-toto statement `payload` of a DSSE envelope.
+fn statement(payload_type: &str, payload: &[u8]) -> Result<Value, String> {
+    if payload_type != IN_TOTO_PAYLOAD {
+        return Err("SLSA provenance is not an in-toto attestation".to_string());
+    }
+    serde_json::from_slice(payload).map_err(|e| format!("invalid SLSA provenance statement: {e}"))
+}
+
+/// Whether `statement` is SLSA provenance listing a subject with `sha256`.
+fn is_provenance_of(statement: &Value, sha256: &str) -> bool {
+    let is_provenance = text_at(statement, "/predicateType")
+        .is_some_and(|predicate| predicate.starts_with(SLSA_PROVENANCE));
+    let subjects = statement.get("subject").and_then(Value::as_array);
+    is_provenance
+        && subjects.is_some_and(|subjects| {
+            subjects.iter().any(|subject| {
+                text_at(subject, "/digest/sha256")
+                    .is_some_and(|digest| digest.eq_ignore_ascii_case(sha256))
+            })
+        })
+}
+
+/// Return the URI of the source repository the provenance was built from.
+fn source_repository(statement: &Value) -> Option<&str> {
+    // SLSA v1, as recorded for GitHub Actions workflows
+    text_at(
+        statement,
+        "/predicate/buildDefinition/externalParameters/workflow/repository",
+    )
+    .or_else(|| {
+        statement
+            .pointer("/predicate/buildDefinition/resolvedDependencies")
+            .and_then(Value::as_array)?
+            .iter()
+            .filter_map(|dependency| text_at(dependency, "/uri"))
+            .find(|uri| uri.starts_with("git+"))
+    })
+    // SLSA v0.2
+    .or_else(|| text_at(statement, "/predicate/invocation/configSource/uri"))
+    .or_else(|| text_at(statement, "/predicate/materials/0/uri"))
+}
+
+/// Reduce a repository URI such as
+/// `git+https://github.com/centy-io/centy-daemon.git@refs/tags/v0.4.0` to
+/// `https://github.com/centy-io/centy-daemon`.
+fn repository_name(uri: &str) -> &str {
+    let uri = uri.strip_prefix("git+").unwrap_or(uri);
+    let uri = uri.split_once('@').map_or(uri, |(repository, _)| repository);
+    let uri = uri.trim_end_matches('/');
+    uri.strip_suffix(".git").unwrap_or(uri)
+}
+
+fn text_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(Value::as_str)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+pub mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+    use serde_json::json;
+
+    pub const SHA256: &str = "c40ca88d580de8fa32db7d997b37f971a9a0a050d1b79a12aaf409a2191a32ca";
+    pub const REPOSITORY: &str = "https://github.com/centy-io/centy-daemon";
+
+    /// Fixture written by `tests/fixtures/sigstore/generate.py`: a Sigstore
+    /// bundle of SLSA v0.2 provenance of an archive with [`SHA256`], signed
+    /// by the generic generator of slsa-github-generator.
+    pub const PROVENANCE: &str = include_str!("../tests/fixtures/sigstore/multiple.intoto.jsonl");
+
+    /// A DSSE envelope of `statement`, as one line of an `.intoto.jsonl` file.
+    pub fn envelope(statement: &Value) -> String {
+        json!({
+            "payloadType": IN_TOTO_PAYLOAD,
+            "payload": STANDARD.encode(statement.to_string()),
+            "signatures": [{ "keyid": "", "sig": "" }],
+        })
+        .to_string()
+    }
+
+    /// SLSA v0.2 provenance of slsa-github-generator for an asset with `sha256`.
+    pub fn provenance_v02(sha256: &str) -> Value {
+        json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "subject": [
+                { "name": "centy-daemon-v0.4.0-x86_64-apple-darwin.tar.gz",
+                  "digest": { "sha256": "0".repeat(64) } },
+                { "name": "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz",
+                  "digest": { "sha256": sha256 } },
+            ],
+            "predicate": {
+                "builder": { "id": format!("{DEFAULT_BUILDER}@refs/tags/v2.0.0") },
+                "invocation": { "configSource": {
+                    "uri": "git+https://github.com/centy-io/centy-daemon@refs/tags/v0.4.0",
+                } },
+            },
+        })
+    }
+
+    /// Decode the statements of the DSSE envelopes in `text`, bare or in
+    /// Sigstore bundles, without checking their signatures.
+    fn unsigned(text: &str) -> Result<Vec<Value>, String> {
+        serde_json::Deserializer::from_str(text)
+            .into_iter::<Value>()
+            .map(|envelope| {
+                let envelope = envelope.map_err(|e| format!("invalid SLSA provenance: {e}"))?;
+                let envelope = envelope.get("dsseEnvelope").unwrap_or(&envelope);
+                let payload = STANDARD.decode(text_at(envelope, "/payload").unwrap()).unwrap();
+                statement(text_at(envelope, "/payloadType").unwrap(), &payload)
+            })
+            .collect()
+    }
+
+    /// Check the contents of the provenance `text`, ignoring its signatures.
+    fn check(text: &str, builder: &str, repository: &str) -> Result<(), String> {
+        check_statements(&unsigned(text)?, SHA256, builder, repository, "daemon.tar.gz")
+    }
+
+    fn verify_default(text: &str) -> Result<(), String> {
+        check(text, DEFAULT_BUILDER, REPOSITORY)
+    }
+
+    fn verify_signed(text: &str, signers: &[Identity<'_>]) -> Result<(), String> {
+        let root = TrustedRoot::parse(sigstore::tests::ROOT).unwrap();
+        verify(text, SHA256, DEFAULT_BUILDER, REPOSITORY, "daemon.tar.gz", &root, signers)
+    }
+
+    #[test]
+    fn verifies_signature_before_contents() {
+        let builder = [Identity::Builder(DEFAULT_BUILDER)];
+        verify_signed(PROVENANCE, &builder).unwrap();
+
+        // The fixture is signed by the builder, not by the release workflow
+        let release = [Identity::Release {
+            workflow: sigstore::RELEASE_WORKFLOWS,
+            tag: "v0.4.0",
+        }];
+        let err = verify_signed(PROVENANCE, &release).unwrap_err();
+        assert!(err.contains("not by a workflow under"), "{err}");
+
+        let err = verify_signed(&envelope(&provenance_v02(SHA256)), &builder).unwrap_err();
+        assert_eq!(
+            err,
+            "SLSA provenance is not a Sigstore bundle, so its signature cannot be checked"
+        );
+
+        let mut forged: Value = serde_json::from_str(PROVENANCE).unwrap();
+        *forged.pointer_mut("/dsseEnvelope/payload").unwrap() =
+            STANDARD.encode(provenance_v02(SHA256).to_string()).into();
+        let err = verify_signed(&forged.to_string(), &builder).unwrap_err();
+        assert!(err.contains("does not match the Sigstore bundle"), "{err}");
+
+        let root = TrustedRoot::default();
+        let err = verify(PROVENANCE, SHA256, DEFAULT_BUILDER, REPOSITORY, "a", &root, &builder)
+            .unwrap_err();
+        assert!(err.contains("not recorded in a trusted transparency log"), "{err}");
+    }
+
+    #[test]
+    fn verifies_slsa_v02_provenance() {
+        verify_default(&envelope(&provenance_v02(SHA256))).unwrap();
+        let uppercase = SHA256.to_ascii_uppercase();
+        verify_default(&envelope(&provenance_v02(&uppercase))).unwrap();
+    }
+
+    #[test]
+    fn verifies_slsa_v1_provenance_in_sigstore_bundle() {
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "predicateType": "https://slsa.dev/provenance/v1",
+            "subject": [{ "name": "daemon.tar.gz", "digest": { "sha256": SHA256 } }],
+            "predicate": {
+                "buildDefinition": {
+                    "externalParameters": { "workflow": {
+                        "ref": "refs/tags/v0.4.0",
+                        "repository": "https://github.com/centy-io/centy-daemon",
+                        "path": ".github/workflows/release.yml",
+                    } },
+                },
+                "runDetails": {
+                    "builder": { "id": "https://github.com/actions/runner/github-hosted" },
+                },
+            },
+        });
+        let bundle: Value = serde_json::from_str(&envelope(&statement)).unwrap();
+        let bundle = json!({ "dsseEnvelope": bundle }).to_string();
+
+        check(&bundle, "https://github.com/actions/runner", REPOSITORY).unwrap_err();
+        check(&bundle, "https://github.com/actions/runner/github-hosted", REPOSITORY).unwrap();
+    }
+
+    #[test]
+    fn finds_provenance_among_other_attestations() {
+        let sbom = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "predicateType": "https://spdx.dev/Document",
+            "subject": [{ "name": "daemon.tar.gz", "digest": { "sha256": SHA256 } }],
+            "predicate": {},
+        });
+        let text = format!("{}\n{}\n", envelope(&sbom), envelope(&provenance_v02(SHA256)));
+        verify_default(&text).unwrap();
+        assert_eq!(
+            verify_default(&envelope(&sbom)).unwrap_err(),
+            "daemon.tar.gz is not a subject of the SLSA provenance"
+        );
+    }
+
+    #[test]
+    fn rejects_other_digest_builder_and_repository() {
+        let text = envelope(&provenance_v02(&"1".repeat(64)));
+        assert_eq!(
+            verify_default(&text).unwrap_err(),
+            "daemon.tar.gz is not a subject of the SLSA provenance"
+        );
+
+        let text = envelope(&provenance_v02(SHA256));
+        let verify_with =
+            |builder: &str, repository: &str| check(&text, builder, repository).unwrap_err();
+        let err = verify_with("https://github.com/acme/builder", REPOSITORY);
+        assert!(err.starts_with("daemon.tar.gz was built by "), "{err}");
+        // A builder whose ID merely starts with the expected one is another builder
+        let err = verify_with(&DEFAULT_BUILDER.replace("slsa3.yml", "slsa"), REPOSITORY);
+        assert!(err.starts_with("daemon.tar.gz was built by "), "{err}");
+
+        let err = verify_with(DEFAULT_BUILDER, "https://github.com/acme/fork");
+        assert_eq!(
+            err,
+            "daemon.tar.gz was built from \
+             git+https://github.com/centy-io/centy-daemon@refs/tags/v0.4.0, \
+             expected https://github.com/acme/fork"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_provenance() {
+        assert!(verify_default("not json").unwrap_err().starts_with("invalid SLSA provenance"));
+        let other = json!({ "payloadType": "text/plain", "payload": "" }).to_string();
+        assert_eq!(
+            verify_default(&other).unwrap_err(),
+            "SLSA provenance is not an in-toto attestation"
+        );
+    }
+
+    #[test]
+    fn repository_name_ignores_ref_and_git_suffix() {
+        assert_eq!(
+            repository_name("git+https://github.com/centy-io/centy-daemon.git@refs/tags/v0.4.0"),
+            REPOSITORY
+        );
+        assert_eq!(repository_name("https://github.com/centy-io/centy-daemon/"), REPOSITORY);
+    }
+}
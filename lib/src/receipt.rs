@@ -0,0 +1,397 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::digest::{Digest, DigestAlgorithm};
+use crate::install;
+
+/// Record of what was installed, from where, and when.
+///
+/// Written to `~/.centy/install.json` for the active version and to
+/// `~/.centy/versions/<tag>/install.json` for every installed version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallReceipt {
+    /// Release tag, e.g. `v0.4.0`.
+    pub version: String,
+    /// Target triple the binary was built for.
+    pub target: String,
+    /// Name of the downloaded release archive.
+    pub asset_name: String,
+    /// SHA-256 of the downloaded archive, hex encoded.
+    pub sha256: String,
+    /// Digest the archive was verified against when it was not SHA-256, as
+    /// `<algorithm>:<hex>`, e.g. `sha512:…`.
+    pub digest: Option<String>,
+    /// URL the archive was downloaded from.
+    pub source_url: String,
+    /// Install time as an RFC 3339 UTC timestamp.
+    pub installed_at: String,
+    /// Version of `centy-installer` that performed the install.
+    pub installer_version: String,
+}
+
+impl InstallReceipt {
+    fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "target": self.target,
+            "asset_name": self.asset_name,
+            "sha256": self.sha256,
+            "digest": self.digest,
+            "source_url": self.source_url,
+            "installed_at": self.installed_at,
+            "installer_version": self.installer_version,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(Value::as_str)
+                .map(String::from)
+                .ok_or_else(|| format!("install receipt is missing \"{name}\""))
+        };
+
+        Ok(Self {
+            version: field("version")?,
+            target: field("target")?,
+            asset_name: field("asset_name")?,
+            sha256: field("sha256")?,
+            digest: value.get("digest").and_then(Value::as_str).map(String::from),
+            source_url: field("source_url")?,
+            installed_at: field("installed_at")?,
+            installer_version: field("installer_version")?,
+        })
+    }
+}
+
+/// Return the path of the receipt for the active version.
+pub fn receipt_path_in(home_dir: &Path) -> PathBuf {
+    home_dir.join(".centy").join("install.json")
+}
+
+fn version_receipt_path_in(home_dir: &Path, tag: &str) -> Result<PathBuf, String> {
+    let binary_path = install::version_binary_path_in(home_dir, tag)?;
+    let version_dir = binary_path
+        .parent()
+        .ok_or("version binary path has no parent directory")?;
+    Ok(version_dir.join("install.json"))
+}
+
+/// Write the receipt for a freshly installed, now active, version.
+pub fn write_receipt_in(home_dir: &Path, receipt: &InstallReceipt) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(&receipt.to_json())
+        .map_err(|e| format!("failed to serialize install receipt: {e}"))?;
+
+    for path in [
+        version_receipt_path_in(home_dir, &receipt.version)?,
+        receipt_path_in(home_dir),
+    ] {
+        fs::write(&path, &text)
+            .map_err(|e| format!("failed to write install receipt {}: {e}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Read the receipt for the active version, or `None` if there is none.
+pub fn read_receipt_in(home_dir: &Path) -> Result<Option<InstallReceipt>, String> {
+    read_receipt_at(&receipt_path_in(home_dir))
+}
+
+/// Check whether version `tag` is installed from the given asset and checksum.
+///
+/// Both the stored binary and its receipt must be present; versions without a
+/// receipt are never considered a match, so they are downloaded again. A
+/// digest other than SHA-256 only matches receipts that recorded it.
+pub fn matches_installed_in(
+    home_dir: &Path,
+    tag: &str,
+    asset_name: &str,
+    expected: &Digest,
+) -> bool {
+    let binary_installed =
+        install::version_binary_path_in(home_dir, tag).is_ok_and(|path| path.is_file());
+
+    binary_installed
+        && version_receipt_path_in(home_dir, tag)
+            .and_then(|path| read_receipt_at(&path))
+            .ok()
+            .flatten()
+            .is_some_and(|receipt| {
+                let same_archive = match expected.algorithm {
+                    DigestAlgorithm::Sha256 => receipt.sha256.eq_ignore_ascii_case(&expected.hex),
+                    _ => receipt.digest.as_deref().is_some_and(|digest| {
+                        digest.eq_ignore_ascii_case(&expected.to_string())
+                    }),
+                };
+                receipt.asset_name == asset_name && same_archive
+            })
+}
+
+fn read_receipt_at(path: &Path) -> Result<Option<InstallReceipt>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+    };
+
+    let value: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("failed to parse install receipt {}: {e}", path.display()))?;
+
+    InstallReceipt::from_json(&value).map(Some)
+}
+
+/// Make the receipt of version `tag` the active receipt after switching versions.
+///
+/// Versions installed before receipts existed have none, in which case the
+/// active receipt is removed rather than left describing another version.
+pub fn activate_receipt_in(home_dir: &Path, tag: &str) -> Result<(), String> {
+    let version_receipt = version_receipt_path_in(home_dir, tag)?;
+    let active_receipt = receipt_path_in(home_dir);
+
+    if version_receipt.is_file() {
+        fs::copy(&version_receipt, &active_receipt)
+            .map(|_| ())
+            .map_err(|e| {
+                format!("failed to write install receipt {}: {e}", active_receipt.display())
+            })
+    } else {
+        remove_receipt_in(home_dir)
+    }
+}
+
+/// Remove the active receipt, if any.
+pub fn remove_receipt_in(home_dir: &Path) -> Result<(), String> {
+    let path = receipt_path_in(home_dir);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("failed to remove {}: {e}", path.display())),
+    }
+}
+
+/// Return the current time as an RFC 3339 UTC timestamp.
+pub fn now_rfc3339() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    format_rfc3339(secs)
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn format_rfc3339(secs: u64) -> String {
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Convert days since 1970-01-01 into a (year, month, day) civil date.
+///
+/// Howard Hinnant's `civil_from_days` algorithm, restricted to dates after the epoch.
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    fn sample(tag: &str) -> InstallReceipt {
+        InstallReceipt {
+            version: tag.to_string(),
+            target: "x86_64-unknown-linux-gnu".to_string(),
+            asset_name: format!("centy-daemon-{tag}-x86_64-unknown-linux-gnu.tar.gz"),
+            sha256: "abc123".to_string(),
+            digest: None,
+            source_url: "https://example.com/asset.tar.gz".to_string(),
+            installed_at: "2026-01-02T03:04:05Z".to_string(),
+            installer_version: "0.1.0".to_string(),
+        }
+    }
+
+    fn sha256(hex: &str) -> Digest {
+        Digest {
+            algorithm: DigestAlgorithm::Sha256,
+            hex: hex.to_string(),
+        }
+    }
+
+    fn install_version(home: &Path, tag: &str) {
+        install::install_binary_to(&b"binary"[..], tag, home)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    #[test]
+    fn write_and_read_receipt_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+
+        write_receipt_in(tmp.path(), &sample("v1.0.0")).unwrap();
+
+        let receipt = read_receipt_in(tmp.path()).unwrap();
+        assert_eq!(receipt, Some(sample("v1.0.0")));
+    }
+
+    #[test]
+    fn read_receipt_missing_returns_none() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(read_receipt_in(tmp.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn read_receipt_invalid_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join(".centy")).unwrap();
+        fs::write(receipt_path_in(tmp.path()), "not-json").unwrap();
+
+        let result = read_receipt_in(tmp.path());
+        assert!(result.unwrap_err().contains("failed to parse install receipt"));
+    }
+
+    #[test]
+    fn read_receipt_missing_field() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join(".centy")).unwrap();
+        fs::write(receipt_path_in(tmp.path()), r#"{"version": "v1.0.0"}"#).unwrap();
+
+        let result = read_receipt_in(tmp.path());
+        assert!(result.unwrap_err().contains("missing \"target\""));
+    }
+
+    #[test]
+    fn activate_receipt_switches_to_version_receipt() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+        write_receipt_in(tmp.path(), &sample("v1.0.0")).unwrap();
+        install_version(tmp.path(), "v2.0.0");
+        write_receipt_in(tmp.path(), &sample("v2.0.0")).unwrap();
+
+        activate_receipt_in(tmp.path(), "v1.0.0").unwrap();
+
+        let receipt = read_receipt_in(tmp.path()).unwrap().unwrap();
+        assert_eq!(receipt.version, "v1.0.0");
+    }
+
+    #[test]
+    fn activate_receipt_without_version_receipt_removes_active() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+        install_version(tmp.path(), "v2.0.0");
+        write_receipt_in(tmp.path(), &sample("v2.0.0")).unwrap();
+
+        activate_receipt_in(tmp.path(), "v1.0.0").unwrap();
+
+        assert_eq!(read_receipt_in(tmp.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn matches_installed_same_release() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+        let receipt = sample("v1.0.0");
+        write_receipt_in(tmp.path(), &receipt).unwrap();
+
+        assert!(matches_installed_in(
+            tmp.path(),
+            "v1.0.0",
+            &receipt.asset_name,
+            &sha256("ABC123")
+        ));
+    }
+
+    #[test]
+    fn matches_installed_rejects_different_checksum() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+        let receipt = sample("v1.0.0");
+        write_receipt_in(tmp.path(), &receipt).unwrap();
+
+        assert!(!matches_installed_in(
+            tmp.path(),
+            "v1.0.0",
+            &receipt.asset_name,
+            &sha256("def456")
+        ));
+    }
+
+    #[test]
+    fn matches_installed_requires_binary_and_receipt() {
+        let tmp = tempfile::tempdir().unwrap();
+        let receipt = sample("v1.0.0");
+        assert!(!matches_installed_in(
+            tmp.path(),
+            "v1.0.0",
+            &receipt.asset_name,
+            &sha256(&receipt.sha256)
+        ));
+
+        install_version(tmp.path(), "v1.0.0");
+        assert!(!matches_installed_in(
+            tmp.path(),
+            "v1.0.0",
+            &receipt.asset_name,
+            &sha256(&receipt.sha256)
+        ));
+    }
+
+    #[test]
+    fn matches_installed_compares_recorded_digest() {
+        let tmp = tempfile::tempdir().unwrap();
+        install_version(tmp.path(), "v1.0.0");
+        let sha512 = Digest {
+            algorithm: DigestAlgorithm::Sha512,
+            hex: "ab".repeat(64),
+        };
+        let mut receipt = sample("v1.0.0");
+        write_receipt_in(tmp.path(), &receipt).unwrap();
+        assert!(!matches_installed_in(tmp.path(), "v1.0.0", &receipt.asset_name, &sha512));
+
+        receipt.digest = Some(sha512.to_string());
+        write_receipt_in(tmp.path(), &receipt).unwrap();
+        assert_eq!(read_receipt_in(tmp.path()).unwrap(), Some(receipt.clone()));
+        assert!(matches_installed_in(tmp.path(), "v1.0.0", &receipt.asset_name, &sha512));
+        let blake3 = Digest {
+            algorithm: DigestAlgorithm::Blake3,
+            ..sha512
+        };
+        assert!(!matches_installed_in(tmp.path(), "v1.0.0", &receipt.asset_name, &blake3));
+    }
+
+    #[test]
+    fn format_rfc3339_epoch() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn format_rfc3339_known_dates() {
+        assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(1_767_323_045), "2026-01-02T03:04:05Z");
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::Response;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+
+use crate::progress::{NoProgress, ProgressObserver, RetryNotice};
+
+/// How failed network requests are retried.
+///
+/// Connection errors, timeouts, `408`, `429` and `5xx` responses are retried
+/// with jittered exponential backoff. A `Retry-After` header replaces the
+/// computed delay. Other failures, such as `404` or a checksum mismatch, are
+/// reported straight away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry. It doubles with every further retry.
+    pub initial_delay: Duration,
+    /// Longest delay between two attempts. A `Retry-After` asking for a
+    /// longer wait fails the request instead.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt.
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 0): half of the
+    /// exponential delay, plus a random share of the other half so that
+    /// clients failing together do not retry together.
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1_u32.checked_shl(retry).unwrap_or(u32::MAX);
+        let delay = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+        let half = delay / 2;
+        half + jitter(delay.saturating_sub(half))
+    }
+}
+
+/// A [`RetryPolicy`] together with the observer told about each retry.
+#[derive(Clone)]
+pub struct Retrier {
+    pub policy: RetryPolicy,
+    pub observer: Arc<dyn ProgressObserver>,
+}
+
+impl Retrier {
+    pub fn new(policy: RetryPolicy, observer: Arc<dyn ProgressObserver>) -> Self {
+        Self { policy, observer }
+    }
+
+    /// Retry as `policy` says without reporting the retries.
+    pub fn quiet(policy: RetryPolicy) -> Self {
+        Self::new(policy, Arc::new(NoProgress))
+    }
+}
+
+/// A random duration between zero and `max`.
+fn jitter(max: Duration) -> Duration {
+    let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+    // Every `RandomState` is freshly keyed, which is random enough for jitter
+    let random = RandomState::new().hash_one(());
+    Duration::from_nanos(max_nanos.checked_add(1).map_or(random, |range| random % range))
+}
+
+/// Why an attempt failed, and whether trying again may help.
+#[derive(Debug)]
+pub enum Failure {
+    /// A network hiccup or overloaded server; worth retrying, after
+    /// `retry_after` if the server asked for a delay.
+    Transient {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// Retrying would fail the same way.
+    Permanent(String),
+}
+
+impl Failure {
+    /// A transient failure without a server-requested delay.
+    pub const fn transient(message: String) -> Self {
+        Self::Transient {
+            message,
+            retry_after: None,
+        }
+    }
+
+    /// The error message, whatever the kind of failure.
+    pub fn message(self) -> String {
+        match self {
+            Self::Transient { message, .. } | Self::Permanent(message) => message,
+        }
+    }
+
+    /// Classify a failed request: anything but an invalid request is treated
+    /// as a network problem and retried.
+    pub fn request(error: &reqwest::Error, message: String) -> Self {
+        if error.is_builder() || error.is_redirect() {
+            Self::Permanent(message)
+        } else {
+            Self::transient(message)
+        }
+    }
+
+    /// Classify an unsuccessful response by its status code.
+    pub fn status(response: &Response, message: String) -> Self {
+        let status = response.status();
+        if status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT
+        {
+            Self::Transient {
+                message,
+                retry_after: retry_after(response),
+            }
+        } else {
+            Self::Permanent(message)
+        }
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds.
+///
+/// The HTTP-date form is not supported and falls back to the backoff delay.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Run `attempt` until it succeeds, fails permanently, or the policy of
+/// `retrier` gives up. Each retry is reported to its observer.
+pub fn retry<T>(
+    retrier: &Retrier,
+    mut attempt: impl FnMut() -> Result<T, Failure>,
+) -> Result<T, String> {
+    let policy = &retrier.policy;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let (message, retry_after) = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(Failure::Permanent(message)) => return Err(message),
+            Err(Failure::Transient {
+                message,
+                retry_after,
+            }) => (message, retry_after),
+        };
+
+        if attempts >= policy.max_attempts {
+            return Err(if attempts > 1 {
+                format!("{message} (gave up after {attempts} attempts)")
+            } else {
+                message
+            });
+        }
+
+        let delay = retry_after.unwrap_or_else(|| policy.backoff(attempts - 1));
+        if delay > policy.max_delay {
+            return Err(format!(
+                "{message} (server asked to retry after {}s)",
+                delay.as_secs()
+            ));
+        }
+
+        retrier.observer.on_retry(&RetryNotice {
+            message,
+            delay,
+            attempt: attempts + 1,
+            max_attempts: policy.max_attempts,
+        });
+        thread::sleep(delay);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::significant_drop_tightening,
+    reason = "tests use unwrap/expect/panic and short-lived locks for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+    use reqwest::blocking::Client;
+
+    fn fast_policy(max_attempts: u32) -> Retrier {
+        Retrier::quiet(RetryPolicy {
+            max_attempts,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::from_secs(1),
+        })
+    }
+
+    fn fetch(server: &mockito::Server) -> Result<Response, Failure> {
+        let response = Client::new()
+            .get(format!("{}/file", server.url()))
+            .send()
+            .map_err(|e| Failure::request(&e, e.to_string()))?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            let message = format!("server returned {}", response.status());
+            Err(Failure::status(&response, message))
+        }
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+
+        for _ in 0..20 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            assert!(policy.backoff(31) <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn retries_transient_failures_until_success() {
+        let mut calls = 0;
+        let result = retry(&fast_policy(3), || {
+            calls += 1;
+            if calls < 3 {
+                Err(Failure::transient("connection reset".to_string()))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn retries_are_reported_to_the_observer() {
+        struct Notices(std::sync::Mutex<Vec<RetryNotice>>);
+
+        impl ProgressObserver for Notices {
+            fn on_progress(&self, _progress: crate::progress::Progress) {}
+
+            fn on_retry(&self, notice: &RetryNotice) {
+                self.0.lock().unwrap().push(notice.clone());
+            }
+        }
+
+        let notices = Arc::new(Notices(std::sync::Mutex::new(Vec::new())));
+        let retrier = Retrier::new(fast_policy(3).policy, notices.clone());
+        let mut calls = 0;
+        let result = retry(&retrier, || {
+            calls += 1;
+            if calls < 3 {
+                Err(Failure::transient(format!("connection reset {calls}")))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+
+        let notices = notices.0.lock().unwrap();
+        let reported: Vec<_> = notices
+            .iter()
+            .map(|notice| (notice.message.as_str(), notice.attempt, notice.max_attempts))
+            .collect();
+        assert_eq!(reported, [("connection reset 1", 2, 3), ("connection reset 2", 3, 3)]);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let mut calls = 0;
+        let result: Result<(), String> = retry(&fast_policy(3), || {
+            calls += 1;
+            Err(Failure::transient("connection reset".to_string()))
+        });
+        assert_eq!(calls, 3);
+        assert_eq!(
+            result.unwrap_err(),
+            "connection reset (gave up after 3 attempts)"
+        );
+    }
+
+    #[test]
+    fn permanent_failure_is_not_retried() {
+        let mut calls = 0;
+        let result: Result<(), String> = retry(&fast_policy(3), || {
+            calls += 1;
+            Err(Failure::Permanent("checksum mismatch".to_string()))
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(result.unwrap_err(), "checksum mismatch");
+    }
+
+    #[test]
+    fn retry_after_beyond_max_delay_fails() {
+        let result: Result<(), String> = retry(&fast_policy(3), || {
+            Err(Failure::Transient {
+                message: "server returned 429".to_string(),
+                retry_after: Some(Duration::from_secs(45)),
+            })
+        });
+        assert!(result.unwrap_err().contains("retry after 45s"));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let mut server = mockito::Server::new();
+        let unavailable = server
+            .mock("GET", "/file")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let ok = server.mock("GET", "/file").with_status(200).create();
+
+        let result = retry(&fast_policy(2), || fetch(&server));
+        assert!(result.is_ok());
+        unavailable.assert();
+        ok.assert();
+    }
+
+    #[test]
+    fn retry_after_header_is_parsed() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/file")
+            .with_status(429)
+            .with_header("retry-after", "7")
+            .create();
+
+        let Err(Failure::Transient { retry_after, .. }) = fetch(&server) else {
+            panic!("429 should be transient");
+        };
+        assert_eq!(retry_after, Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn not_found_is_permanent() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("GET", "/file").with_status(404).create();
+
+        let result = retry(&fast_policy(3), || fetch(&server));
+        assert_eq!(result.unwrap_err(), "server returned 404 Not Found");
+        mock.assert();
+    }
+}
@@ -0,0 +1,134 @@
+use minisign_verify::{PublicKey, Signature};
+
+/// Release signing keys embedded in the installer.
+const RELEASE_KEYS: &str = include_str!("../release-keys.txt");
+
+/// Extension of a detached minisign signature, appended to the signed file name.
+pub const SIGNATURE_EXT: &str = ".minisig";
+
+/// Return the minisign public keys embedded in the installer.
+///
+/// Several keys may be trusted at once so that the signing key can be
+/// rotated without breaking installers already in use.
+pub fn release_keys() -> Vec<String> {
+    RELEASE_KEYS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Check that at least one key is trusted to sign releases.
+pub fn require_keys(keys: &[String]) -> Result<(), String> {
+    if keys.is_empty() {
+        return Err("no release signing key is trusted; list one in \"trusted_keys\", or set \
+                    \"verify_signatures\" to false to install unsigned releases"
+            .to_string());
+    }
+    Ok(())
+}
+
+/// Check that `key` is a base64-encoded minisign public key.
+pub fn check_key(key: &str) -> Result<(), String> {
+    PublicKey::from_base64(key.trim())
+        .map(drop)
+        .map_err(|e| format!("invalid minisign public key \"{key}\": {e}"))
+}
+
+/// Verify the detached minisign `signature` of `data`, a file named `name`,
+/// against the trusted `keys`.
+///
+/// Only prehashed signatures, the default of minisign since 0.8, are
+/// accepted; legacy signatures over the raw data are rejected.
+pub fn verify(data: &[u8], signature: &str, keys: &[String], name: &str) -> Result<(), String> {
+    let signature = Signature::decode(signature)
+        .map_err(|e| format!("invalid signature of {name}: {e}"))?;
+
+    for key in keys {
+        let key = PublicKey::from_base64(key.trim())
+            .map_err(|e| format!("invalid minisign public key \"{key}\": {e}"))?;
+        match key.verify(data, &signature, false) {
+            Ok(()) => return Ok(()),
+            // Signed by another key; try the next trusted one
+            Err(minisign_verify::Error::UnexpectedKeyId) => {}
+            Err(e) => return Err(format!("{name} does not match its signature: {e}")),
+        }
+    }
+    Err(format!("{name} is not signed by a trusted key"))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+pub mod tests {
+    use super::*;
+
+    /// Test keys, with their signatures of [`CHECKSUMS`] below.
+    pub const KEY: &str = "RWTv2fozqof3i1NbkAThq0REJoFvXovHluqmgmFEb4XHgmL6d2fcC/bI";
+    pub const OTHER_KEY: &str = "RWT0EMC8zc796hFnUIGxj6uDmsUXZyHGRj9dJm8OgBO6441OC5ZQGatw";
+
+    pub const CHECKSUMS: &str =
+        "abc123  centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz\n";
+
+    pub const SIGNATURE: &str = "\
+untrusted comment: signature from minisign secret key
+RUTv2fozqof3izKkfmSvVMLApRrbGNyJeF2RLMFO/8DXVm16Se/UDmp0k6qpGzE5qRbB/sdWjEU9qFhiELo6B55nI/t1bdPhcgs=
+trusted comment: timestamp:1735689600\tfile:checksums-sha256.txt
+p3VyksPsPxuLIYklA38A6w3iVoD44MbiFr3ucKVmzdQuRmxqNCd9O5450hQRhIwpwMak7kqHT9RxGSTxiIAeAA==
+";
+
+    pub const OTHER_SIGNATURE: &str = "\
+untrusted comment: signature from minisign secret key
+RUT0EMC8zc796hqySM5HGO5CfrI//IlTOOoGmEk3grmQGBUNJX6ETGlEGKaUCFvfx37kBpMmnGcB4KPr9Sazt3F2FDPVcwIDJwM=
+trusted comment: timestamp:1735689600\tfile:checksums-sha256.txt
+9avgU3lNDLUlNpI0T3htl7BS4zeoFu8Cd3s+s5My4K2+d8hADj1IvVCIJoLPYHyeCLxqCYiA3kIAM67J1HkeDg==
+";
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn verifies_signature_of_any_trusted_key() {
+        let data = CHECKSUMS.as_bytes();
+        verify(data, SIGNATURE, &keys(&[KEY]), "checksums").unwrap();
+        verify(data, OTHER_SIGNATURE, &keys(&[KEY, OTHER_KEY]), "checksums").unwrap();
+    }
+
+    #[test]
+    fn rejects_untrusted_key() {
+        let err = verify(CHECKSUMS.as_bytes(), OTHER_SIGNATURE, &keys(&[KEY]), "checksums")
+            .unwrap_err();
+        assert_eq!(err, "checksums is not signed by a trusted key");
+    }
+
+    #[test]
+    fn rejects_tampered_data() {
+        let tampered = CHECKSUMS.replace("abc123", "def456");
+        let err = verify(tampered.as_bytes(), SIGNATURE, &keys(&[KEY]), "checksums")
+            .unwrap_err();
+        assert!(err.starts_with("checksums does not match its signature"), "{err}");
+    }
+
+    #[test]
+    fn rejects_malformed_signature_and_key() {
+        let err = verify(CHECKSUMS.as_bytes(), "not a signature", &keys(&[KEY]), "checksums")
+            .unwrap_err();
+        assert!(err.starts_with("invalid signature of checksums"), "{err}");
+
+        assert!(check_key(KEY).is_ok());
+        assert!(check_key("RWQ-not-base64").is_err());
+    }
+
+    #[test]
+    fn embedded_keys_are_valid() {
+        for key in release_keys() {
+            check_key(&key).unwrap();
+        }
+    }
+}
@@ -0,0 +1,763 @@
+use std::fmt;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use rustls_pki_types::{CertificateDer, TrustAnchor, UnixTime};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Sigstore trust root embedded in the installer.
+const TRUSTED_ROOT: &str = include_str!("../sigstore-trusted-root.json");
+
+/// Extension of a Sigstore bundle, appended to the name of the signed asset.
+pub const BUNDLE_EXT: &str = ".sigstore.json";
+
+/// Workflows of the centy-daemon repository, which release signing
+/// certificates are issued to unless another identity is configured.
+pub const RELEASE_WORKFLOWS: &str = "https://github.com/centy-io/centy-daemon/.github/workflows/";
+
+/// OIDC issuer of the identities of GitHub Actions workflows.
+pub const GITHUB_ACTIONS_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// Extended key usage extension (`2.5.29.37`), which signing certificates
+/// must carry with the code signing purpose (`id-kp-codeSigning`).
+const EXTENDED_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+const CODE_SIGNING: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x03];
+
+/// Fulcio certificate extensions naming the OIDC issuer: the deprecated raw
+/// string (`1.3.6.1.4.1.57264.1.1`) and its DER-encoded successor (`.1.8`).
+const ISSUER_V1: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x01];
+const ISSUER_V2: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x08];
+
+/// Return the Sigstore trust root embedded in the installer, in the format of
+/// Sigstore's `trusted_root.json`.
+pub fn embedded_root() -> String {
+    TRUSTED_ROOT.to_string()
+}
+
+/// Certificate authorities and transparency logs trusted to vouch for
+/// Sigstore bundles.
+#[derive(Debug, Default)]
+pub struct TrustedRoot {
+    authorities: Vec<Authority>,
+    logs: Vec<Log>,
+}
+
+/// A Fulcio certificate authority: its root and intermediate certificates.
+#[derive(Debug)]
+struct Authority {
+    anchor: TrustAnchor<'static>,
+    intermediates: Vec<CertificateDer<'static>>,
+}
+
+/// A Rekor transparency log, identified by the SHA-256 hash of its key.
+#[derive(Debug)]
+struct Log {
+    id: Vec<u8>,
+    key: VerifyingKey,
+}
+
+impl TrustedRoot {
+    /// Parse a trust root in the format of Sigstore's `trusted_root.json`.
+    ///
+    /// Only the certificate authorities and transparency logs are read. Logs
+    /// whose key is not ECDSA P-256, the key type of Rekor, are skipped.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let invalid = |e: String| format!("invalid Sigstore trusted root: {e}");
+        let value: Value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+
+        let authorities = array(&value, "/certificateAuthorities")
+            .iter()
+            .map(|authority| {
+                let certificates = array(authority, "/certChain/certificates")
+                    .iter()
+                    .map(|certificate| decode(certificate, "/rawBytes"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (root, intermediates) = certificates
+                    .split_last()
+                    .ok_or("certificate authority without certificates")?;
+                let root = CertificateDer::from(root.as_slice());
+                let anchor = webpki::anchor_from_trusted_cert(&root)
+                    .map_err(|e| format!("invalid root certificate: {e}"))?
+                    .to_owned();
+                let intermediates = intermediates
+                    .iter()
+                    .map(|certificate| CertificateDer::from(certificate.clone()))
+                    .collect();
+                Ok(Authority {
+                    anchor,
+                    intermediates,
+                })
+            })
+            .collect::<Result<_, String>>()
+            .map_err(invalid)?;
+
+        let logs = array(&value, "/tlogs")
+            .iter()
+            .map(|log| decode(log, "/publicKey/rawBytes"))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?
+            .into_iter()
+            .filter_map(|key| {
+                let id = Sha256::digest(&key).to_vec();
+                VerifyingKey::from_public_key_der(&key)
+                    .ok()
+                    .map(|key| Log { id, key })
+            })
+            .collect();
+
+        Ok(Self { authorities, logs })
+    }
+
+    /// Whether no certificate authority is trusted, so that no Sigstore
+    /// bundle can be verified.
+    pub const fn is_empty(&self) -> bool {
+        self.authorities.is_empty()
+    }
+}
+
+/// A GitHub Actions workflow a signing certificate may be issued to.
+#[derive(Debug, Clone, Copy)]
+pub enum Identity<'a> {
+    /// Any workflow under a URI prefix, running for a release tag.
+    Release {
+        /// Prefix of the workflow URI, such as
+        /// `https://github.com/centy-io/centy-daemon/.github/workflows/`.
+        workflow: &'a str,
+        /// Release tag the workflow must have run for.
+        tag: &'a str,
+    },
+    /// The reusable workflow with this URI, at any ref, such as the
+    /// generator of slsa-github-generator.
+    Builder(&'a str),
+}
+
+impl Identity<'_> {
+    /// Whether the certificate subject `uri` is this identity.
+    fn matches(&self, uri: &str) -> bool {
+        match *self {
+            Self::Release { workflow, tag } => uri
+                .strip_prefix(workflow)
+                .and_then(|rest| rest.strip_suffix(&format!("@refs/tags/{tag}")))
+                .is_some_and(|file| !file.is_empty() && !file.contains(['/', '@'])),
+            Self::Builder(builder) => uri
+                .strip_prefix(builder)
+                .is_some_and(|version| version.starts_with('@')),
+        }
+    }
+}
+
+impl fmt::Display for Identity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Release { workflow, tag } => write!(f, "a workflow under {workflow} for {tag}"),
+            Self::Builder(builder) => write!(f, "{builder}"),
+        }
+    }
+}
+
+/// What a bundle signs, as recorded in its transparency log entry.
+#[derive(Clone, Copy)]
+enum Signed<'a> {
+    /// The SHA-256 digest of a file, logged as a `hashedrekord` entry.
+    Digest(&'a [u8]),
+    /// The payload of a DSSE envelope, logged as a `dsse` or `intoto` entry.
+    Envelope(&'a [u8]),
+}
+
+/// Verify the Sigstore `bundle` of an asset named `name`, whose SHA-256
+/// digest is `sha256` (hex), without any network access.
+///
+/// The bundle must sign `sha256` with a certificate that chains to a
+/// certificate authority of `root` at the time the signature was logged, and
+/// that GitHub Actions issued to a workflow of `identity` running for its
+/// tag. Its transparency log entry must match the signature and be proven
+/// included in a checkpoint signed by a log of `root`.
+pub fn verify(
+    bundle: &str,
+    sha256: &str,
+    root: &TrustedRoot,
+    identity: Identity<'_>,
+    name: &str,
+) -> Result<(), String> {
+    let bundle: Value = serde_json::from_str(bundle)
+        .map_err(|e| format!("invalid Sigstore bundle for {name}: {e}"))?;
+    let digest =
+        hex::decode(sha256).map_err(|e| format!("invalid SHA-256 digest of {name}: {e}"))?;
+    check_bundle(&bundle, &digest, root, identity).map_err(|e| format!("{name}: {e}"))
+}
+
+/// Verify `bundle`, a Sigstore bundle of a DSSE envelope, without any network
+/// access, and return the payload type and payload of the envelope.
+///
+/// The envelope must be signed with a certificate that chains to a
+/// certificate authority of `root` at the time the signature was logged, and
+/// that GitHub Actions issued to one of `identities`. Its transparency log
+/// entry must record the same signature and payload and be proven included in
+/// a checkpoint signed by a log of `root`.
+pub fn verify_envelope<'b>(
+    bundle: &'b Value,
+    root: &TrustedRoot,
+    identities: &[Identity<'_>],
+) -> Result<(&'b str, Vec<u8>), String> {
+    let certificate = signing_certificate(bundle)?;
+    let envelope = bundle
+        .get("dsseEnvelope")
+        .ok_or("the Sigstore bundle holds no DSSE envelope")?;
+    let payload_type = text(envelope, "/payloadType").ok_or("missing /payloadType")?;
+    let payload = decode(envelope, "/payload")?;
+    let [signature] = array(envelope, "/signatures") else {
+        return Err("the DSSE envelope must carry exactly one signature".to_string());
+    };
+    let signature = decode(signature, "/sig")?;
+
+    let time = check_log_entry(bundle, root, &certificate, &signature, Signed::Envelope(&payload))?;
+    let key = check_certificate(&certificate, root, time, identities)?;
+    verify_signature(&key, &pae(payload_type, &payload), &signature)
+        .map_err(|e| format!("does not match its DSSE signature: {e}"))?;
+    Ok((payload_type, payload))
+}
+
+/// Encode the payload of a DSSE envelope for signing, as DSSE's
+/// pre-authentication encoding.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message =
+        format!("DSSEv1 {} {payload_type} {} ", payload_type.len(), payload.len()).into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+fn check_bundle(
+    bundle: &Value,
+    digest: &[u8],
+    root: &TrustedRoot,
+    identity: Identity<'_>,
+) -> Result<(), String> {
+    let certificate = signing_certificate(bundle)?;
+    if bundle.get("messageSignature").is_none() {
+        return Err("only Sigstore bundles of message signatures are supported".to_string());
+    }
+    let signed_digest = decode(bundle, "/messageSignature/messageDigest/digest")?;
+    if text(bundle, "/messageSignature/messageDigest/algorithm") != Some("SHA2_256")
+        || signed_digest != digest
+    {
+        return Err("the Sigstore bundle signs a different file".to_string());
+    }
+    let signature = decode(bundle, "/messageSignature/signature")?;
+
+    let time = check_log_entry(bundle, root, &certificate, &signature, Signed::Digest(digest))?;
+    let key = check_certificate(&certificate, root, time, &[identity])?;
+    Signature::from_der(&signature)
+        .and_then(|signature| key.verify_prehash(digest, &signature))
+        .map_err(|e| format!("does not match its Sigstore signature: {e}"))
+}
+
+/// Return the DER signing certificate of `bundle`.
+fn signing_certificate(bundle: &Value) -> Result<Vec<u8>, String> {
+    decode(bundle, "/verificationMaterial/certificate/rawBytes").or_else(|_| {
+        decode(bundle, "/verificationMaterial/x509CertificateChain/certificates/0/rawBytes")
+    })
+}
+
+/// Check that the signing `certificate` chains to a trusted certificate
+/// authority at `time` and was issued to one of `identities`, and return its
+/// public key.
+fn check_certificate(
+    certificate: &[u8],
+    root: &TrustedRoot,
+    time: u64,
+    identities: &[Identity<'_>],
+) -> Result<VerifyingKey, String> {
+    let encoded = CertificateDer::from(certificate);
+    let cert = webpki::EndEntityCert::try_from(&encoded)
+        .map_err(|e| format!("invalid signing certificate: {e}"))?;
+    let algorithms = [
+        webpki::ring::ECDSA_P256_SHA256,
+        webpki::ring::ECDSA_P256_SHA384,
+        webpki::ring::ECDSA_P384_SHA256,
+        webpki::ring::ECDSA_P384_SHA384,
+    ];
+    let time = UnixTime::since_unix_epoch(Duration::from_secs(time));
+    let mut error = "no Sigstore certificate authority is trusted".to_string();
+    let trusted = root.authorities.iter().any(|authority| {
+        cert.verify_for_usage(
+            &algorithms,
+            std::slice::from_ref(&authority.anchor),
+            &authority.intermediates,
+            time,
+            // Certificate authorities need not restrict their key usage
+            webpki::KeyUsage::required_if_present(CODE_SIGNING),
+            None,
+            None,
+        )
+        .map_err(|e| error = e.to_string())
+        .is_ok()
+    });
+    if !trusted {
+        return Err(format!("signing certificate is not trusted: {error}"));
+    }
+    if extension(certificate, EXTENDED_KEY_USAGE).is_none() {
+        return Err("signing certificate is not issued for code signing".to_string());
+    }
+
+    let uris: Vec<_> = cert.valid_uri_names().collect();
+    let issued_to_workflow = uris
+        .iter()
+        .any(|uri| identities.iter().any(|identity| identity.matches(uri)));
+    if !issued_to_workflow {
+        let expected: Vec<_> = identities.iter().map(ToString::to_string).collect();
+        return Err(format!(
+            "signed by {}, not by {}",
+            if uris.is_empty() { "an unknown identity".to_string() } else { uris.join(", ") },
+            expected.join(" or ")
+        ));
+    }
+    let issuer = extension(certificate, ISSUER_V2)
+        .and_then(|value| der(value).filter(|(tag, _, _)| *tag == 0x0c))
+        .map(|(_, issuer, _)| issuer)
+        .or_else(|| extension(certificate, ISSUER_V1));
+    if issuer != Some(GITHUB_ACTIONS_ISSUER.as_bytes()) {
+        return Err(format!(
+            "signing certificate was not issued by {GITHUB_ACTIONS_ISSUER}"
+        ));
+    }
+
+    tbs(certificate)
+        .and_then(|mut fields| fields.nth(5))
+        .ok_or_else(|| "signing certificate has no public key".to_string())
+        .and_then(|(_, _, key)| {
+            VerifyingKey::from_public_key_der(key)
+                .map_err(|e| format!("unsupported signing key, expected ECDSA P-256: {e}"))
+        })
+}
+
+/// Check the transparency log entry of the bundle against the signing
+/// `certificate`, `signature` and what it `signed`, and return the time it
+/// was logged.
+fn check_log_entry(
+    bundle: &Value,
+    root: &TrustedRoot,
+    certificate: &[u8],
+    signature: &[u8],
+    signed: Signed<'_>,
+) -> Result<u64, String> {
+    let (entry, log) = array(bundle, "/verificationMaterial/tlogEntries")
+        .iter()
+        .find_map(|entry| {
+            let id = decode(entry, "/logId/keyId").ok()?;
+            root.logs.iter().find(|log| log.id == id).map(|log| (entry, log))
+        })
+        .ok_or("the signature is not recorded in a trusted transparency log")?;
+
+    let body = decode(entry, "/canonicalizedBody")?;
+    check_log_body(&body, certificate, signature, signed)?;
+
+    // The signed entry timestamp vouches for the time the entry was logged
+    let time = integer(entry, "/integratedTime")?;
+    let promise = format!(
+        r#"{{"body":"{}","integratedTime":{time},"logID":"{}","logIndex":{}}}"#,
+        STANDARD.encode(&body),
+        hex::encode(&log.id),
+        integer(entry, "/logIndex")?
+    );
+    let timestamp = decode(entry, "/inclusionPromise/signedEntryTimestamp")?;
+    verify_signature(&log.key, promise.as_bytes(), &timestamp)
+        .map_err(|e| format!("invalid signed entry timestamp: {e}"))?;
+
+    check_inclusion(entry, log, &body)?;
+    Ok(time)
+}
+
+/// Check that a log entry `body` records what was `signed`, with
+/// `signature` by `certificate`: a `hashedrekord` entry for a digest, a
+/// `dsse` or `intoto` entry for a DSSE envelope.
+fn check_log_body(
+    body: &[u8],
+    certificate: &[u8],
+    signature: &[u8],
+    signed: Signed<'_>,
+) -> Result<(), String> {
+    let body: Value = serde_json::from_slice(body)
+        .map_err(|e| format!("invalid transparency log entry: {e}"))?;
+    let logged_certificate =
+        |value: &Value, pointer| decode(value, pointer).ok().and_then(|pem| pem_to_der(&pem));
+    let matches = match (text(&body, "/kind"), signed) {
+        (Some("hashedrekord"), Signed::Digest(digest)) => {
+            text(&body, "/spec/data/hash/algorithm") == Some("sha256")
+                && logged_hash(&body, "/spec/data/hash/value").as_deref() == Some(digest)
+                && decode(&body, "/spec/signature/content").ok().as_deref() == Some(signature)
+                && logged_certificate(&body, "/spec/signature/publicKey/content").as_deref()
+                    == Some(certificate)
+        }
+        (Some("dsse"), Signed::Envelope(payload)) => {
+            logs_payload(&body, "/spec/payloadHash", payload)
+                && array(&body, "/spec/signatures").iter().any(|logged| {
+                    decode(logged, "/signature").ok().as_deref() == Some(signature)
+                        && logged_certificate(logged, "/verifier").as_deref() == Some(certificate)
+                })
+        }
+        (Some("intoto"), Signed::Envelope(payload)) => {
+            logs_payload(&body, "/spec/content/payloadHash", payload)
+                && array(&body, "/spec/content/envelope/signatures").iter().any(|logged| {
+                    // Rekor stores the base64 signature of the envelope base64-encoded again
+                    let sig = decode(logged, "/sig").ok();
+                    let sig = sig.and_then(|sig| STANDARD.decode(&sig).ok().or(Some(sig)));
+                    sig.as_deref() == Some(signature)
+                        && logged_certificate(logged, "/publicKey").as_deref() == Some(certificate)
+                })
+        }
+        (_, Signed::Digest(_)) => {
+            return Err("unsupported transparency log entry, expected hashedrekord".to_string());
+        }
+        (_, Signed::Envelope(_)) => {
+            return Err("unsupported transparency log entry, expected dsse or intoto".to_string());
+        }
+    };
+    if !matches {
+        return Err("the transparency log entry does not match the Sigstore bundle".to_string());
+    }
+    Ok(())
+}
+
+/// Decode the hex hash at `pointer` of a log entry.
+fn logged_hash(body: &Value, pointer: &str) -> Option<Vec<u8>> {
+    text(body, pointer).and_then(|hash| hex::decode(hash).ok())
+}
+
+/// Whether the SHA-256 payload hash at `pointer` of a log entry is that of
+/// `payload`.
+fn logs_payload(body: &Value, pointer: &str, payload: &[u8]) -> bool {
+    body.pointer(pointer).is_some_and(|hash| {
+        text(hash, "/algorithm") == Some("sha256")
+            && logged_hash(hash, "/value").as_deref() == Some(Sha256::digest(payload).as_slice())
+    })
+}
+
+/// Check the inclusion proof of a log `entry` with `body` and the checkpoint
+/// of the tree it was included in, signed by `log`.
+fn check_inclusion(entry: &Value, log: &Log, body: &[u8]) -> Result<(), String> {
+    let index = integer(entry, "/inclusionProof/logIndex")?;
+    let size = integer(entry, "/inclusionProof/treeSize")?;
+    let root_hash = decode(entry, "/inclusionProof/rootHash")?;
+    let hashes = array(entry, "/inclusionProof/hashes")
+        .iter()
+        .map(|hash| {
+            hash.as_str()
+                .ok_or_else(|| "invalid inclusion proof hash".to_string())
+                .and_then(|hash| {
+                    STANDARD.decode(hash).map_err(|e| format!("invalid inclusion proof hash: {e}"))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let leaf = Sha256::new().chain_update([0]).chain_update(body).finalize().to_vec();
+    if root_from_inclusion_proof(index, size, leaf, &hashes)? != root_hash {
+        return Err("the inclusion proof does not match the log root hash".to_string());
+    }
+
+    let checkpoint = text(entry, "/inclusionProof/checkpoint/envelope")
+        .ok_or("missing transparency log checkpoint")?;
+    check_checkpoint(checkpoint, log, size, &root_hash)
+}
+
+/// Compute the root hash of a tree of `size` leaves from the hash of the
+/// leaf at `index` and its inclusion proof, as specified by RFC 9162.
+fn root_from_inclusion_proof(
+    index: u64,
+    size: u64,
+    leaf: Vec<u8>,
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, String> {
+    if index >= size {
+        return Err(format!("log index {index} is outside a tree of {size} entries"));
+    }
+    let (mut node, mut last) = (index, size - 1);
+    let mut hash = leaf;
+    for sibling in proof {
+        if last == 0 {
+            return Err("the inclusion proof is too long".to_string());
+        }
+        if node & 1 == 1 || node == last {
+            hash = node_hash(sibling, &hash);
+            while node & 1 == 0 && node != 0 {
+                node >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        node >>= 1;
+        last >>= 1;
+    }
+    if last != 0 {
+        return Err("the inclusion proof is too short".to_string());
+    }
+    Ok(hash)
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update([1])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .to_vec()
+}
+
+/// Check that `checkpoint`, a signed note, commits `log` to a tree of `size`
+/// entries with `root_hash`.
+fn check_checkpoint(
+    checkpoint: &str,
+    log: &Log,
+    size: u64,
+    root_hash: &[u8],
+) -> Result<(), String> {
+    let (note, signatures) = checkpoint
+        .split_once("\n\n")
+        .ok_or("malformed transparency log checkpoint")?;
+    let mut lines = note.lines().skip(1);
+    let checkpoint_size = lines.next().and_then(|size| size.parse::<u64>().ok());
+    let checkpoint_hash = lines.next().and_then(|hash| STANDARD.decode(hash).ok());
+    if checkpoint_size != Some(size) || checkpoint_hash.as_deref() != Some(root_hash) {
+        return Err(
+            "the transparency log checkpoint does not match the inclusion proof".to_string()
+        );
+    }
+
+    // Each signature line is `— <name> <base64 of key hint and signature>`,
+    // where the key hint is the start of the log ID
+    let message = format!("{note}\n");
+    let signed = signatures
+        .lines()
+        .filter_map(|line| line.strip_prefix("\u{2014} ")?.split_once(' '))
+        .filter_map(|(_, signature)| STANDARD.decode(signature).ok())
+        .any(|signature| {
+            signature.split_at_checked(4).is_some_and(|(hint, signature)| {
+                log.id.starts_with(hint)
+                    && verify_signature(&log.key, message.as_bytes(), signature).is_ok()
+            })
+        });
+    if !signed {
+        return Err("the transparency log checkpoint is not signed by the log".to_string());
+    }
+    Ok(())
+}
+
+fn verify_signature(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+    Signature::from_der(signature)
+        .and_then(|signature| key.verify(message, &signature))
+        .map_err(|e| e.to_string())
+}
+
+fn text<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(Value::as_str)
+}
+
+fn array<'a>(value: &'a Value, pointer: &str) -> &'a [Value] {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+/// Decode the base64 string at `pointer`.
+fn decode(value: &Value, pointer: &str) -> Result<Vec<u8>, String> {
+    let text = text(value, pointer).ok_or_else(|| format!("missing {pointer}"))?;
+    STANDARD
+        .decode(text)
+        .map_err(|e| format!("invalid base64 in {pointer}: {e}"))
+}
+
+/// Read the integer at `pointer`, which Sigstore encodes as a string.
+fn integer(value: &Value, pointer: &str) -> Result<u64, String> {
+    value
+        .pointer(pointer)
+        .and_then(|value| value.as_u64().or_else(|| value.as_str()?.parse().ok()))
+        .ok_or_else(|| format!("missing or invalid {pointer}"))
+}
+
+/// Decode the DER certificate of a PEM document.
+fn pem_to_der(pem: &[u8]) -> Option<Vec<u8>> {
+    let base64: String = std::str::from_utf8(pem)
+        .ok()?
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    STANDARD.decode(base64).ok()
+}
+
+/// Split the DER element at the start of `input` into its tag and contents,
+/// and the input that follows it.
+fn der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&length, rest) = rest.split_first()?;
+    let (length, rest) = if length < 0x80 {
+        (usize::from(length), rest)
+    } else {
+        let (length, rest) = rest.split_at_checked(usize::from(length & 0x7f))?;
+        if length.is_empty() || length.len() > 4 {
+            return None;
+        }
+        let length = length
+            .iter()
+            .fold(0, |length, &byte| length << 8 | usize::from(byte));
+        (length, rest)
+    };
+    let (contents, rest) = rest.split_at_checked(length)?;
+    Some((tag, contents, rest))
+}
+
+/// Iterate over the DER elements of `input` as their tag, contents and
+/// complete encoding.
+fn elements(mut input: &[u8]) -> impl Iterator<Item = (u8, &[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        let (tag, contents, rest) = der(input)?;
+        let encoded = input.get(..input.len() - rest.len())?;
+        input = rest;
+        Some((tag, contents, encoded))
+    })
+}
+
+/// Iterate over the fields of a certificate's `TBSCertificate` after its
+/// version: serial number, signature algorithm, issuer, validity, subject,
+/// public key and extensions.
+fn tbs(certificate: &[u8]) -> Option<impl Iterator<Item = (u8, &[u8], &[u8])>> {
+    let (_, certificate, _) = der(certificate)?;
+    let (_, tbs, _) = der(certificate)?;
+    Some(elements(tbs).filter(|(tag, _, _)| *tag != 0xa0))
+}
+
+/// Return the value of the certificate extension `oid`.
+fn extension<'a>(certificate: &'a [u8], oid: &[u8]) -> Option<&'a [u8]> {
+    let (_, extensions, _) = tbs(certificate)?.find(|(tag, _, _)| *tag == 0xa3)?;
+    let (_, extensions, _) = der(extensions)?;
+    elements(extensions).find_map(|(_, extension, _)| {
+        let mut fields = elements(extension);
+        let (_, id, _) = fields.next()?;
+        if id != oid {
+            return None;
+        }
+        fields.last().map(|(_, value, _)| value)
+    })
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+pub mod tests {
+    use super::*;
+
+    /// Fixtures written by `tests/fixtures/sigstore/generate.py`: a trust
+    /// root and the bundle of an archive with [`SHA256`], signed by the
+    /// release workflow for v0.4.0.
+    pub const ROOT: &str = include_str!("../tests/fixtures/sigstore/trusted_root.json");
+    pub const BUNDLE: &str = include_str!(
+        "../tests/fixtures/sigstore/centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz.sigstore.json"
+    );
+    pub const SHA256: &str = "c40ca88d580de8fa32db7d997b37f971a9a0a050d1b79a12aaf409a2191a32ca";
+    pub const WORKFLOW: &str = "https://github.com/centy-io/centy-daemon/.github/workflows/";
+
+    const IDENTITY: Identity<'static> = Identity::Release {
+        workflow: WORKFLOW,
+        tag: "v0.4.0",
+    };
+
+    fn verify_bundle(bundle: &str, sha256: &str, identity: Identity<'_>) -> Result<(), String> {
+        verify(bundle, sha256, &TrustedRoot::parse(ROOT).unwrap(), identity, "daemon.tar.gz")
+    }
+
+    /// The fixture bundle with the value at `pointer` replaced by `change`.
+    fn tampered(pointer: &str, change: impl FnOnce(&Value) -> Value) -> String {
+        let mut bundle: Value = serde_json::from_str(BUNDLE).unwrap();
+        let value = bundle.pointer_mut(pointer).unwrap();
+        *value = change(value);
+        bundle.to_string()
+    }
+
+    #[test]
+    fn verifies_bundle_offline() {
+        verify_bundle(BUNDLE, SHA256, IDENTITY).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_file() {
+        let other = "0".repeat(64);
+        let err = verify_bundle(BUNDLE, &other, IDENTITY).unwrap_err();
+        assert_eq!(err, "daemon.tar.gz: the Sigstore bundle signs a different file");
+    }
+
+    #[test]
+    fn rejects_other_identity() {
+        let other_tag = Identity::Release {
+            workflow: WORKFLOW,
+            tag: "v0.4.1",
+        };
+        let err = verify_bundle(BUNDLE, SHA256, other_tag).unwrap_err();
+        assert!(err.contains("not by a workflow under"), "{err}");
+
+        let other_repo = Identity::Release {
+            workflow: "https://github.com/someone/fork/.github/workflows/",
+            tag: "v0.4.0",
+        };
+        let err = verify_bundle(BUNDLE, SHA256, other_repo).unwrap_err();
+        assert!(err.contains("release.yml@refs/tags/v0.4.0"), "{err}");
+    }
+
+    #[test]
+    fn rejects_untrusted_authority_and_log() {
+        let root = TrustedRoot::parse(&embedded_root()).unwrap();
+        let err = verify(BUNDLE, SHA256, &root, IDENTITY, "daemon.tar.gz").unwrap_err();
+        assert!(err.contains("not recorded in a trusted transparency log"), "{err}");
+
+        let mut logs_only: Value = serde_json::from_str(ROOT).unwrap();
+        *logs_only.pointer_mut("/certificateAuthorities").unwrap() = Value::Array(Vec::new());
+        let root = TrustedRoot::parse(&logs_only.to_string()).unwrap();
+        let err = verify(BUNDLE, SHA256, &root, IDENTITY, "daemon.tar.gz").unwrap_err();
+        assert!(err.contains("signing certificate is not trusted"), "{err}");
+    }
+
+    #[test]
+    fn rejects_tampered_log_entry() {
+        const ENTRY: &str = "/verificationMaterial/tlogEntries/0";
+
+        let proof = tampered(&format!("{ENTRY}/inclusionProof/hashes/0"), |_| {
+            STANDARD.encode([0; 32]).into()
+        });
+        let err = verify_bundle(&proof, SHA256, IDENTITY).unwrap_err();
+        assert!(err.contains("inclusion proof does not match"), "{err}");
+
+        let time = tampered(&format!("{ENTRY}/integratedTime"), |_| "1735700000".into());
+        let err = verify_bundle(&time, SHA256, IDENTITY).unwrap_err();
+        assert!(err.contains("invalid signed entry timestamp"), "{err}");
+
+        let checkpoint = tampered(&format!("{ENTRY}/inclusionProof/checkpoint/envelope"), |note| {
+            note.as_str().unwrap().replacen("rekor.test - 1", "rekor.test - 2", 1).into()
+        });
+        let err = verify_bundle(&checkpoint, SHA256, IDENTITY).unwrap_err();
+        assert!(err.contains("checkpoint is not signed by the log"), "{err}");
+    }
+
+    #[test]
+    fn inclusion_proof_bounds() {
+        let leaf = vec![7; 32];
+        assert_eq!(root_from_inclusion_proof(0, 1, leaf.clone(), &[]).unwrap(), leaf);
+        let sibling = vec![vec![8; 32]];
+        assert!(root_from_inclusion_proof(0, 1, leaf.clone(), &sibling).is_err());
+        assert!(root_from_inclusion_proof(0, 2, leaf.clone(), &[]).is_err());
+        assert!(root_from_inclusion_proof(2, 2, leaf, &sibling).is_err());
+    }
+
+    #[test]
+    fn embedded_root_is_valid() {
+        TrustedRoot::parse(&embedded_root()).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use reqwest::blocking::Client;
+
+use crate::config::SourceConfig;
+use crate::digest::Digest;
+use crate::platform::Platform;
+use crate::retry::Retrier;
+use crate::{github, manifest};
+
+/// Error from looking up releases in a [`ReleaseSource`].
+#[derive(Debug)]
+pub enum ApiError {
+    /// The API rate limit is exhausted. The message says when it resets.
+    RateLimited(String),
+    /// A signed release document does not match a trusted key.
+    Signature(String),
+    /// The API answered `404 Not Found`.
+    NotFound(String),
+    Other(String),
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited(message)
+            | Self::Signature(message)
+            | Self::NotFound(message)
+            | Self::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VersionInfo {
+    pub tag: String,
+    pub notice: Option<String>,
+}
+
+/// Notice shown when a stable release was resolved but pre-release `tag`
+/// is newer.
+pub fn prerelease_notice(tag: &str) -> String {
+    format!("Note: Pre-release {tag} is available. Use --pre to install it.")
+}
+
+/// How the expected hash of a release asset is obtained.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    /// URL of a checksums file, such as `checksums-sha256.txt`, listing the
+    /// asset.
+    File(String),
+    /// Digest of the asset, published alongside its URL.
+    Digest(Digest),
+}
+
+#[derive(Debug)]
+pub struct ReleaseInfo {
+    pub tag: String,
+    pub asset_url: String,
+    pub asset_name: String,
+    /// Size of the asset in bytes, if published.
+    pub size: Option<u64>,
+    pub checksum: Checksum,
+    /// URL of the SLSA provenance attesting the release assets, if published.
+    pub provenance: Option<String>,
+}
+
+/// A release listed by the release source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRelease {
+    pub tag: String,
+    pub prerelease: bool,
+    /// An unpublished draft, only listed to users with push access.
+    pub draft: bool,
+    /// When the release was published (RFC 3339), if known.
+    pub published_at: Option<String>,
+    /// Release notes, usually Markdown, if any were published.
+    pub notes: Option<String>,
+    pub assets: Vec<RemoteAsset>,
+}
+
+/// A downloadable file attached to a [`RemoteRelease`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteAsset {
+    pub name: String,
+    pub url: String,
+    /// Size in bytes, if known.
+    pub size: Option<u64>,
+    /// Digest published for the asset, such as `sha256:<hex>`.
+    pub digest: Option<String>,
+}
+
+/// Where releases are looked up.
+pub trait ReleaseSource {
+    /// Resolve `version`, or the latest release when it is `None`, to a
+    /// release tag. `prerelease` allows the latest release to be a
+    /// pre-release.
+    fn resolve_version(
+        &self,
+        version: Option<&str>,
+        prerelease: bool,
+    ) -> Result<VersionInfo, ApiError>;
+
+    /// Describe the asset of release `tag` built for `platform`.
+    fn release_info(&self, tag: &str, platform: &Platform) -> Result<ReleaseInfo, ApiError>;
+
+    /// List every release, newest first.
+    fn list_releases(&self) -> Result<Vec<RemoteRelease>, ApiError>;
+}
+
+/// Releases published on GitHub, or a GitHub Enterprise instance.
+pub struct GitHubSource {
+    pub client: Client,
+    pub retry: Retrier,
+    pub token: Option<String>,
+    pub config: SourceConfig,
+}
+
+impl ReleaseSource for GitHubSource {
+    fn resolve_version(
+        &self,
+        version: Option<&str>,
+        prerelease: bool,
+    ) -> Result<VersionInfo, ApiError> {
+        github::resolve_version_from(
+            &self.client,
+            &self.retry,
+            self.token.as_deref(),
+            version,
+            prerelease,
+            &self.config,
+        )
+    }
+
+    fn release_info(&self, tag: &str, platform: &Platform) -> Result<ReleaseInfo, ApiError> {
+        github::release_info(
+            &self.client,
+            &self.retry,
+            self.token.as_deref(),
+            tag,
+            platform,
+            &self.config,
+        )
+    }
+
+    fn list_releases(&self) -> Result<Vec<RemoteRelease>, ApiError> {
+        github::list_releases_from(
+            &self.client,
+            &self.retry,
+            self.token.as_deref(),
+            &self.config,
+        )
+    }
+}
+
+/// Build the release source selected by `config`: its static manifest when
+/// one is configured, the GitHub API otherwise.
+pub fn from_config(
+    client: &Client,
+    retry: &Retrier,
+    token: Option<String>,
+    config: &SourceConfig,
+) -> Box<dyn ReleaseSource> {
+    match &config.manifest {
+        Some(url) => Box::new(manifest::ManifestSource::new(
+            client.clone(),
+            retry.clone(),
+            url.clone(),
+            config.signing_keys().map(<[String]>::to_vec),
+        )),
+        None => Box::new(GitHubSource {
+            client: client.clone(),
+            retry: retry.clone(),
+            token,
+            config: config.clone(),
+        }),
+    }
+}
@@ -0,0 +1,260 @@
+use semver::{Version, VersionReq};
+
+use crate::github::normalize_tag;
+
+/// A version given by the user: an exact release tag or a semver range.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Requested {
+    /// A full version such as `0.4.2`, or any tag that is not a range.
+    Tag(String),
+    /// A range such as `^0.4`, `~0.4.2`, `>=0.3,<0.5` or `0.4`.
+    Range(VersionReq),
+}
+
+/// Parse a user-supplied version.
+///
+/// Full versions keep pinning that exact release. Anything else that parses
+/// as a semver requirement is a range; a bare `0.4` means `^0.4`.
+pub fn parse_requested(version: &str) -> Requested {
+    let version = version.trim();
+    let bare = version.strip_prefix('v').unwrap_or(version);
+    if Version::parse(bare).is_ok() {
+        return Requested::Tag(normalize_tag(bare));
+    }
+    VersionReq::parse(bare).map_or_else(
+        |_| Requested::Tag(normalize_tag(version)),
+        Requested::Range,
+    )
+}
+
+/// Parse a release tag such as `v0.4.2` as a semver version.
+pub fn parse_tag(tag: &str) -> Option<Version> {
+    Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Return the highest of `releases` matching `range`.
+///
+/// Each release is a tag together with whether its source flags it as a
+/// pre-release. A release is a pre-release when it is flagged or its tag has
+/// a semver pre-release part. Pre-releases are only considered when
+/// `prerelease` is set, in which case `0.4.3-rc.1` matches any range `0.4.3`
+/// would match. Tags that are not semver versions are ignored.
+pub fn highest_matching<'a>(
+    range: &VersionReq,
+    releases: impl IntoIterator<Item = (&'a str, bool)>,
+    prerelease: bool,
+) -> Option<&'a str> {
+    releases
+        .into_iter()
+        .filter_map(|(tag, flagged)| parse_tag(tag).map(|version| (version, tag, flagged)))
+        .filter(|(version, _, flagged)| {
+            if !flagged && version.pre.is_empty() {
+                return range.matches(version);
+            }
+            let release = Version::new(version.major, version.minor, version.patch);
+            prerelease && (range.matches(version) || range.matches(&release))
+        })
+        .max_by(|(a, _, _), (b, _, _)| a.cmp(b))
+        .map(|(_, tag, _)| tag)
+}
+
+/// Return the highest of `prereleases` that is strictly newer than the
+/// stable release `stable`. Tags that are not semver versions are ignored.
+pub fn newer_prerelease<'a>(
+    stable: &str,
+    prereleases: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let stable = parse_tag(stable)?;
+    prereleases
+        .into_iter()
+        .filter_map(|tag| parse_tag(tag).map(|version| (version, tag)))
+        .filter(|(version, _)| *version > stable)
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// Resolve `range`, which the user wrote as `requested`, to the highest
+/// matching release. `releases` are as for [`highest_matching`].
+pub fn resolve_range<'a>(
+    requested: &str,
+    range: &VersionReq,
+    releases: impl IntoIterator<Item = (&'a str, bool)> + Clone,
+    prerelease: bool,
+) -> Result<String, String> {
+    if let Some(tag) = highest_matching(range, releases.clone(), prerelease) {
+        return Ok(tag.to_string());
+    }
+    let hint = if !prerelease && highest_matching(range, releases, true).is_some() {
+        " (only pre-releases match; use --pre to allow them)"
+    } else {
+        ""
+    };
+    Err(format!("no release matches \"{requested}\"{hint}"))
+}
+
+/// Check that the pinned release `tag` is one of `tags`.
+///
+/// A missing tag is reported together with the closest published versions.
+pub fn find_tag<'a>(
+    tag: &str,
+    tags: impl IntoIterator<Item = &'a str> + Clone,
+) -> Result<String, String> {
+    if tags.clone().into_iter().any(|t| t == tag) {
+        return Ok(tag.to_string());
+    }
+    let closest = closest_tags(tag, tags, 3);
+    if closest.is_empty() {
+        return Err(format!("release {tag} not found; no releases are published"));
+    }
+    Err(format!(
+        "release {tag} not found; closest versions: {}",
+        closest.join(", ")
+    ))
+}
+
+/// Return up to `count` of `tags` closest to `tag`, nearest first.
+///
+/// Versions are compared by major, then minor, then patch distance, newer
+/// releases winning ties. When `tag` is not a semver version, the first
+/// `count` tags are returned.
+pub fn closest_tags<'a>(
+    tag: &str,
+    tags: impl IntoIterator<Item = &'a str>,
+    count: usize,
+) -> Vec<&'a str> {
+    let Some(wanted) = parse_tag(tag) else {
+        return tags.into_iter().take(count).collect();
+    };
+    let mut versions: Vec<_> = tags
+        .into_iter()
+        .filter_map(|t| parse_tag(t).map(|version| (version, t)))
+        .collect();
+    versions.sort_by(|(a, _), (b, _)| {
+        let distance = |v: &Version| {
+            (
+                v.major.abs_diff(wanted.major),
+                v.minor.abs_diff(wanted.minor),
+                v.patch.abs_diff(wanted.patch),
+            )
+        };
+        distance(a).cmp(&distance(b)).then_with(|| b.cmp(a))
+    });
+    versions.into_iter().take(count).map(|(_, t)| t).collect()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    reason = "tests use unwrap/expect/panic for brevity; only production code must stay panic-free"
+)]
+mod tests {
+    use super::*;
+
+    const TAGS: [&str; 8] = [
+        "v0.5.0-alpha.1",
+        "v0.4.3-rc.1",
+        "v0.4.2",
+        "v0.4.1",
+        "v0.4.0",
+        "v0.3.9",
+        "nightly",
+        "v0.2.0",
+    ];
+
+    /// `TAGS` as releases that no source flags as pre-releases.
+    fn unflagged() -> impl Iterator<Item = (&'static str, bool)> + Clone {
+        TAGS.into_iter().map(|tag| (tag, false))
+    }
+
+    fn range(req: &str) -> VersionReq {
+        match parse_requested(req) {
+            Requested::Range(range) => range,
+            Requested::Tag(tag) => panic!("{req} parsed as tag {tag}"),
+        }
+    }
+
+    #[test]
+    fn full_versions_are_exact_tags() {
+        assert_eq!(parse_requested("0.4.2"), Requested::Tag("v0.4.2".to_string()));
+        assert_eq!(parse_requested("v0.4.2"), Requested::Tag("v0.4.2".to_string()));
+        assert_eq!(
+            parse_requested("0.5.0-alpha.1"),
+            Requested::Tag("v0.5.0-alpha.1".to_string())
+        );
+        assert_eq!(parse_requested("nightly"), Requested::Tag("vnightly".to_string()));
+    }
+
+    #[test]
+    fn ranges_select_highest_match() {
+        let highest = |req: &str| highest_matching(&range(req), unflagged(), false);
+        assert_eq!(highest("^0.4"), Some("v0.4.2"));
+        assert_eq!(highest("0.4"), Some("v0.4.2"));
+        assert_eq!(highest("v0.4"), Some("v0.4.2"));
+        assert_eq!(highest("~0.4.1"), Some("v0.4.2"));
+        assert_eq!(highest(">=0.3,<0.4.1"), Some("v0.4.0"));
+        assert_eq!(highest(">=0.3, <0.5"), Some("v0.4.2"));
+        assert_eq!(highest("*"), Some("v0.4.2"));
+        assert_eq!(highest("^1"), None);
+    }
+
+    #[test]
+    fn prereleases_need_the_flag() {
+        assert_eq!(highest_matching(&range("^0.4"), unflagged(), true), Some("v0.4.3-rc.1"));
+        assert_eq!(highest_matching(&range(">=0.4"), unflagged(), true), Some("v0.5.0-alpha.1"));
+        assert_eq!(highest_matching(&range("~0.3"), unflagged(), true), Some("v0.3.9"));
+    }
+
+    #[test]
+    fn flagged_prereleases_need_the_flag() {
+        let releases = [("v0.4.3", true), ("v0.4.2", false), ("v0.5.0", true)];
+        assert_eq!(highest_matching(&range("^0.4"), releases, false), Some("v0.4.2"));
+        assert_eq!(highest_matching(&range("^0.4"), releases, true), Some("v0.4.3"));
+        assert_eq!(highest_matching(&range("*"), releases, true), Some("v0.5.0"));
+
+        let err = resolve_range("0.5", &range("0.5"), releases, false).unwrap_err();
+        assert!(err.contains("use --pre"));
+    }
+
+    #[test]
+    fn resolve_range_explains_missing_match() {
+        let err = resolve_range("^1", &range("^1"), unflagged(), false).unwrap_err();
+        assert_eq!(err, "no release matches \"^1\"");
+
+        let err = resolve_range("0.5", &range("0.5"), unflagged(), false).unwrap_err();
+        assert!(err.contains("use --pre"));
+        assert_eq!(
+            resolve_range("0.5", &range("0.5"), unflagged(), true).unwrap(),
+            "v0.5.0-alpha.1"
+        );
+    }
+
+    #[test]
+    fn find_tag_suggests_closest_versions() {
+        assert_eq!(find_tag("v0.4.1", TAGS).unwrap(), "v0.4.1");
+        assert_eq!(find_tag("nightly", TAGS).unwrap(), "nightly");
+
+        let err = find_tag("v0.4.5", TAGS).unwrap_err();
+        assert_eq!(
+            err,
+            "release v0.4.5 not found; closest versions: v0.4.3-rc.1, v0.4.2, v0.4.1"
+        );
+        let err = find_tag("v99.99.99", TAGS).unwrap_err();
+        assert!(err.ends_with("closest versions: v0.5.0-alpha.1, v0.4.3-rc.1, v0.4.2"));
+        let err = find_tag("vlatest", TAGS).unwrap_err();
+        assert!(err.ends_with("closest versions: v0.5.0-alpha.1, v0.4.3-rc.1, v0.4.2"));
+
+        let err = find_tag("v1.0.0", []).unwrap_err();
+        assert_eq!(err, "release v1.0.0 not found; no releases are published");
+    }
+
+    #[test]
+    fn newer_prerelease_compares_as_semver() {
+        let pre = ["v0.4.0-rc.1", "v0.5.0-alpha.1", "v0.5.0-alpha.2", "nightly"];
+        assert_eq!(newer_prerelease("v0.4.0", pre), Some("v0.5.0-alpha.2"));
+        assert_eq!(newer_prerelease("v0.5.0", pre), None);
+        assert_eq!(newer_prerelease("v0.4.0", ["v0.4.0-rc.1", "v0.3.0-beta.1"]), None);
+        assert_eq!(newer_prerelease("stable", pre), None);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! These tests download real releases from GitHub and verify the full
 //! install flow: version resolution, download, checksum, extract, install.
+//! They also install a fixture archive whose checksums are signed with a test
+//! key, through the signature checks that releases without one skip.
 //!
 //! Run with: `cargo test --test e2e_install -- --test-threads=1`
 
@@ -15,6 +17,8 @@
 use std::fs;
 use std::path::PathBuf;
 
+use centy_installer::{InstallOptions, InstallerError, SourceConfig};
+
 fn binary_path() -> PathBuf {
     let name = if cfg!(target_os = "windows") {
         "centy-daemon.exe"
@@ -28,6 +32,56 @@ fn binary_path() -> PathBuf {
         .join(name)
 }
 
+/// Options installing from github.com without signature checks, as the
+/// releases installed here predate release signing.
+fn unsigned_options() -> InstallOptions {
+    InstallOptions {
+        source: Some(SourceConfig {
+            verify_signatures: false,
+            verify_sigstore: false,
+            ..SourceConfig::default()
+        }),
+        ..InstallOptions::default()
+    }
+}
+
+/// Options trusting only `key` to sign release checksums.
+fn signed_options(key: &str) -> InstallOptions {
+    InstallOptions {
+        source: Some(SourceConfig {
+            trusted_keys: vec![key.to_string()],
+            verify_signatures: true,
+            verify_sigstore: false,
+            ..SourceConfig::default()
+        }),
+        ..InstallOptions::default()
+    }
+}
+
+/// The signed fixture archive for the platform the tests run on, written by
+/// `tests/fixtures/minisign/generate.py` next to its signed checksums.
+fn signed_fixture() -> PathBuf {
+    let (os, ext) = match std::env::consts::OS {
+        "macos" => ("apple-darwin", ".tar.gz"),
+        "windows" => ("pc-windows-msvc", ".zip"),
+        _ => ("unknown-linux-gnu", ".tar.gz"),
+    };
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/minisign")
+        .join(format!(
+            "centy-daemon-v0.4.0-{}-{os}{ext}",
+            std::env::consts::ARCH
+        ))
+}
+
+/// Public key of the test key the fixture checksums are signed with.
+fn fixture_key() -> &'static str {
+    include_str!("fixtures/minisign/key.pub")
+        .lines()
+        .last()
+        .expect("key.pub must hold a key")
+}
+
 fn cleanup() {
     let path = binary_path();
     if path.exists() {
@@ -39,8 +93,8 @@ fn cleanup() {
 fn install_pinned_version() {
     cleanup();
 
-    let path =
-        centy_installer::install(Some("v0.1.6"), false, false).expect("install v0.1.6 should succeed");
+    let path = centy_installer::install_with(Some("v0.1.6"), &unsigned_options())
+        .expect("install v0.1.6 should succeed");
 
     assert!(path.exists(), "binary should exist at {}", path.display());
 
@@ -63,7 +117,8 @@ fn install_pinned_version() {
 fn install_latest_version() {
     cleanup();
 
-    let path = centy_installer::install(None, false, false).expect("install latest should succeed");
+    let path = centy_installer::install_with(None, &unsigned_options())
+        .expect("install latest should succeed");
 
     assert!(path.exists(), "binary should exist at {}", path.display());
 }
@@ -72,7 +127,7 @@ fn install_latest_version() {
 fn install_version_without_v_prefix() {
     cleanup();
 
-    let path = centy_installer::install(Some("0.1.6"), false, false)
+    let path = centy_installer::install_with(Some("0.1.6"), &unsigned_options())
         .expect("install without v prefix should succeed");
 
     assert!(path.exists(), "binary should exist at {}", path.display());
@@ -82,10 +137,44 @@ fn install_version_without_v_prefix() {
 fn install_nonexistent_version_fails() {
     cleanup();
 
-    let result = centy_installer::install(Some("v99.99.99"), false, false);
+    let result = centy_installer::install_with(Some("v99.99.99"), &unsigned_options());
 
-    assert!(
-        result.is_err(),
-        "install of nonexistent version should fail"
+    match result {
+        Err(InstallerError::VersionResolution(message)) => {
+            assert!(message.contains("v99.99.99"), "error should name the version: {message}");
+        }
+        other => panic!("install of nonexistent version should fail to resolve: {other:?}"),
+    }
+}
+
+#[test]
+fn install_signed_archive() {
+    cleanup();
+
+    let archive = signed_fixture();
+    let path =
+        centy_installer::install_from_file(&archive, None, None, &signed_options(fixture_key()))
+            .expect("install of an archive with signed checksums should succeed");
+
+    assert!(path.exists(), "binary should exist at {}", path.display());
+}
+
+#[test]
+fn install_archive_signed_by_untrusted_key_fails() {
+    cleanup();
+
+    let other_key = "RWT0EMC8zc796hFnUIGxj6uDmsUXZyHGRj9dJm8OgBO6441OC5ZQGatw";
+    let result = centy_installer::install_from_file(
+        &signed_fixture(),
+        None,
+        None,
+        &signed_options(other_key),
     );
+
+    match result {
+        Err(InstallerError::Signature(message)) => {
+            assert!(message.contains("not signed by a trusted key"), "{message}");
+        }
+        other => panic!("install signed by an untrusted key should fail: {other:?}"),
+    }
 }
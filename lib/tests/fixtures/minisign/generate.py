@@ -0,0 +1,106 @@
+#!/usr/bin/env python3
+"""Regenerate the signed release fixtures used by `tests/e2e_install.rs`.
+
+Writes a v0.4.0 release archive for every supported target, each holding a
+stand-in `centy-daemon` binary, the `checksums-sha256.txt` listing them, and
+its detached minisign signature made with a test key derived from a fixed
+seed. The public key is written to `key.pub`. Archives carry fixed
+timestamps and Ed25519 signatures are deterministic, so the output is the
+same on every run. Requires the `cryptography` package.
+
+    python3 tests/fixtures/minisign/generate.py
+"""
+
+import base64
+import gzip
+import hashlib
+import io
+import pathlib
+import tarfile
+import zipfile
+
+from cryptography.hazmat.primitives import serialization
+from cryptography.hazmat.primitives.asymmetric import ed25519
+
+HERE = pathlib.Path(__file__).parent
+VERSION = "v0.4.0"
+BINARY = b"#!/bin/sh\necho 'centy-daemon v0.4.0 test fixture'\n"
+TARGETS = [
+    ("aarch64-apple-darwin", ".tar.gz"),
+    ("x86_64-apple-darwin", ".tar.gz"),
+    ("aarch64-unknown-linux-gnu", ".tar.gz"),
+    ("x86_64-unknown-linux-gnu", ".tar.gz"),
+    ("x86_64-pc-windows-msvc", ".zip"),
+]
+CHECKSUMS = "checksums-sha256.txt"
+KEY_ID = bytes.fromhex("0123456789abcdef")
+SECRET = ed25519.Ed25519PrivateKey.from_private_bytes(
+    hashlib.sha256(b"centy-installer e2e minisign key").digest()
+)
+# 2025-01-01T00:00:00Z
+TIMESTAMP = 1735689600
+
+
+def tar_gz(name, data):
+    raw = io.BytesIO()
+    with tarfile.open(fileobj=raw, mode="w", format=tarfile.USTAR_FORMAT) as archive:
+        info = tarfile.TarInfo(name)
+        info.size = len(data)
+        info.mode = 0o755
+        info.mtime = TIMESTAMP
+        archive.addfile(info, io.BytesIO(data))
+    out = io.BytesIO()
+    with gzip.GzipFile(fileobj=out, mode="wb", mtime=TIMESTAMP) as gz:
+        gz.write(raw.getvalue())
+    return out.getvalue()
+
+
+def zip_file(name, data):
+    out = io.BytesIO()
+    with zipfile.ZipFile(out, "w", zipfile.ZIP_DEFLATED) as archive:
+        info = zipfile.ZipInfo(name, date_time=(2025, 1, 1, 0, 0, 0))
+        info.compress_type = zipfile.ZIP_DEFLATED
+        archive.writestr(info, data)
+    return out.getvalue()
+
+
+def minisign(data, file_name):
+    """Sign data as `minisign -S` does, prehashed with BLAKE2b-512."""
+    prehash = hashlib.blake2b(data, digest_size=64).digest()
+    signature = SECRET.sign(prehash)
+    trusted = f"timestamp:{TIMESTAMP}\tfile:{file_name}\thashed"
+    global_signature = SECRET.sign(signature + trusted.encode())
+    return (
+        "untrusted comment: signature from minisign secret key\n"
+        f"{base64.b64encode(b'ED' + KEY_ID + signature).decode()}\n"
+        f"trusted comment: {trusted}\n"
+        f"{base64.b64encode(global_signature).decode()}\n"
+    )
+
+
+def main():
+    lines = []
+    for target, ext in TARGETS:
+        name = f"centy-daemon-{VERSION}-{target}{ext}"
+        if ext == ".zip":
+            data = zip_file("centy-daemon.exe", BINARY)
+        else:
+            data = tar_gz("centy-daemon", BINARY)
+        (HERE / name).write_bytes(data)
+        lines.append(f"{hashlib.sha256(data).hexdigest()}  {name}\n")
+
+    checksums = "".join(lines).encode()
+    (HERE / CHECKSUMS).write_bytes(checksums)
+    (HERE / f"{CHECKSUMS}.minisig").write_text(minisign(checksums, CHECKSUMS))
+
+    public = SECRET.public_key().public_bytes(
+        serialization.Encoding.Raw, serialization.PublicFormat.Raw
+    )
+    key = base64.b64encode(b"Ed" + KEY_ID + public).decode()
+    (HERE / "key.pub").write_text(
+        f"untrusted comment: minisign public key {KEY_ID.hex().upper()}\n{key}\n"
+    )
+
+
+if __name__ == "__main__":
+    main()
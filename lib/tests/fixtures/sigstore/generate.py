@@ -0,0 +1,343 @@
+#!/usr/bin/env python3
+"""Regenerate the Sigstore test fixtures used by `src/sigstore.rs`.
+
+Creates a throwaway Fulcio-like CA (root and intermediate), a Rekor-like
+transparency log key, a signing certificate issued to the centy-daemon
+release workflow, and a bundle for ARTIFACT logged at index 2 of a five-entry
+log. Also writes SLSA provenance of ARTIFACT as a bundle of a DSSE envelope,
+signed with a certificate issued to the slsa-github-generator workflow and
+logged at index 3. Keys are derived from fixed seeds; ECDSA signatures are
+randomized, so the output differs on every run. Requires the `cryptography`
+package.
+
+    python3 tests/fixtures/sigstore/generate.py
+"""
+
+import base64
+import datetime
+import hashlib
+import json
+import pathlib
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.x509.oid import ExtendedKeyUsageOID, NameOID
+
+HERE = pathlib.Path(__file__).parent
+ARTIFACT = b"centy-daemon v0.4.0 test archive\n"
+ASSET = "centy-daemon-v0.4.0-x86_64-unknown-linux-gnu.tar.gz"
+IDENTITY = (
+    "https://github.com/centy-io/centy-daemon/.github/workflows/release.yml"
+    "@refs/tags/v0.4.0"
+)
+BUILDER = (
+    "https://github.com/slsa-framework/slsa-github-generator"
+    "/.github/workflows/generator_generic_slsa3.yml"
+)
+BUILDER_IDENTITY = f"{BUILDER}@refs/tags/v2.0.0"
+IN_TOTO_PAYLOAD = "application/vnd.in-toto+json"
+ISSUER = "https://token.actions.githubusercontent.com"
+INTEGRATED_TIME = 1735689600  # 2025-01-01T00:00:00Z
+ORIGIN = "rekor.test - 1"
+LOG_INDEX = 2
+PROVENANCE_INDEX = 3
+TREE_SIZE = 5
+
+
+def b64(data):
+    return base64.b64encode(data).decode()
+
+
+def key(label, curve):
+    seed = hashlib.sha256(f"centy sigstore test {label}".encode()).digest()
+    return ec.derive_private_key(int.from_bytes(seed, "big"), curve)
+
+
+def spki(private_key):
+    return private_key.public_key().public_bytes(
+        serialization.Encoding.DER, serialization.PublicFormat.SubjectPublicKeyInfo
+    )
+
+
+def name(common_name):
+    return x509.Name(
+        [
+            x509.NameAttribute(NameOID.ORGANIZATION_NAME, "centy test"),
+            x509.NameAttribute(NameOID.COMMON_NAME, common_name),
+        ]
+    )
+
+
+def ca_cert(subject_key, issuer_key, subject, issuer, path_length):
+    return (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer))
+        .public_key(subject_key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(datetime.datetime(2020, 1, 1, tzinfo=datetime.timezone.utc))
+        .not_valid_after(datetime.datetime(2040, 1, 1, tzinfo=datetime.timezone.utc))
+        .add_extension(x509.BasicConstraints(ca=True, path_length=path_length), critical=True)
+        .add_extension(
+            x509.KeyUsage(False, False, False, False, False, True, True, False, False),
+            critical=True,
+        )
+        .sign(issuer_key, hashes.SHA384())
+    )
+
+
+def leaf_cert(signing_key, intermediate_key, identity):
+    issued = datetime.datetime.fromtimestamp(INTEGRATED_TIME - 60, datetime.timezone.utc)
+    issuer_v2 = bytes([0x0C, len(ISSUER)]) + ISSUER.encode()
+    return (
+        x509.CertificateBuilder()
+        .subject_name(x509.Name([]))
+        .issuer_name(name("sigstore-intermediate"))
+        .public_key(signing_key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(issued)
+        .not_valid_after(issued + datetime.timedelta(minutes=10))
+        .add_extension(
+            x509.KeyUsage(True, False, False, False, False, False, False, False, False),
+            critical=True,
+        )
+        .add_extension(x509.ExtendedKeyUsage([ExtendedKeyUsageOID.CODE_SIGNING]), critical=False)
+        .add_extension(
+            x509.SubjectAlternativeName([x509.UniformResourceIdentifier(identity)]),
+            critical=True,
+        )
+        .add_extension(
+            x509.UnrecognizedExtension(
+                x509.ObjectIdentifier("1.3.6.1.4.1.57264.1.1"), ISSUER.encode()
+            ),
+            critical=False,
+        )
+        .add_extension(
+            x509.UnrecognizedExtension(x509.ObjectIdentifier("1.3.6.1.4.1.57264.1.8"), issuer_v2),
+            critical=False,
+        )
+        .sign(intermediate_key, hashes.SHA384())
+    )
+
+
+def leaf_hash(data):
+    return hashlib.sha256(b"\x00" + data).digest()
+
+
+def node_hash(left, right):
+    return hashlib.sha256(b"\x01" + left + right).digest()
+
+
+def split(n):
+    k = 1
+    while k * 2 < n:
+        k *= 2
+    return k
+
+
+def tree_hash(leaves):
+    if len(leaves) == 1:
+        return leaves[0]
+    k = split(len(leaves))
+    return node_hash(tree_hash(leaves[:k]), tree_hash(leaves[k:]))
+
+
+def audit_path(index, leaves):
+    if len(leaves) == 1:
+        return []
+    k = split(len(leaves))
+    if index < k:
+        return audit_path(index, leaves[:k]) + [tree_hash(leaves[k:])]
+    return audit_path(index - k, leaves[k:]) + [tree_hash(leaves[:k])]
+
+
+def canonical(value):
+    return json.dumps(value, separators=(",", ":"), sort_keys=True).encode()
+
+
+def pae(payload_type, payload):
+    """DSSE pre-authentication encoding of an envelope's payload."""
+    return b"DSSEv1 %d %s %d %s" % (
+        len(payload_type),
+        payload_type.encode(),
+        len(payload),
+        payload,
+    )
+
+
+def provenance(digest):
+    """SLSA v0.2 provenance of slsa-github-generator for ARTIFACT."""
+    return canonical(
+        {
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "subject": [{"name": ASSET, "digest": {"sha256": digest.hex()}}],
+            "predicate": {
+                "builder": {"id": BUILDER_IDENTITY},
+                "invocation": {
+                    "configSource": {
+                        "uri": "git+https://github.com/centy-io/centy-daemon@refs/tags/v0.4.0"
+                    }
+                },
+            },
+        }
+    )
+
+
+def log_entry(body, index, kind, path, checkpoint, root_hash, log_id, log_key):
+    promise = canonical(
+        {
+            "body": b64(body),
+            "integratedTime": INTEGRATED_TIME,
+            "logID": log_id.hex(),
+            "logIndex": index,
+        }
+    )
+    set_signature = log_key.sign(promise, ec.ECDSA(hashes.SHA256()))
+    return {
+        "logIndex": str(index),
+        "logId": {"keyId": b64(log_id)},
+        "kindVersion": {"kind": kind, "version": "0.0.1"},
+        "integratedTime": str(INTEGRATED_TIME),
+        "inclusionPromise": {"signedEntryTimestamp": b64(set_signature)},
+        "inclusionProof": {
+            "logIndex": str(index),
+            "rootHash": b64(root_hash),
+            "treeSize": str(TREE_SIZE),
+            "hashes": [b64(h) for h in path],
+            "checkpoint": {"envelope": checkpoint},
+        },
+        "canonicalizedBody": b64(body),
+    }
+
+
+def main():
+    root_key = key("root", ec.SECP384R1())
+    intermediate_key = key("intermediate", ec.SECP384R1())
+    signing_key = key("signing", ec.SECP256R1())
+    builder_key = key("builder", ec.SECP256R1())
+    log_key = key("rekor", ec.SECP256R1())
+
+    root = ca_cert(root_key, root_key, "sigstore", "sigstore", 1)
+    intermediate = ca_cert(
+        intermediate_key, root_key, "sigstore-intermediate", "sigstore", 0
+    )
+    leaf = leaf_cert(signing_key, intermediate_key, IDENTITY)
+    leaf_der = leaf.public_bytes(serialization.Encoding.DER)
+    leaf_pem = leaf.public_bytes(serialization.Encoding.PEM)
+    builder_leaf = leaf_cert(builder_key, intermediate_key, BUILDER_IDENTITY)
+    builder_pem = builder_leaf.public_bytes(serialization.Encoding.PEM)
+
+    digest = hashlib.sha256(ARTIFACT).digest()
+    signature = signing_key.sign(ARTIFACT, ec.ECDSA(hashes.SHA256()))
+    body = canonical(
+        {
+            "apiVersion": "0.0.1",
+            "kind": "hashedrekord",
+            "spec": {
+                "data": {"hash": {"algorithm": "sha256", "value": digest.hex()}},
+                "signature": {"content": b64(signature), "publicKey": {"content": b64(leaf_pem)}},
+            },
+        }
+    )
+
+    payload = provenance(digest)
+    envelope_signature = builder_key.sign(
+        pae(IN_TOTO_PAYLOAD, payload), ec.ECDSA(hashes.SHA256())
+    )
+    envelope = {
+        "payload": b64(payload),
+        "payloadType": IN_TOTO_PAYLOAD,
+        "signatures": [{"sig": b64(envelope_signature)}],
+    }
+    provenance_body = canonical(
+        {
+            "apiVersion": "0.0.1",
+            "kind": "dsse",
+            "spec": {
+                "envelopeHash": {
+                    "algorithm": "sha256",
+                    "value": hashlib.sha256(canonical(envelope)).hexdigest(),
+                },
+                "payloadHash": {
+                    "algorithm": "sha256",
+                    "value": hashlib.sha256(payload).hexdigest(),
+                },
+                "signatures": [
+                    {"signature": b64(envelope_signature), "verifier": b64(builder_pem)}
+                ],
+            },
+        }
+    )
+
+    leaves = [leaf_hash(f"entry {i}".encode()) for i in range(TREE_SIZE)]
+    leaves[LOG_INDEX] = leaf_hash(body)
+    leaves[PROVENANCE_INDEX] = leaf_hash(provenance_body)
+    root_hash = tree_hash(leaves)
+
+    log_id = hashlib.sha256(spki(log_key)).digest()
+    note = f"{ORIGIN}\n{TREE_SIZE}\n{b64(root_hash)}\n"
+    note_signature = log_key.sign(note.encode(), ec.ECDSA(hashes.SHA256()))
+    checkpoint = f"{note}\n— rekor.test {b64(log_id[:4] + note_signature)}\n"
+
+    def entry(body, index, kind):
+        path = audit_path(index, leaves)
+        return log_entry(body, index, kind, path, checkpoint, root_hash, log_id, log_key)
+
+    bundle = {
+        "mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json",
+        "verificationMaterial": {
+            "certificate": {"rawBytes": b64(leaf_der)},
+            "tlogEntries": [entry(body, LOG_INDEX, "hashedrekord")],
+        },
+        "messageSignature": {
+            "messageDigest": {"algorithm": "SHA2_256", "digest": b64(digest)},
+            "signature": b64(signature),
+        },
+    }
+    provenance_bundle = {
+        "mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json",
+        "verificationMaterial": {
+            "certificate": {
+                "rawBytes": b64(builder_leaf.public_bytes(serialization.Encoding.DER))
+            },
+            "tlogEntries": [entry(provenance_body, PROVENANCE_INDEX, "dsse")],
+        },
+        "dsseEnvelope": envelope,
+    }
+
+    trusted_root = {
+        "mediaType": "application/vnd.dev.sigstore.trustedroot+json;version=0.1",
+        "tlogs": [
+            {
+                "baseUrl": "https://rekor.test",
+                "hashAlgorithm": "SHA2_256",
+                "publicKey": {
+                    "rawBytes": b64(spki(log_key)),
+                    "keyDetails": "PKIX_ECDSA_P256_SHA_256",
+                },
+                "logId": {"keyId": b64(log_id)},
+            }
+        ],
+        "certificateAuthorities": [
+            {
+                "uri": "https://fulcio.test",
+                "certChain": {
+                    "certificates": [
+                        {"rawBytes": b64(intermediate.public_bytes(serialization.Encoding.DER))},
+                        {"rawBytes": b64(root.public_bytes(serialization.Encoding.DER))},
+                    ]
+                },
+            }
+        ],
+    }
+
+    (HERE / f"{ASSET}.sigstore.json").write_text(json.dumps(bundle, indent=2) + "\n")
+    (HERE / "trusted_root.json").write_text(json.dumps(trusted_root, indent=2) + "\n")
+    (HERE / "multiple.intoto.jsonl").write_text(json.dumps(provenance_bundle) + "\n")
+    print(f"artifact sha256: {digest.hex()}")
+
+
+if __name__ == "__main__":
+    main()